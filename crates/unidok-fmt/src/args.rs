@@ -0,0 +1,199 @@
+use unidok_repr::ast::blocks::*;
+use unidok_repr::ast::html::{ElemContentAst, HtmlNodeAst};
+use unidok_repr::ast::macros::{BlockMacroContent, MacroArgs, TokenTree, TokenTreeAtom};
+use unidok_repr::ast::segments::{LinkAst, SegmentAst};
+
+use crate::Edit;
+
+/// Normalizes the whitespace in the arguments of all macros in the blocks:
+/// Token trees are separated by a single space, there are no spaces around the
+/// `=` of key-value pairs or inside of parentheses and brackets. Only the
+/// whitespace between tokens is changed, so the tokens themselves, including
+/// quoted strings and braces, are preserved. Arguments spanning multiple lines
+/// are left as is.
+pub(crate) fn format_macro_args(blocks: &[BlockAst], text: &str, edits: &mut Vec<Edit>) {
+    for block in blocks {
+        format_block(block, text, edits);
+    }
+}
+
+fn format_block(block: &BlockAst, text: &str, edits: &mut Vec<Edit>) {
+    match block {
+        BlockAst::Paragraph(p) => format_segments(&p.segments, text, edits),
+        BlockAst::Heading(h) => format_segments(&h.segments, text, edits),
+        BlockAst::Table(t) => {
+            for cell in t.rows.iter().flat_map(|r| &r.cells) {
+                match &cell.content {
+                    CellContentAst::Inline(s) => format_segments(s, text, edits),
+                    CellContentAst::Blocks(b) => format_macro_args(b, text, edits),
+                }
+            }
+        }
+        BlockAst::List(l) => {
+            for item in &l.items {
+                format_macro_args(item, text, edits);
+            }
+        }
+        BlockAst::LabeledList(l) => {
+            for item in &l.items {
+                format_segments(&item.label, text, edits);
+                format_macro_args(&item.content, text, edits);
+            }
+        }
+        BlockAst::Quote(q) => format_macro_args(&q.content, text, edits),
+        BlockAst::BlockMacro(m) => {
+            format_args(m.name.end(), &m.args, text, edits);
+            match &m.content {
+                BlockMacroContent::Prefixed(b) => format_block(b, text, edits),
+                BlockMacroContent::Braces(b) => format_macro_args(b, text, edits),
+                BlockMacroContent::None => {}
+            }
+        }
+        BlockAst::BlockHtml(h) => format_html(h, text, edits),
+        BlockAst::FootnoteDef(f) => format_segments(&f.segments, text, edits),
+        BlockAst::CodeBlock(_)
+        | BlockAst::ThematicBreak(_)
+        | BlockAst::Comment(_)
+        | BlockAst::LinkRefDef(_) => {}
+    }
+}
+
+fn format_segments(segments: &[SegmentAst], text: &str, edits: &mut Vec<Edit>) {
+    for segment in segments {
+        match segment {
+            SegmentAst::Braces(b) => format_segments(&b.segments, text, edits),
+            SegmentAst::Link(LinkAst { text: Some(s), .. }) => format_segments(s, text, edits),
+            SegmentAst::Image(i) => {
+                if let Some(s) = &i.alt {
+                    format_segments(s, text, edits);
+                }
+            }
+            SegmentAst::InlineMacro(m) => {
+                format_args(m.name.end(), &m.args, text, edits);
+                format_segments(std::slice::from_ref(&*m.segment), text, edits);
+            }
+            SegmentAst::InlineHtml(h) => format_html(h, text, edits),
+            SegmentAst::Format(f) => format_segments(&f.segments, text, edits),
+            SegmentAst::Code(c) => format_segments(&c.segments, text, edits),
+            _ => {}
+        }
+    }
+}
+
+fn format_html(node: &HtmlNodeAst, text: &str, edits: &mut Vec<Edit>) {
+    if let HtmlNodeAst::Element(e) = node {
+        match &e.content {
+            Some(ElemContentAst::Blocks(b)) => format_macro_args(b, text, edits),
+            Some(ElemContentAst::Inline(s)) => format_segments(s, text, edits),
+            Some(ElemContentAst::Verbatim(_)) | None => {}
+        }
+    }
+}
+
+/// Formats the arguments of a macro. `start` is the position after the macro
+/// name, where the `(` is.
+fn format_args(start: usize, args: &Option<MacroArgs>, text: &str, edits: &mut Vec<Edit>) {
+    let trees = match args {
+        Some(MacroArgs::TokenTrees(trees)) => trees,
+        _ => return,
+    };
+    let mut args = ArgsFormatter { text, pos: start + 1, edits: Vec::new() };
+    let end = match args.trees(trees).and_then(|_| args.expect(")", "")) {
+        Some(()) => args.pos,
+        None => return,
+    };
+    if !text[start..end].contains(['\n', '\r']) {
+        edits.append(&mut args.edits);
+    }
+    // quoted strings and braces can contain inline macros
+    for tree in trees {
+        format_tree_content(tree, text, edits);
+    }
+}
+
+fn format_tree_content(tree: &TokenTree, text: &str, edits: &mut Vec<Edit>) {
+    match tree {
+        TokenTree::Atom(atom) | TokenTree::KV(_, atom) => match atom {
+            TokenTreeAtom::Tuple(trees) => {
+                for tree in trees {
+                    format_tree_content(tree, text, edits);
+                }
+            }
+            TokenTreeAtom::Braces(b, _) => format_segments(&b.segments, text, edits),
+            TokenTreeAtom::QuotedSegments(s, _) => format_segments(s, text, edits),
+            TokenTreeAtom::Word(_) | TokenTreeAtom::QuotedWord(..) => {}
+        },
+    }
+}
+
+/// Walks through the token trees and the text at the same time, and replaces
+/// the whitespace before each token. It fails if a token isn't where it is
+/// expected, so the arguments are left as is.
+struct ArgsFormatter<'a> {
+    text: &'a str,
+    /// The end of the previous token
+    pos: usize,
+    edits: Vec<Edit>,
+}
+
+impl ArgsFormatter<'_> {
+    /// Replaces the whitespace before the next token, and returns the start of
+    /// the token
+    fn whitespace(&mut self, replacement: &str) -> usize {
+        let ws = self.text[self.pos..].len()
+            - self.text[self.pos..].trim_start_matches([' ', '\t', '\n', '\r']).len();
+        let start = self.pos + ws;
+        if self.text[self.pos..start] != *replacement {
+            self.edits.push(Edit::new(self.pos, start, replacement.to_string()));
+        }
+        start
+    }
+
+    /// Expects the token after the whitespace
+    fn expect(&mut self, token: &str, ws: &str) -> Option<()> {
+        let start = self.whitespace(ws);
+        if !self.text[start..].starts_with(token) {
+            return None;
+        }
+        self.pos = start + token.len();
+        Some(())
+    }
+
+    /// Expects the token with the source span after the whitespace
+    fn span(&mut self, span: (usize, usize), ws: &str) -> Option<()> {
+        if self.whitespace(ws) != span.0 {
+            return None;
+        }
+        self.pos = span.1;
+        Some(())
+    }
+
+    fn trees(&mut self, trees: &[TokenTree]) -> Option<()> {
+        for (i, tree) in trees.iter().enumerate() {
+            let ws = if i == 0 { "" } else { " " };
+            match tree {
+                TokenTree::Atom(atom) => self.atom(atom, ws)?,
+                TokenTree::KV(key, value) => {
+                    self.span((key.start(), key.end()), ws)?;
+                    self.expect("=", "")?;
+                    self.atom(value, "")?;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn atom(&mut self, atom: &TokenTreeAtom, ws: &str) -> Option<()> {
+        match atom {
+            TokenTreeAtom::Word(s)
+            | TokenTreeAtom::QuotedWord(_, s)
+            | TokenTreeAtom::Braces(_, s)
+            | TokenTreeAtom::QuotedSegments(_, s) => self.span((s.start(), s.end()), ws),
+            TokenTreeAtom::Tuple(trees) => {
+                self.expect("[", ws)?;
+                self.trees(trees)?;
+                self.expect("]", "")
+            }
+        }
+    }
+}
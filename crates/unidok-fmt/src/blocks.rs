@@ -0,0 +1,306 @@
+use unidok_repr::ast::blocks::*;
+use unidok_repr::ast::html::{ElemClose, ElemContentAst, HtmlNodeAst};
+use unidok_repr::ast::macros::BlockMacroContent;
+use unidok_repr::ast::segments::SegmentAst;
+use unidok_repr::Span;
+
+use crate::Edit;
+
+pub(crate) fn format_blocks(blocks: &[BlockAst], text: &str, edits: &mut Vec<Edit>) {
+    for (i, block) in blocks.iter().enumerate() {
+        format_block(block, blocks, i, text, edits);
+    }
+}
+
+/// Formats a block. `siblings[i]` is the block itself, or the macro that
+/// contains it.
+fn format_block(
+    block: &BlockAst,
+    siblings: &[BlockAst],
+    i: usize,
+    text: &str,
+    edits: &mut Vec<Edit>,
+) {
+    match block {
+        BlockAst::Heading(h) => heading(h, text, edits),
+        BlockAst::Table(t) => {
+            for cell in t.rows.iter().flat_map(|r| &r.cells) {
+                if let CellContentAst::Blocks(b) = &cell.content {
+                    format_blocks(b, text, edits);
+                }
+            }
+            table(t, text, edits)
+        }
+        BlockAst::List(l) => {
+            // Changing the bullet could merge the list with an adjacent list
+            if !has_adjacent_list(siblings, i, l.bullet) {
+                bullets(l, edits);
+            }
+            for item in &l.items {
+                format_blocks(item, text, edits);
+            }
+        }
+        BlockAst::LabeledList(l) => {
+            for item in &l.items {
+                format_blocks(&item.content, text, edits);
+            }
+        }
+        BlockAst::Quote(q) => format_blocks(&q.content, text, edits),
+        BlockAst::BlockMacro(m) => match &m.content {
+            BlockMacroContent::Prefixed(b) => format_block(b, siblings, i, text, edits),
+            BlockMacroContent::Braces(b) => format_blocks(b, text, edits),
+            BlockMacroContent::None => {}
+        },
+        BlockAst::BlockHtml(HtmlNodeAst::Element(e)) => {
+            if let Some(ElemContentAst::Blocks(b)) = &e.content {
+                format_blocks(b, text, edits);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn as_list(block: &BlockAst) -> Option<&ListAst> {
+    match block {
+        BlockAst::List(l) => Some(l),
+        BlockAst::BlockMacro(m) => match &m.content {
+            BlockMacroContent::Prefixed(b) => as_list(b),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn has_adjacent_list(siblings: &[BlockAst], i: usize, bullet: Bullet) -> bool {
+    let is_ordered = |b: Bullet| matches!(b, Bullet::Dot { .. } | Bullet::Paren { .. });
+    let is_invisible = |b: &&BlockAst| {
+        matches!(b, BlockAst::Comment(_) | BlockAst::LinkRefDef(_) | BlockAst::FootnoteDef(_))
+    };
+
+    let before = siblings[..i].iter().rev().find(|b| !is_invisible(b));
+    let after = siblings[i + 1..].iter().find(|b| !is_invisible(b));
+    before
+        .into_iter()
+        .chain(after)
+        .filter_map(as_list)
+        .any(|l| is_ordered(l.bullet) == is_ordered(bullet))
+}
+
+fn slice(text: &str, span: Span) -> &str {
+    &text[span.start as usize..span.end as usize]
+}
+
+/// Replaces `*` and `+` with `-`, and `)` with `.`
+fn bullets(list: &ListAst, edits: &mut Vec<Edit>) {
+    let replacement = match list.bullet {
+        Bullet::Plus | Bullet::Star => "-",
+        Bullet::Paren { .. } => ".",
+        Bullet::Dash | Bullet::Dot { .. } => return,
+    };
+    for &bullet in &list.bullets {
+        let end = bullet.end as usize;
+        edits.push(Edit::new(end - 1, end, replacement.to_string()));
+    }
+}
+
+/// Converts the heading to an ATX heading with a single space after the `#`
+/// signs. Only the whitespace and the markers around the content are replaced,
+/// so edits within the content don't overlap.
+fn heading(heading: &HeadingAst, text: &str, edits: &mut Vec<Edit>) {
+    let source = slice(text, heading.span);
+    let start = heading.span.start as usize;
+    let line_end = |i: usize| source[i..].find(['\n', '\r']).map_or(source.len(), |j| i + j);
+
+    let first_line = &source[..line_end(0)];
+    let indent = first_line.len() - first_line.trim_start().len();
+    let (prefix_start, content_start, end) = match heading.kind {
+        HeadingKind::Atx => {
+            if source.contains(['\n', '\r']) {
+                return;
+            }
+            let hashes_end = indent + heading.level as usize;
+            (hashes_end, hashes_end + leading_ws(&first_line[hashes_end..]), first_line.len())
+        }
+        HeadingKind::Setext => {
+            let underline_start = match source.find('\n') {
+                Some(i) => i + 1,
+                None => return,
+            };
+            // Removing the underline can change how the previous lines are
+            // parsed, and braces and macro arguments in ATX headings can span
+            // multiple lines
+            let content = &first_line[indent..];
+            if !follows_blank_line(text, start)
+                || is_unclosed(content, '{', '}')
+                || is_unclosed(content, '(', ')')
+            {
+                return;
+            }
+            if source[underline_start..].trim_end().contains(['\n', '\r']) {
+                return;
+            }
+            (indent, indent, line_end(underline_start))
+        }
+    };
+    let content = first_line[content_start..].trim_end_matches([' ', '\t']);
+    let content_end = content_start + content.len();
+    // a trailing backslash would escape the line break
+    if content.ends_with('\\') {
+        return;
+    }
+
+    let mut prefix = match heading.kind {
+        HeadingKind::Atx => String::new(),
+        HeadingKind::Setext => "#".repeat(heading.level as usize),
+    };
+    if content.is_empty() {
+        push_edit(edits, text, start + prefix_start, start + end, prefix);
+        return;
+    }
+    prefix.push(' ');
+    push_edit(edits, text, start + prefix_start, start + content_start, prefix);
+
+    if ends_outside_element(&heading.segments) {
+        push_edit(edits, text, start + content_end, start + end, String::new());
+    } else if end > first_line.len() {
+        // the whitespace belongs to an unclosed HTML element, but the underline
+        // must be removed
+        push_edit(edits, text, start + first_line.len(), start + end, String::new());
+    }
+}
+
+/// Aligns the columns of a table. Only the whitespace between the cell meta,
+/// the cell content and the `|` separators is replaced. Tables with rows
+/// spanning multiple lines are left as is.
+fn table(table: &TableAst, text: &str, edits: &mut Vec<Edit>) {
+    if table.rows.iter().any(|r| slice(text, r.span).contains(['\n', '\r'])) {
+        return;
+    }
+
+    struct Cell {
+        meta_end: usize,
+        content_start: usize,
+        content_end: usize,
+        end: usize,
+        /// The whitespace between the meta and the content
+        separator: &'static str,
+        width: usize,
+    }
+
+    let mut rows = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        let mut cells = Vec::with_capacity(row.cells.len());
+        for cell in &row.cells {
+            let (meta_end, end) = (cell.meta_span.end as usize, cell.span.end as usize);
+            let content_start = meta_end + leading_ws(&text[meta_end..end]);
+            let content = text[content_start..end].trim_end_matches([' ', '\t']);
+            let content_end = content_start + content.len();
+            let segments = match &cell.content {
+                CellContentAst::Inline(s) => s,
+                CellContentAst::Blocks(_) => return,
+            };
+            if content.ends_with('\\') || !ends_outside_element(segments) {
+                return;
+            }
+            // whitespace after the content would turn it into the cell meta
+            let is_meta = meta_end == cell.meta_span.start as usize
+                && !content.is_empty()
+                && content.chars().all(|c| matches!(c, '#' | '<' | '>' | '^' | 'x' | '0'..='9'));
+            if is_meta {
+                return;
+            }
+
+            // Whitespace before the content is only inserted where it doesn't
+            // change how the content is parsed
+            let separator = if content.is_empty() {
+                ""
+            } else if meta_end > cell.meta_span.start as usize
+                || content_start > meta_end
+                || (content.starts_with(char::is_alphanumeric) && !is_autolink(content))
+            {
+                " "
+            } else {
+                ""
+            };
+            let formatted = crate::formatted(text, content_start, content_end, edits);
+            let width = (meta_end - cell.meta_span.start as usize)
+                + separator.len()
+                + formatted.chars().count();
+            cells.push(Cell { meta_end, content_start, content_end, end, separator, width });
+        }
+        rows.push(cells);
+    }
+
+    let mut widths = Vec::new();
+    for cells in &rows {
+        for (j, cell) in cells.iter().enumerate() {
+            match widths.get_mut(j) {
+                Some(w) if *w < cell.width => *w = cell.width,
+                Some(_) => {}
+                None => widths.push(cell.width),
+            }
+        }
+    }
+
+    for cells in rows {
+        let last = cells.len().saturating_sub(1);
+        for (j, cell) in cells.into_iter().enumerate() {
+            let padding =
+                if j < last { " ".repeat(widths[j] - cell.width + 1) } else { String::new() };
+            if cell.content_start == cell.content_end {
+                push_edit(edits, text, cell.meta_end, cell.end, padding);
+            } else {
+                let separator = cell.separator.to_string();
+                push_edit(edits, text, cell.meta_end, cell.content_start, separator);
+                push_edit(edits, text, cell.content_end, cell.end, padding);
+            }
+        }
+    }
+}
+
+/// Returns whether trailing whitespace after the segments is ignored. This is
+/// not the case if the segments end with an HTML element without a closing tag.
+fn ends_outside_element(segments: &[SegmentAst]) -> bool {
+    match segments.last() {
+        Some(SegmentAst::InlineHtml(HtmlNodeAst::Element(e))) => e.close != ElemClose::AutoClosing,
+        Some(SegmentAst::InlineMacro(m)) => ends_outside_element(std::slice::from_ref(&*m.segment)),
+        _ => true,
+    }
+}
+
+/// Returns whether the text contains an opening delimiter that isn't closed
+fn is_unclosed(text: &str, open: char, close: char) -> bool {
+    let mut depth = 0usize;
+    for c in text.chars() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth = depth.saturating_sub(1);
+        }
+    }
+    depth > 0
+}
+
+fn follows_blank_line(text: &str, pos: usize) -> bool {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let before = &text[..line_start];
+    let prev_line =
+        before.strip_suffix("\r\n").or_else(|| before.strip_suffix('\n')).unwrap_or(before);
+    line_start == 0 || prev_line[prev_line.rfind('\n').map_or(0, |i| i + 1)..].trim().is_empty()
+}
+
+/// Returns whether the text starts with a bare autolink, which must be
+/// preceded by whitespace or punctuation
+fn is_autolink(text: &str) -> bool {
+    ["www.", "http://", "https://", "mailto:"].iter().any(|p| text.starts_with(p))
+}
+
+fn leading_ws(text: &str) -> usize {
+    text.len() - text.trim_start_matches([' ', '\t']).len()
+}
+
+fn push_edit(edits: &mut Vec<Edit>, text: &str, start: usize, end: usize, replacement: String) {
+    if text[start..end] != replacement {
+        edits.push(Edit::new(start, end, replacement));
+    }
+}
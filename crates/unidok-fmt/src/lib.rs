@@ -0,0 +1,115 @@
+//! A formatter for Unidok source files.
+//!
+//! The formatter only rewrites the parts of a document that have a canonical
+//! form, and leaves everything else untouched, so comments, link reference
+//! definitions, code blocks and whitespace survive formatting. It normalizes
+//!
+//! - list bullets (`-` for unordered lists, `1.` for ordered lists),
+//! - headings (ATX headings with a single space after the `#` signs),
+//! - the column alignment of tables, and
+//! - the whitespace in macro arguments.
+
+mod args;
+mod blocks;
+
+use unidok_parser::Input;
+use unidok_repr::config::Config;
+use unidok_repr::{Span, SyntaxKind};
+
+/// Formats a Unidok document.
+pub fn format(text: &str) -> String {
+    let mut input = Input::new(text);
+    let config = Config { retrieve_spans: true, ..Config::default() };
+    let doc = unidok_parser::parse_ast(&mut input, config);
+
+    let mut edits = Vec::new();
+    // the table formatter needs the formatted cell contents to align columns
+    args::format_macro_args(&doc.blocks, text, &mut edits);
+    blocks::format_blocks(&doc.blocks, text, &mut edits);
+
+    // code blocks, comments etc. must be preserved verbatim
+    let protected: Vec<Span> = doc
+        .spans
+        .iter()
+        .filter(|s| {
+            matches!(
+                s.0,
+                SyntaxKind::CodeBlock
+                    | SyntaxKind::Comment
+                    | SyntaxKind::FrontMatter
+                    | SyntaxKind::HtmlCdata
+            )
+        })
+        .map(|s| s.1)
+        .collect();
+    edits.retain(|e| {
+        !protected.iter().any(|p| e.start < p.end as usize && (p.start as usize) < e.end)
+    });
+
+    apply_edits(text, edits)
+}
+
+/// Returns whether a document is already formatted.
+pub fn is_formatted(text: &str) -> bool {
+    format(text) == text
+}
+
+/// A replacement of a byte range in the source text
+#[derive(Debug)]
+pub(crate) struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub(crate) fn new(start: usize, end: usize, replacement: String) -> Self {
+        Edit { start, end, replacement }
+    }
+}
+
+/// Applies the edits to the text. The edits only replace whitespace and
+/// markers, never the content of a node, so they don't overlap, and nested
+/// nodes are formatted in a single pass.
+fn apply_edits(text: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|e| (e.start, e.end));
+    debug_assert!(edits.windows(2).all(|w| w[0].end <= w[1].start), "overlapping edits");
+
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+    for edit in edits {
+        result.push_str(&text[pos..edit.start]);
+        result.push_str(&edit.replacement);
+        pos = edit.end;
+    }
+    result.push_str(&text[pos..]);
+    result
+}
+
+/// Returns the text in the range with the edits in this range applied
+pub(crate) fn formatted(text: &str, start: usize, end: usize, edits: &[Edit]) -> String {
+    let mut result = String::new();
+    let mut pos = start;
+    let mut inner: Vec<&Edit> = edits.iter().filter(|e| start <= e.start && e.end <= end).collect();
+    inner.sort_by_key(|e| (e.start, e.end));
+    for edit in inner {
+        result.push_str(&text[pos..edit.start]);
+        result.push_str(&edit.replacement);
+        pos = edit.end;
+    }
+    result.push_str(&text[pos..end]);
+    result
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(format("* a\n* b\n\n1) c\n2) d"), "- a\n- b\n\n1. c\n2. d");
+    assert_eq!(format("##   Heading  "), "## Heading");
+    assert_eq!(format("Heading\n=======\n\nText\n---"), "# Heading\n\n## Text");
+    assert_eq!(format("Foo\nBar\n---"), "Foo\nBar\n---");
+    assert_eq!(format("#|| a |bbb\n ||cc|   d\n||< e|f"), "#|| a  | bbb\n || cc | d\n||< e | f",);
+    assert_eq!(format("@BULLET( none )\n+ a"), "@BULLET(none)\n- a");
+    assert_eq!(format("@(  class = \"a b\"  [ x  y ] )\nText"), "@(class=\"a b\" [x y])\nText");
+    assert_eq!(format("// * comment\n\n```\n* code\n```"), "// * comment\n\n```\n* code\n```");
+    assert_eq!(format("* a\n\n- b"), "* a\n\n- b");
+}
@@ -0,0 +1,61 @@
+//! Random documents, assembled from snippets of Unidok syntax, are formatted.
+//! Formatting must be idempotent and must not change the generated HTML.
+
+use unidok_parser::Input;
+use unidok_repr::config::Config;
+
+const ITERATIONS: usize = 20_000;
+
+const MAX_SNIPPETS: usize = 16;
+
+#[rustfmt::skip]
+const SNIPPETS: &[&str] = &[
+    "a", "b c", " ", "  ", "\t", "\n", "\n", "\n\n", "\\", "#", "# ", "## ", "=", "===", "---",
+    "- ", "* ", "+ ", "1. ", "2) ", "> ", "||", "||#", "||<", "||>", "|", "#||", "$", "`",
+    "<span>", "</span>", "<b title=\"x  \">", "<!-- x -->", "&amp;", "[", "]", "(", ")", "{", "}",
+    "\"", "'", "@BULLET( - )", "@BULLET(-)", "@( .a  #b )", "@(class = \"x  y\")", "@NOTE{",
+    "@COLS( l  r )", "@DETAILS( \"s\" )", "@LOOSE\n", "@(", "[x] ", "%{a}", "```\n", "www.a.b",
+    "#x#", "*a*", "|| a |bb", "||2x3 ", "@B( [ a ] k = v ){", "@C(\"@D( y )\")", "Title\n---\n",
+    "\n===\n", "<i>", "</i>", "<br>",
+];
+
+/// A xorshift random number generator, so the tests are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn document(&mut self) -> String {
+        let len = 1 + self.below(MAX_SNIPPETS);
+        (0..len).map(|_| SNIPPETS[self.below(SNIPPETS.len())]).collect()
+    }
+}
+
+fn to_html(text: &str) -> String {
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, Config::default());
+    unidok_to_html::to_string(&unidok_to_html::convert(doc))
+}
+
+fn check(text: &str) {
+    let formatted = unidok_fmt::format(text);
+    assert_eq!(unidok_fmt::format(&formatted), formatted, "formatting {:?} isn't idempotent", text);
+    assert_eq!(to_html(&formatted), to_html(text), "formatting {:?} changes the HTML", text);
+}
+
+#[test]
+fn random_documents() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..ITERATIONS {
+        check(&rng.document());
+    }
+}
@@ -0,0 +1,43 @@
+//! Formats the input of every test case in `tests/test_cases`, and checks that
+//! formatting is idempotent and doesn't change the generated HTML.
+
+use std::fs;
+
+use unidok_parser::Input;
+use unidok_repr::config::Config;
+
+const SPLIT: &str = "\n............................................................\n";
+
+fn to_html(text: &str) -> String {
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, Config::default());
+    unidok_to_html::to_string(&unidok_to_html::convert(doc))
+}
+
+fn inputs() -> Vec<(String, String)> {
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir("../../tests/test_cases").unwrap() {
+        let path = entry.unwrap().path();
+        let content = fs::read_to_string(&path).unwrap().replace("\r\n", "\n");
+        let input = content.split(SPLIT).next().unwrap().to_string();
+        inputs.push((path.display().to_string(), input));
+    }
+    assert!(!inputs.is_empty());
+    inputs
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for (path, input) in inputs() {
+        let formatted = unidok_fmt::format(&input);
+        assert_eq!(unidok_fmt::format(&formatted), formatted, "in {}", path);
+    }
+}
+
+#[test]
+fn formatting_preserves_html() {
+    for (path, input) in inputs() {
+        let formatted = unidok_fmt::format(&input);
+        assert_eq!(to_html(&formatted), to_html(&input), "in {}", path);
+    }
+}
@@ -0,0 +1,262 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::mem;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, DocumentSymbol, NumberOrString, SemanticToken,
+    SemanticTokenType, SymbolKind,
+};
+use unidok_parser::Input;
+use unidok_repr::ast::blocks::{FootnoteDef, HeadingAst, LinkRefDef};
+use unidok_repr::ast::macros::MACRO_NAMES;
+use unidok_repr::config::Config;
+use unidok_repr::diagnostics::{Diagnostic, Severity};
+use unidok_repr::{Span, SyntaxKind, SyntaxSpan, ToPlaintext};
+
+use crate::line_index::LineIndex;
+
+/// The semantic token types reported by the server. Only standard token types
+/// are used, so the tokens are highlighted by most color themes.
+pub(crate) const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::MACRO,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::STRING,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::CLASS,
+];
+
+fn token_type(kind: SyntaxKind) -> Option<u32> {
+    Some(match kind {
+        SyntaxKind::MacroName => 0,
+        SyntaxKind::MacroArg | SyntaxKind::MacroArgString | SyntaxKind::MacroArgList => 1,
+        SyntaxKind::MacroKey => 2,
+        SyntaxKind::LinkRef | SyntaxKind::LinkRefDef | SyntaxKind::Footnote => 3,
+        SyntaxKind::LinkHref
+        | SyntaxKind::LinkTitle
+        | SyntaxKind::ImageHref
+        | SyntaxKind::ImageTitle
+        | SyntaxKind::HtmlCdata => 4,
+        SyntaxKind::Comment | SyntaxKind::HtmlComment | SyntaxKind::FrontMatter => 5,
+        SyntaxKind::CodeFence
+        | SyntaxKind::AtxHeadingMarker
+        | SyntaxKind::SetextHeadingMarker
+        | SyntaxKind::ListMarker
+        | SyntaxKind::TaskMarker
+        | SyntaxKind::BlockquoteMarker => 6,
+        SyntaxKind::InfoString => 7,
+        SyntaxKind::Heading
+        | SyntaxKind::AtxHeading
+        | SyntaxKind::SetextHeading1
+        | SyntaxKind::SetextHeading2 => 8,
+        _ => return None,
+    })
+}
+
+/// The information about a document that is needed to answer requests.
+pub(crate) struct Analysis<'a> {
+    pub(crate) index: LineIndex<'a>,
+    text: &'a str,
+    spans: Vec<SyntaxSpan>,
+    headings: Vec<(HeadingAst, String)>,
+    link_ref_defs: HashMap<String, LinkRefDef>,
+    footnote_defs: HashMap<String, FootnoteDef>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Analysis<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let mut input = Input::new(text);
+        let config = Config { retrieve_spans: true, ..Config::default() };
+
+        let mut ast = unidok_parser::parse_ast(&mut input, config);
+        let spans = mem::take(&mut ast.spans);
+        let heading_asts = ast.data.headings.clone();
+        let link_ref_defs = ast.data.link_ref_defs.clone();
+        let footnote_defs = ast.data.footnote_defs.clone();
+
+        let doc = ast.into_doc(&input.text);
+        let headings = heading_asts
+            .into_iter()
+            .zip(&doc.state.headings)
+            .map(|(ast, ir)| {
+                let mut name = String::new();
+                for segment in &ir.segments {
+                    segment.to_plaintext(&mut name);
+                }
+                (ast, name.trim().to_string())
+            })
+            .collect();
+
+        Analysis {
+            index: LineIndex::new(text),
+            text,
+            spans,
+            headings,
+            link_ref_defs,
+            footnote_defs,
+            diagnostics: doc.diagnostics,
+        }
+    }
+
+    pub(crate) fn semantic_tokens(&self) -> Vec<SemanticToken> {
+        let mut spans = self.spans.clone();
+        spans.sort_by_key(|SyntaxSpan(_, span)| (span.start, Reverse(span.end)));
+
+        // Nested spans are painted over outer spans, because semantic tokens
+        // must not overlap
+        let mut painted = vec![None; self.text.len()];
+        for SyntaxSpan(kind, span) in spans {
+            if let Some(token_type) = token_type(kind) {
+                let end = (span.end as usize).min(self.text.len());
+                for p in &mut painted[span.start as usize..end] {
+                    *p = Some(token_type);
+                }
+            }
+        }
+
+        let mut tokens = Vec::new();
+        let (mut prev_line, mut prev_start) = (0, 0);
+
+        for line in 0..self.index.line_count() {
+            let line_start = self.index.line_start(line);
+            let line_end = self.index.line_end(line);
+
+            let mut i = line_start;
+            while i < line_end {
+                let token_type = match painted[i] {
+                    Some(token_type) => token_type,
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                };
+                let run_end = (i..line_end).find(|&j| painted[j] != Some(token_type));
+                let run_end = run_end.unwrap_or(line_end);
+
+                let start = self.text[line_start..i].encode_utf16().count() as u32;
+                let length = self.text[i..run_end].encode_utf16().count() as u32;
+                let line = line as u32;
+
+                tokens.push(SemanticToken {
+                    delta_line: line - prev_line,
+                    delta_start: if line == prev_line { start - prev_start } else { start },
+                    length,
+                    token_type,
+                    token_modifiers_bitset: 0,
+                });
+                prev_line = line;
+                prev_start = start;
+                i = run_end;
+            }
+        }
+        tokens
+    }
+
+    /// Returns the headings as a tree, where each heading contains the
+    /// headings with a higher level until the next heading with the same or a
+    /// lower level.
+    pub(crate) fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        let mut result = Vec::new();
+        let mut stack: Vec<(u8, DocumentSymbol)> = Vec::new();
+
+        for (heading, name) in &self.headings {
+            let end = self.index.position(heading.span.start as usize);
+            while let Some(&(level, _)) = stack.last() {
+                if level < heading.level {
+                    break;
+                }
+                let (_, mut symbol) = stack.pop().unwrap();
+                symbol.range.end = end;
+                add_symbol(&mut stack, &mut result, symbol);
+            }
+
+            let range = self.index.range(heading.span);
+            #[allow(deprecated)]
+            let symbol = DocumentSymbol {
+                name: if name.is_empty() { "(empty)".to_string() } else { name.clone() },
+                detail: None,
+                kind: SymbolKind::STRING,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            };
+            stack.push((heading.level, symbol));
+        }
+
+        let end = self.index.position(self.text.len());
+        while let Some((_, mut symbol)) = stack.pop() {
+            symbol.range.end = end;
+            add_symbol(&mut stack, &mut result, symbol);
+        }
+        result
+    }
+
+    /// Returns the span of the link reference definition or footnote
+    /// definition that is referenced at the given offset.
+    pub(crate) fn definition(&self, offset: usize) -> Option<Span> {
+        let &SyntaxSpan(kind, span) = self.spans.iter().find(|&&SyntaxSpan(kind, span)| {
+            matches!(kind, SyntaxKind::LinkRef | SyntaxKind::Footnote)
+                && span.start as usize <= offset
+                && offset <= span.end as usize
+        })?;
+        let name = &self.text[span.start as usize..span.end as usize];
+        if kind == SyntaxKind::Footnote {
+            Some(self.footnote_defs.get(name)?.name.into())
+        } else {
+            Some(self.link_ref_defs.get(name)?.name.into())
+        }
+    }
+
+    /// Returns the macro names if the cursor is after an `@`, optionally
+    /// followed by a partial macro name.
+    pub(crate) fn completions(&self, offset: usize) -> Vec<CompletionItem> {
+        let before = self.text[..offset]
+            .trim_end_matches(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        if !before.ends_with('@') {
+            return vec![];
+        }
+
+        MACRO_NAMES
+            .iter()
+            .map(|&name| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    pub(crate) fn diagnostics(&self) -> Vec<lsp_types::Diagnostic> {
+        self.diagnostics
+            .iter()
+            .map(|d| lsp_types::Diagnostic {
+                range: self.index.range(d.span),
+                severity: Some(match d.severity {
+                    Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+                    Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+                }),
+                code: Some(NumberOrString::String(d.code.as_str().to_string())),
+                source: Some("unidok".to_string()),
+                message: d.message.clone(),
+                ..lsp_types::Diagnostic::default()
+            })
+            .collect()
+    }
+}
+
+fn add_symbol(
+    stack: &mut [(u8, DocumentSymbol)],
+    result: &mut Vec<DocumentSymbol>,
+    symbol: DocumentSymbol,
+) {
+    match stack.last_mut() {
+        Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+        None => result.push(symbol),
+    }
+}
@@ -0,0 +1,62 @@
+use lsp_types::{Position, Range};
+use unidok_repr::Span;
+
+/// Converts between byte offsets and LSP positions, which consist of a line
+/// number and a column in UTF-16 code units.
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let line_starts =
+            std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        LineIndex { text, line_starts }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(crate) fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start as usize), self.position(span.end as usize))
+    }
+
+    pub(crate) fn offset(&self, position: Position) -> usize {
+        let line_start = match self.line_starts.get(position.line as usize) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let line = &self.text[line_start..self.line_end(position.line as usize)];
+
+        let mut utf16_len = 0;
+        for (i, c) in line.char_indices() {
+            if utf16_len >= position.character as usize {
+                return line_start + i;
+            }
+            utf16_len += c.len_utf16();
+        }
+        line_start + line.len()
+    }
+
+    /// Returns the offset of the end of the line, excluding the line break
+    pub(crate) fn line_end(&self, line: usize) -> usize {
+        match self.line_starts.get(line + 1) {
+            Some(&next) => next - 1,
+            None => self.text.len(),
+        }
+    }
+
+    pub(crate) fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub(crate) fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line]
+    }
+}
@@ -0,0 +1,10 @@
+use lsp_server::Connection;
+
+fn main() -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    unidok_lsp::run(&connection)?;
+
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::thread::{self, JoinHandle};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId};
+use lsp_types::notification::{
+    DidOpenTextDocument, Exit, Initialized, Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, Initialize, Request as RequestTrait,
+    SemanticTokensFullRequest, Shutdown,
+};
+use lsp_types::*;
+
+/// A client that talks to a server running in another thread
+struct TestClient {
+    connection: Connection,
+    server: Option<JoinHandle<()>>,
+    next_id: i32,
+}
+
+impl TestClient {
+    fn new() -> Self {
+        let (server_conn, connection) = Connection::memory();
+        let server = thread::spawn(move || crate::run(&server_conn).unwrap());
+
+        let mut client = TestClient { connection, server: Some(server), next_id: 0 };
+        client.request::<Initialize>(InitializeParams::default());
+        client.notify::<Initialized>(InitializedParams {});
+        client
+    }
+
+    fn request<R: RequestTrait>(&mut self, params: R::Params) -> R::Result {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        let req = Request::new(id.clone(), R::METHOD.to_string(), params);
+        self.connection.sender.send(Message::Request(req)).unwrap();
+
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(res) if res.id == id => {
+                    assert!(res.error.is_none(), "{:?}", res.error);
+                    return serde_json::from_value(res.result.unwrap()).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn notify<N: NotificationTrait>(&self, params: N::Params) {
+        let not = Notification::new(N::METHOD.to_string(), params);
+        self.connection.sender.send(Message::Notification(not)).unwrap();
+    }
+
+    fn receive_notification<N: NotificationTrait>(&self) -> N::Params {
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Notification(not) if not.method == N::METHOD => {
+                    return serde_json::from_value(not.params).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn open(&self, text: &str) -> Url {
+        let uri = Url::parse("file:///test.ud").unwrap();
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(uri.clone(), "unidok".into(), 1, text.into()),
+        });
+        uri
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        self.server.take().unwrap().join().unwrap();
+    }
+}
+
+fn position_params(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+    TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(uri.clone()),
+        Position::new(line, character),
+    )
+}
+
+#[test]
+fn test_diagnostics() {
+    let client = TestClient::new();
+    let uri = client.open("# Heading\n\n@FOO\n[föö]");
+
+    let params = client.receive_notification::<PublishDiagnostics>();
+    assert_eq!(params.uri, uri);
+    let diagnostics: Vec<_> = params.diagnostics.iter().map(|d| (d.range, d.severity)).collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (Range::new(Position::new(2, 1), Position::new(2, 4)), Some(DiagnosticSeverity::ERROR)),
+            (
+                Range::new(Position::new(3, 1), Position::new(3, 4)),
+                Some(DiagnosticSeverity::WARNING)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_semantic_tokens() {
+    let mut client = TestClient::new();
+    let uri = client.open("# Heading\n\n@BULLET(none)\n- a");
+
+    let result = client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+        text_document: TextDocumentIdentifier::new(uri),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let tokens = match result {
+        Some(SemanticTokensResult::Tokens(tokens)) => tokens.data,
+        _ => panic!("expected semantic tokens"),
+    };
+    let tokens: Vec<_> =
+        tokens.iter().map(|t| (t.delta_line, t.delta_start, t.length, t.token_type)).collect();
+    assert_eq!(tokens, [(0, 0, 9, 8), (2, 1, 6, 0), (0, 7, 4, 1), (1, 0, 1, 6)]);
+}
+
+#[test]
+fn test_document_symbols() {
+    let mut client = TestClient::new();
+    let uri = client.open("# A\n\n## B\n\n### C\n\nD\n--\n\n# E");
+
+    let result = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(uri),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let symbols = match result {
+        Some(DocumentSymbolResponse::Nested(symbols)) => symbols,
+        _ => panic!("expected nested document symbols"),
+    };
+
+    fn names(symbols: &[DocumentSymbol]) -> String {
+        let names: Vec<_> = symbols
+            .iter()
+            .map(|s| match &s.children {
+                Some(c) => format!("{}[{}]", s.name, names(c)),
+                None => s.name.clone(),
+            })
+            .collect();
+        names.join(", ")
+    }
+    assert_eq!(names(&symbols), "A[B[C], D], E");
+    assert_eq!(symbols[0].range, Range::new(Position::new(0, 0), Position::new(9, 0)));
+    assert_eq!(symbols[0].selection_range, Range::new(Position::new(0, 0), Position::new(0, 3)));
+}
+
+#[test]
+fn test_goto_definition() {
+    let mut client = TestClient::new();
+    let uri = client.open("See [here][link] or [link].\n\n[link]: https://example.com");
+
+    for character in [12, 22] {
+        let result = client.request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: position_params(&uri, 0, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let range = Range::new(Position::new(2, 1), Position::new(2, 5));
+        assert_eq!(result, Some(GotoDefinitionResponse::Scalar(Location::new(uri.clone(), range))));
+    }
+
+    let result = client.request::<GotoDefinition>(GotoDefinitionParams {
+        text_document_position_params: position_params(&uri, 0, 1),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    assert_eq!(result, None);
+}
+
+#[test]
+fn test_goto_footnote_definition() {
+    let mut client = TestClient::new();
+    let uri = client.open("Claim [^foo]\n\n[^foo]: Footnote");
+
+    let result = client.request::<GotoDefinition>(GotoDefinitionParams {
+        text_document_position_params: position_params(&uri, 0, 9),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let range = Range::new(Position::new(2, 2), Position::new(2, 5));
+    assert_eq!(result, Some(GotoDefinitionResponse::Scalar(Location::new(uri, range))));
+}
+
+#[test]
+fn test_completion() {
+    let mut client = TestClient::new();
+    let uri = client.open("@TO\nfoo");
+
+    let mut complete = |line, character| {
+        let result = client.request::<Completion>(CompletionParams {
+            text_document_position: position_params(&uri, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        });
+        match result {
+            Some(CompletionResponse::Array(items)) => items,
+            _ => panic!("expected completion items"),
+        }
+    };
+
+    let items = complete(0, 3);
+    assert!(items.iter().any(|item| item.label == "TOC"));
+    assert!(items.iter().any(|item| item.label == "FOOTNOTES"));
+    assert!(complete(1, 2).is_empty());
+}
@@ -1,8 +1,9 @@
-use unidok_repr::ast::blocks::BlockAst;
+use unidok_repr::ast::blocks::{BlockAst, CellContentAst};
 use unidok_repr::ast::html::{ElemContentAst, HtmlNodeAst};
 use unidok_repr::ast::macros::BlockMacroContent;
 use unidok_repr::ast::segments::SegmentAst;
 use unidok_repr::ast::AstData;
+use unidok_repr::config::Dialect;
 
 pub(crate) fn accumulate_block_data(
     parsed: &[BlockAst],
@@ -29,7 +30,10 @@ fn accumulate_single_block_data(parsed: &BlockAst, data: &mut AstData, no_toc: b
         BlockAst::Table(t) => {
             for row in &t.rows {
                 for cell in &row.cells {
-                    accumulate_segment_data(&cell.segments, data, no_toc, text);
+                    match &cell.content {
+                        CellContentAst::Inline(s) => accumulate_segment_data(s, data, no_toc, text),
+                        CellContentAst::Blocks(b) => accumulate_block_data(b, data, no_toc, text),
+                    }
                 }
             }
         }
@@ -39,6 +43,12 @@ fn accumulate_single_block_data(parsed: &BlockAst, data: &mut AstData, no_toc: b
                 accumulate_block_data(item, data, no_toc, text);
             }
         }
+        BlockAst::LabeledList(l) => {
+            for item in &l.items {
+                accumulate_segment_data(&item.label, data, no_toc, text);
+                accumulate_block_data(&item.content, data, no_toc, text);
+            }
+        }
         BlockAst::Quote(q) => accumulate_block_data(&q.content, data, no_toc, text),
         BlockAst::BlockMacro(b) => {
             let no_toc = match b.name.to_str(text) {
@@ -56,8 +66,21 @@ fn accumulate_single_block_data(parsed: &BlockAst, data: &mut AstData, no_toc: b
         BlockAst::BlockHtml(b) => accumulate_html(b, data, no_toc, text),
         BlockAst::Comment(_) => {}
         BlockAst::LinkRefDef(l) => {
-            let name = l.name.to_str(text).to_string();
-            data.link_ref_defs.insert(name, l.clone());
+            let name = data.link_ref_key(l.name.to_str(text));
+            match data.config.dialect {
+                Dialect::Unidok => {
+                    data.link_ref_defs.insert(name, l.clone());
+                }
+                // in CommonMark, the first definition takes precedence
+                Dialect::CommonMark => {
+                    data.link_ref_defs.entry(name).or_insert_with(|| l.clone());
+                }
+            }
+        }
+        BlockAst::FootnoteDef(f) => {
+            accumulate_segment_data(&f.segments, data, no_toc, text);
+            let name = f.name.to_str(text).to_string();
+            data.footnote_defs.insert(name, f.clone());
         }
     }
 }
@@ -104,7 +127,7 @@ fn accumulate_single_segment_data(
             accumulate_single_segment_data(&i.segment, data, no_toc, text);
         }
         SegmentAst::InlineHtml(i) => accumulate_html(i, data, no_toc, text),
-        SegmentAst::HtmlEntity(_) => {}
+        SegmentAst::HtmlEntity(_) | SegmentAst::CharRef(_) => {}
         SegmentAst::Format(f) => accumulate_segment_data(&f.segments, data, no_toc, text),
         SegmentAst::Code(c) => accumulate_segment_data(&c.segments, data, no_toc, text),
     }
@@ -123,6 +146,6 @@ fn accumulate_html(node: &HtmlNodeAst, data: &mut AstData, no_toc: bool, text: &
         }
         HtmlNodeAst::CData(_) => {}
         HtmlNodeAst::Comment(_) => {}
-        HtmlNodeAst::Doctype(_) => {}
+        HtmlNodeAst::Doctype(_) | HtmlNodeAst::Raw(_) => {}
     }
 }
@@ -1,11 +1,12 @@
 use unidok_repr::ast::blocks::{BlockAst, HeadingAst, HeadingKind, ParagraphAst};
+use unidok_repr::Span;
 
 use crate::blocks::*;
 use crate::inlines::Segments;
 use crate::macros::ParseBlockMacro;
 use crate::parsing_mode::ParsingMode;
 use crate::state::ParsingState;
-use crate::utils::ParseLineBreak;
+use crate::utils::{is_blank_line, ParseLineBreak};
 use crate::{Context, Input, Parse};
 
 #[derive(Debug, Clone, Copy)]
@@ -41,10 +42,24 @@ impl Parse for ParseBlock<'_> {
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
         let ind = self.state.ind();
-        let mode = self.mode.unwrap_or_else(ParsingMode::new_all);
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
         let ac = self.state.special_chars();
         let context = self.state.context();
 
+        if mode.is(ParsingMode::CODE_BLOCKS) {
+            if let Some(block) = input.parse(ParseIndentedCodeBlock { ind, ac }) {
+                self.consume_empty_lines(input);
+                return Some(BlockAst::CodeBlock(block));
+            }
+        }
+
+        if mode.is(ParsingMode::HTML) {
+            if let Some(html) = input.parse(ParseHtmlBlock { ind }) {
+                self.consume_empty_lines(input);
+                return Some(BlockAst::BlockHtml(html));
+            }
+        }
+
         if mode.is(ParsingMode::COMMENTS) {
             if let Some(comment) = input.parse(ParseComment) {
                 self.consume_empty_lines(input);
@@ -53,7 +68,7 @@ impl Parse for ParseBlock<'_> {
         }
 
         if mode.is(ParsingMode::THEMATIC_BREAKS) {
-            if let Some(tb) = input.parse(ParseThematicBreak { ind }) {
+            if let Some(tb) = input.parse(ParseThematicBreak) {
                 self.consume_empty_lines(input);
                 return Some(BlockAst::ThematicBreak(tb));
             }
@@ -85,6 +100,10 @@ impl Parse for ParseBlock<'_> {
                 self.consume_empty_lines(input);
                 return Some(BlockAst::List(list));
             }
+            if let Some(list) = input.parse(ParseLabeledList { ind, mode: self.mode, ac }) {
+                self.consume_empty_lines(input);
+                return Some(BlockAst::LabeledList(list));
+            }
         }
 
         if mode.is(ParsingMode::QUOTES) {
@@ -95,6 +114,10 @@ impl Parse for ParseBlock<'_> {
         }
 
         if mode.is(ParsingMode::LINKS_IMAGES) {
+            if let Some(def) = input.parse(ParseFootnoteDef { ind, context, mode, ac }) {
+                self.consume_empty_lines(input);
+                return Some(BlockAst::FootnoteDef(def));
+            }
             if let Some(lrd) = input.parse(ParseLinkRefDef { ind }) {
                 self.consume_empty_lines(input);
                 return Some(BlockAst::LinkRefDef(lrd));
@@ -109,7 +132,9 @@ impl Parse for ParseBlock<'_> {
             }
         }
 
+        let start = Span::from(input.prev_slice_bytes(0));
         let segments = input.parse(Segments::parser(ind, context, mode, ac))?;
+        let span = start.until(input.prev_slice_bytes(0).into());
         self.consume_empty_lines(input);
 
         match segments {
@@ -117,17 +142,16 @@ impl Parse for ParseBlock<'_> {
                 Some(BlockAst::Paragraph(ParagraphAst { segments: vec![] }))
             }
             Segments::Empty => None,
-            Segments::Some { segments, underline: None } => {
-                Some(BlockAst::Paragraph(ParagraphAst { segments }))
-            }
             Segments::Some { segments, underline: Some(u) } if mode.is(ParsingMode::HEADINGS) => {
                 Some(BlockAst::Heading(HeadingAst {
                     level: u.level(),
                     kind: HeadingKind::Setext,
                     segments,
+                    span,
                 }))
             }
-            _ => panic!("Parsed an underlined heading where no headings are allowed"),
+            // underlines are only parsed where headings are allowed
+            Segments::Some { segments, .. } => Some(BlockAst::Paragraph(ParagraphAst { segments })),
         }
     }
 
@@ -146,21 +170,58 @@ impl Parse for ParseBlocks<'_> {
     type Output = Vec<BlockAst>;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        self.parse_with_blank_lines(input).map(|(blocks, _)| blocks)
+    }
+}
+
+impl ParseBlocks<'_> {
+    /// Parses the blocks and returns for each block whether it is followed by
+    /// a blank line
+    pub(crate) fn parse_with_blank_lines(
+        &mut self,
+        input: &mut Input,
+    ) -> Option<(Vec<BlockAst>, Vec<bool>)> {
+        input.depth += 1;
+        let result = self.parse_blocks(input);
+        input.depth -= 1;
+        result
+    }
+
+    fn parse_blocks(&mut self, input: &mut Input) -> Option<(Vec<BlockAst>, Vec<bool>)> {
         loop {
             if input.parse(ParseLineBreak(self.state.ind())).is_none() {
                 break;
             }
             if input.is_empty() {
-                return Some(vec![]);
+                return Some((vec![], vec![]));
             }
         }
 
-        let parser = ParseBlock { mode: self.mode, state: self.state };
-
         let mut v = Vec::new();
-        while let Some(node) = input.parse(parser) {
-            v.push(node);
+        let mut blank_lines = Vec::new();
+        loop {
+            // when a limit is exceeded, the rest is parsed as plain text
+            let mode =
+                if input.is_restricted() { Some(ParsingMode::new_nothing()) } else { self.mode };
+            let start = input.prev_slice_bytes(0).start();
+            match input.parse(ParseBlock { mode, state: self.state }) {
+                Some(node) => {
+                    let end = input.prev_slice_bytes(0).start();
+                    v.push(node);
+                    blank_lines.push(is_followed_by_blank_line(&input.text[start..end], input));
+                }
+                None => break,
+            }
         }
-        Some(v)
+        Some((v, blank_lines))
     }
 }
+
+/// Returns whether a blank line follows the block. The line breaks after the
+/// block may already be consumed, or not.
+fn is_followed_by_blank_line(block: &str, input: &Input) -> bool {
+    let content = block.trim_end_matches([' ', '\t', '\n', '\r']);
+    let line_breaks = block[content.len()..].matches('\n').count();
+
+    line_breaks >= 2 || line_breaks == 1 && is_blank_line(input.rest())
+}
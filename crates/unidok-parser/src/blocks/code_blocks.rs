@@ -2,12 +2,16 @@ use std::convert::TryInto;
 
 use aho_corasick::AhoCorasick;
 use detached_str::StrSlice;
-use unidok_repr::ast::blocks::{CodeBlockAst, FenceType};
+use unidok_repr::ast::blocks::{BlockAst, CodeBlockAst, FenceType};
+use unidok_repr::config::Dialect;
 use unidok_repr::Span;
 
 use crate::parsing_mode::ParsingMode;
 use crate::state::ParsingState;
-use crate::utils::{ParseLineBreak, ParseSpacesU8, ParseWsAndLineEnd, Until, While};
+use crate::utils::{
+    has_code_indent, ParseAtMostNSpaces, ParseLineBreak, ParseNSpaces, ParseSpacesU8,
+    ParseWsAndLineEnd, Until, While,
+};
 use crate::{Context, Indents, Input, Parse};
 
 use super::ParseBlock;
@@ -25,7 +29,9 @@ impl Parse for ParseCodeBlock<'_> {
         let mut input = input.start();
 
         let indent = input.parse(ParseSpacesU8)?;
-        let ind = self.ind.push_indent(indent);
+        // in CommonMark, lines that are indented less than the fence are allowed
+        let is_commonmark = input.dialect == Dialect::CommonMark;
+        let ind = if is_commonmark { self.ind } else { self.ind.push_indent(indent) };
 
         let mut closing_fence = None;
         let (fence_type, opening_fence) = input.parse(ParseFence)?;
@@ -41,6 +47,9 @@ impl Parse for ParseCodeBlock<'_> {
             }
 
             let mut input2 = input.start();
+            if is_commonmark {
+                input2.try_parse(ParseAtMostNSpaces(3));
+            }
             if let Some((cf_type, span)) = input2.parse(ParseFence) {
                 if cf_type.can_close(fence_type) && input2.parse(ParseWsAndLineEnd).is_some() {
                     closing_fence = Some(span);
@@ -50,9 +59,14 @@ impl Parse for ParseCodeBlock<'_> {
             }
             drop(input2);
 
-            let line = input
-                .parse(ParseBlock::new(Some(mode), ParsingState::new(ind, context, self.ac)))?;
-            lines.push(line);
+            if is_commonmark {
+                input.try_parse(ParseAtMostNSpaces(indent));
+            }
+            match input.parse(ParseBlock::new(Some(mode), ParsingState::new(ind, context, self.ac)))
+            {
+                Some(line) => lines.push(line),
+                None => break,
+            }
         }
 
         let closing_fence = closing_fence.unwrap_or_else(|| input.prev_slice_bytes(0).into());
@@ -62,6 +76,68 @@ impl Parse for ParseCodeBlock<'_> {
     }
 }
 
+/// Parses a code block where each line is indented by 4 spaces. This is only
+/// supported in the CommonMark dialect.
+pub(crate) struct ParseIndentedCodeBlock<'a> {
+    pub ind: Indents<'a>,
+    pub ac: &'a AhoCorasick,
+}
+
+impl Parse for ParseIndentedCodeBlock<'_> {
+    type Output = CodeBlockAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        if !self.can_parse(input) {
+            return None;
+        }
+        let mut input = input.start();
+
+        let opening_fence = input.prev_slice_bytes(0).into();
+        input.parse(ParseNSpaces(4))?;
+        let ind = self.ind.push_indent(4);
+
+        let mode = ParsingMode::new_nothing();
+        let state = ParsingState::new(ind, Context::CodeBlock, self.ac);
+
+        let mut lines = vec![input.parse(ParseBlock::new(Some(mode), state))?];
+        let mut closing_fence = input.prev_slice_bytes(0).into();
+        let mut line_count = 1;
+        while !input.is_empty() {
+            if input.parse(ParseLineBreak(ind)).is_none() {
+                break;
+            }
+            match input.parse(ParseBlock::new(Some(mode), state)) {
+                Some(BlockAst::Paragraph(p)) if p.segments.is_empty() => {
+                    lines.push(BlockAst::Paragraph(p));
+                }
+                Some(line) => {
+                    lines.push(line);
+                    line_count = lines.len();
+                    closing_fence = input.prev_slice_bytes(0).into();
+                }
+                None => break,
+            }
+        }
+        // trailing blank lines aren't part of the code block
+        lines.truncate(line_count);
+
+        let info = input.prev_slice_bytes(0);
+        input.apply();
+        Some(CodeBlockAst {
+            info,
+            fence_type: FenceType::Indented,
+            lines,
+            indent: 0,
+            opening_fence,
+            closing_fence,
+        })
+    }
+
+    fn can_parse(&mut self, input: &mut Input) -> bool {
+        input.dialect == Dialect::CommonMark && has_code_indent(input.rest())
+    }
+}
+
 struct ParseFence;
 
 impl Parse for ParseFence {
@@ -100,7 +176,10 @@ impl Parse for ParseInfo {
 
         let c = match self.0 {
             FenceType::Backticks(_) => '`',
+            // in CommonMark, the info string of a tilde fence can contain tildes
+            FenceType::Tildes(_) if input.dialect == Dialect::CommonMark => return Some(s),
             FenceType::Tildes(_) => '~',
+            FenceType::Indented => return Some(s),
         };
         if s.to_str(&input.text).contains(c) {
             return None;
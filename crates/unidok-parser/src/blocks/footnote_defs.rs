@@ -0,0 +1,62 @@
+use aho_corasick::AhoCorasick;
+use detached_str::StrSlice;
+use unidok_repr::ast::blocks::FootnoteDef;
+use unidok_repr::ast::segments::SegmentAst;
+
+use crate::inlines::Segments;
+use crate::parsing_mode::ParsingMode;
+use crate::utils::{ParseSpacesU8, Until};
+use crate::{Context, Indents, Input, Parse};
+
+pub(crate) struct ParseFootnoteDef<'a> {
+    pub ind: Indents<'a>,
+    pub context: Context<'a>,
+    pub mode: ParsingMode,
+    pub ac: &'a AhoCorasick,
+}
+
+impl Parse for ParseFootnoteDef<'_> {
+    type Output = FootnoteDef;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        let name = input.parse(ParseFootnoteName)?;
+        input.try_parse(ParseSpacesU8);
+        let mut segments = input
+            .parse(Segments::parser(self.ind, self.context, self.mode, self.ac))?
+            .into_segments_no_underline()?;
+        while let Some(SegmentAst::LineBreak) = segments.last() {
+            segments.pop();
+        }
+
+        input.apply();
+        Some(FootnoteDef { name, segments })
+    }
+
+    fn can_parse(&mut self, input: &mut Input) -> bool {
+        input.can_parse(ParseFootnoteName)
+    }
+}
+
+/// Parses `[^name]:`, and returns the name
+struct ParseFootnoteName;
+
+impl Parse for ParseFootnoteName {
+    type Output = StrSlice;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.try_parse(ParseSpacesU8);
+        input.parse("[^")?;
+        let name = input.parse(Until(|c| matches!(c, ']' | '\n' | '\r')))?;
+        if name.is_empty() {
+            return None;
+        }
+        input.parse("]:")?;
+
+        input.apply();
+        Some(name)
+    }
+}
@@ -1,9 +1,11 @@
 use aho_corasick::AhoCorasick;
 use unidok_repr::ast::blocks::{HeadingAst, HeadingKind};
+use unidok_repr::ast::segments::SegmentAst;
+use unidok_repr::config::Dialect;
 
 use crate::inlines::Segments;
 use crate::parsing_mode::ParsingMode;
-use crate::utils::{ParseLineBreak, ParseLineEnd, ParseSpaces, While};
+use crate::utils::{has_code_indent, is_ws, ParseLineBreak, ParseLineEnd, ParseSpaces, While};
 use crate::{Context, Indents, Input, Parse};
 
 pub(crate) struct ParseHeading<'a> {
@@ -19,14 +21,17 @@ impl Parse for ParseHeading<'_> {
 
         input.parse_i(ParseSpaces);
         let level = input.parse(ParseHashes)?;
+        let mode = ParsingMode::new_default(input.dialect);
         let segments = input
-            .parse(Segments::parser(self.ind, Context::Heading, ParsingMode::new_all(), self.ac))?
+            .parse(Segments::parser(self.ind, Context::Heading, mode, self.ac))?
             .into_segments_no_underline_zero()?;
+        let mut segments = segments;
+        if input.dialect == Dialect::CommonMark {
+            strip_closing_sequence(&mut segments, &input);
+        }
 
-        let heading = HeadingAst { level, segments, kind: HeadingKind::Atx };
-
-        input.apply();
-        Some(heading)
+        let span = input.apply().into();
+        Some(HeadingAst { level, segments, kind: HeadingKind::Atx, span })
     }
 
     fn can_parse(&mut self, input: &mut Input) -> bool {
@@ -36,6 +41,38 @@ impl Parse for ParseHeading<'_> {
     }
 }
 
+/// Removes the optional closing sequence of `#` characters of a CommonMark
+/// ATX heading, e.g. `## Heading ##`
+fn strip_closing_sequence(segments: &mut Vec<SegmentAst>, input: &Input) {
+    // the closing sequence may be split into several text segments
+    while let [.., SegmentAst::Text(a), SegmentAst::Text(b)] = segments.as_slice() {
+        match a.try_join(*b) {
+            Some(joined) => {
+                segments.pop();
+                if let Some(last) = segments.last_mut() {
+                    *last = SegmentAst::Text(joined);
+                }
+            }
+            None => break,
+        }
+    }
+
+    let is_only_segment = segments.len() == 1;
+    if let Some(SegmentAst::Text(text)) = segments.last_mut() {
+        let s = text.to_str(&input.text).trim_end_matches(is_ws);
+        let without_hashes = s.trim_end_matches('#');
+
+        if without_hashes.len() < s.len()
+            && (without_hashes.ends_with(is_ws) || without_hashes.is_empty() && is_only_segment)
+        {
+            *text = text.get(..without_hashes.len());
+            if without_hashes.is_empty() {
+                segments.pop();
+            }
+        }
+    }
+}
+
 struct ParseHashes;
 
 impl Parse for ParseHashes {
@@ -87,17 +124,23 @@ impl Parse for ParseUnderline<'_> {
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
         let mut input = input.start();
 
+        // in CommonMark, a single `-` or `=` is enough
+        let min_len = match input.dialect {
+            Dialect::Unidok => 2,
+            Dialect::CommonMark if has_code_indent(input.rest()) => return None,
+            Dialect::CommonMark => 1,
+        };
         input.parse_i(ParseSpaces);
 
-        let u = if input.parse("--").is_some() {
-            input.parse_i(While('-'));
-            Underline::Single
-        } else if input.parse("==").is_some() {
-            input.parse_i(While('='));
-            Underline::Double
-        } else {
-            return None;
+        let u = match input.peek_char() {
+            Some('-') => Underline::Single,
+            Some('=') => Underline::Double,
+            _ => return None,
         };
+        let c = if u == Underline::Single { '-' } else { '=' };
+        if input.parse_i(While(c)).len() < min_len {
+            return None;
+        }
 
         input.parse_i(ParseSpaces);
         input.parse(ParseLineEnd)?;
@@ -0,0 +1,202 @@
+use unidok_repr::ast::html::{HtmlNodeAst, RawHtmlAst};
+use unidok_repr::config::Dialect;
+
+use crate::html::raw::{closing_tag_len, open_tag_len, tag_name_len};
+use crate::utils::{is_blank_line, ParseLineBreak, ParseSpaces, Until};
+use crate::{Indents, Input, Parse};
+
+/// Parses an HTML block, which is passed through verbatim. This is only
+/// supported in the CommonMark dialect.
+pub(crate) struct ParseHtmlBlock<'a> {
+    pub ind: Indents<'a>,
+}
+
+impl Parse for ParseHtmlBlock<'_> {
+    type Output = HtmlNodeAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        if input.dialect != Dialect::CommonMark {
+            return None;
+        }
+        let mut input = input.start();
+
+        let start = input.prev_slice_bytes(0);
+        let len = input.len();
+        input.parse_i(ParseSpaces);
+        let kind = HtmlBlockKind::from_line(input.rest())?;
+
+        let mut text = input.text[start.start()..start.start() + len - input.len()].to_string();
+        loop {
+            let line = input.parse_i(Until(|c| matches!(c, '\n' | '\r')));
+            let line = &input[line];
+            text.push_str(line);
+
+            if kind.is_end(line) || input.is_empty() {
+                break;
+            }
+            let mut input2 = input.start();
+            if input2.parse(ParseLineBreak(self.ind)).is_none()
+                || kind.ends_at_blank_line() && is_blank_line(input2.rest())
+            {
+                break;
+            }
+            input2.apply();
+            text.push('\n');
+        }
+
+        let span = input.apply();
+        Some(HtmlNodeAst::Raw(RawHtmlAst { text, span }))
+    }
+}
+
+/// Returns whether an HTML block starts here that can interrupt a paragraph
+pub(crate) fn can_interrupt_paragraph(input: &Input) -> bool {
+    let rest = input.rest().trim_start_matches(' ');
+    !matches!(HtmlBlockKind::from_line(rest), Some(HtmlBlockKind::CompleteTag) | None)
+}
+
+/// The 7 kinds of HTML blocks in CommonMark, which differ in how they end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HtmlBlockKind {
+    /// `<script>`, `<pre>` or `<style>`
+    Verbatim,
+    Comment,
+    ProcessingInstruction,
+    Declaration,
+    CData,
+    /// An opening or closing tag of a block-level element
+    BlockTag,
+    /// Any other complete tag that is the only thing on the line
+    CompleteTag,
+}
+
+impl HtmlBlockKind {
+    fn from_line(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix('<')?;
+
+        if let Some(len) = tag_name_len(rest) {
+            let name = rest[..len].to_ascii_lowercase();
+            let after = &rest[len..];
+            if VERBATIM_TAGS.contains(&name.as_str())
+                && (after.is_empty() || after.starts_with([' ', '\t', '\n', '\r', '>']))
+            {
+                return Some(HtmlBlockKind::Verbatim);
+            }
+        }
+        if rest.starts_with("!--") {
+            Some(HtmlBlockKind::Comment)
+        } else if rest.starts_with('?') {
+            Some(HtmlBlockKind::ProcessingInstruction)
+        } else if rest.starts_with("![CDATA[") {
+            Some(HtmlBlockKind::CData)
+        } else if rest.starts_with('!') && rest[1..].starts_with(|c: char| c.is_ascii_uppercase()) {
+            Some(HtmlBlockKind::Declaration)
+        } else if is_block_tag(rest.strip_prefix('/').unwrap_or(rest)) {
+            Some(HtmlBlockKind::BlockTag)
+        } else {
+            let len = open_tag_len(line).or_else(|| closing_tag_len(line))?;
+            if is_blank_line(&line[len..]) && !line[..len].contains(['\n', '\r']) {
+                Some(HtmlBlockKind::CompleteTag)
+            } else {
+                None
+            }
+        }
+    }
+
+    fn is_end(self, line: &str) -> bool {
+        match self {
+            HtmlBlockKind::Verbatim => {
+                let line = line.to_ascii_lowercase();
+                VERBATIM_TAGS.iter().any(|tag| line.contains(&format!("</{}>", tag)))
+            }
+            HtmlBlockKind::Comment => line.contains("-->"),
+            HtmlBlockKind::ProcessingInstruction => line.contains("?>"),
+            HtmlBlockKind::Declaration => line.contains('>'),
+            HtmlBlockKind::CData => line.contains("]]>"),
+            HtmlBlockKind::BlockTag | HtmlBlockKind::CompleteTag => false,
+        }
+    }
+
+    fn ends_at_blank_line(self) -> bool {
+        matches!(self, HtmlBlockKind::BlockTag | HtmlBlockKind::CompleteTag)
+    }
+}
+
+fn is_block_tag(s: &str) -> bool {
+    let len = match tag_name_len(s) {
+        Some(len) => len,
+        None => return false,
+    };
+    let after = &s[len..];
+    BLOCK_TAGS.contains(&s[..len].to_ascii_lowercase().as_str())
+        && (after.is_empty()
+            || after.starts_with([' ', '\t', '\n', '\r', '>'])
+            || after.starts_with("/>"))
+}
+
+const VERBATIM_TAGS: &[&str] = &["script", "pre", "style"];
+
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "section",
+    "source",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
@@ -0,0 +1,129 @@
+use aho_corasick::AhoCorasick;
+use unidok_repr::ast::blocks::{LabeledItemAst, LabeledListAst};
+use unidok_repr::ast::segments::SegmentAst;
+use unidok_repr::Span;
+
+use crate::inlines::segments::strip_space_end;
+use crate::inlines::Segments;
+use crate::parsing_mode::ParsingMode;
+use crate::state::ParsingState;
+use crate::utils::{ParseLineBreak, ParseLineEnd, ParseNSpaces, ParseSpaces};
+use crate::{Context, Indents, Input, Parse};
+
+use super::{
+    ParseBlock, ParseCodeBlock, ParseComment, ParseHeading, ParseList, ParseTable,
+    ParseThematicBreak,
+};
+
+/// Parses a labeled list. Each item starts with a label followed by `::`. The
+/// content of an item can start on the same line, and continues on lines
+/// indented by 2 spaces.
+pub(crate) struct ParseLabeledList<'a> {
+    pub ind: Indents<'a>,
+    pub mode: Option<ParsingMode>,
+    pub ac: &'a AhoCorasick,
+}
+
+impl Parse for ParseLabeledList<'_> {
+    type Output = LabeledListAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        let (mut label, mut marker) = input.parse(self.label_parser())?;
+
+        let mut items = Vec::new();
+        loop {
+            let ind = self.ind.push_indent(2);
+            let content_parser =
+                ParseBlock::new_multi(self.mode, ParsingState::new(ind, Context::Global, self.ac));
+            let content = input.parse(content_parser)?;
+            items.push(LabeledItemAst { label, marker, content });
+
+            if input.parse(ParseLineBreak(self.ind)).is_none()
+                || self.starts_other_block(&mut input)
+            {
+                break;
+            }
+
+            let mut input2 = input.start();
+            if let Some((l, m)) = input2.parse(self.label_parser()) {
+                label = l;
+                marker = m;
+                input2.apply();
+                continue;
+            }
+            break;
+        }
+
+        input.apply();
+        Some(LabeledListAst { items })
+    }
+
+    fn can_parse(&mut self, input: &mut Input) -> bool {
+        input.can_parse(self.label_parser())
+    }
+}
+
+impl ParseLabeledList<'_> {
+    fn label_parser(&self) -> ParseLabel<'_> {
+        ParseLabel { ind: self.ind, mode: self.mode, ac: self.ac }
+    }
+
+    /// Returns whether the line starts a block that takes precedence over
+    /// labeled lists, e.g. `- foo:: bar`
+    fn starts_other_block(&self, input: &mut Input) -> bool {
+        use ParsingMode as P;
+
+        let (ind, ac) = (self.ind, self.ac);
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
+
+        mode.is(P::COMMENTS) && input.can_parse(ParseComment)
+            || mode.is(P::THEMATIC_BREAKS) && input.can_parse(ParseThematicBreak)
+            || mode.is(P::CODE_BLOCKS) && input.can_parse(ParseCodeBlock { ind, mode: None, ac })
+            || mode.is(P::TABLES) && input.can_parse(ParseTable { ind, ac })
+            || mode.is(P::HEADINGS) && input.can_parse(ParseHeading { ind, ac })
+            || input.can_parse(ParseList { ind, mode: None, ac })
+    }
+}
+
+/// Parses the label of a labeled list item and the `::` after it. The `::`
+/// must be followed by a space or the end of the line.
+struct ParseLabel<'a> {
+    ind: Indents<'a>,
+    mode: Option<ParsingMode>,
+    ac: &'a AhoCorasick,
+}
+
+impl Parse for ParseLabel<'_> {
+    type Output = (Vec<SegmentAst>, Span);
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let line_len = input.rest().find(['\n', '\r']).unwrap_or_else(|| input.len());
+        if !input.rest()[..line_len].contains("::") {
+            return None;
+        }
+
+        let mut input = input.start();
+
+        input.parse_i(ParseSpaces);
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
+        let mut label = input
+            .parse(Segments::parser(self.ind, Context::Label, mode, self.ac))?
+            .into_segments_no_underline()?;
+        if let Some(last) = label.last_mut() {
+            strip_space_end(last, &input);
+        }
+
+        let start = Span::from(input.prev_slice_bytes(0));
+        input.parse("::")?;
+        let marker = start.until(input.prev_slice_bytes(0).into());
+
+        if input.parse(ParseNSpaces(1)).is_none() && !input.can_parse(ParseLineEnd) {
+            return None;
+        }
+
+        input.apply();
+        Some((label, marker))
+    }
+}
@@ -1,7 +1,11 @@
+use detached_str::StrSlice;
 use unidok_repr::ast::blocks::LinkRefDef;
+use unidok_repr::config::Dialect;
 
+use crate::inlines::link_destinations::{ParseLinkDestination, ParseLinkTitle, ParseLinkWs};
+use crate::input::ModifyInput;
 use crate::utils::{
-    is_ws, ParseLineBreak, ParseLineEnd, ParseSpacesU8, QuotedStringWithEscapes, Until,
+    is_ws, ParseLineBreak, ParseLineEnd, ParseSpaces, ParseSpacesU8, QuotedStringWithEscapes, Until,
 };
 use crate::{Indents, Input, Parse};
 
@@ -16,6 +20,9 @@ impl Parse for ParseLinkRefDef<'_> {
         let mut input = input.start();
 
         input.try_parse(ParseSpacesU8);
+        if input.dialect == Dialect::CommonMark {
+            return parse_commonmark(input, self.ind);
+        }
         input.parse('[')?;
         let name = input.parse_i(Until(|c| matches!(c, '\n' | '\r' | ']')));
         input.parse("]:")?;
@@ -44,3 +51,80 @@ impl Parse for ParseLinkRefDef<'_> {
         Some(lrd)
     }
 }
+
+/// Parses a link reference definition in the CommonMark dialect, where the
+/// label and title can span multiple lines and the URL can be enclosed in
+/// angle brackets
+fn parse_commonmark(mut input: ModifyInput<'_>, ind: Indents<'_>) -> Option<LinkRefDef> {
+    input.parse('[')?;
+    let name = input.parse(ParseLinkLabel)?;
+    input.parse("]:")?;
+    input.parse(ParseLinkWs)?;
+
+    let angle_brackets = input.can_parse('<');
+    let len = input.len();
+    input.parse(ParseLinkDestination)?;
+    let url = input.prev_slice_bytes(len - input.len());
+    let url = if angle_brackets { url.get(1..url.len() - 1) } else { url };
+    if url.is_empty() && !angle_brackets {
+        return None;
+    }
+
+    let mut input2 = input.start();
+    let title = match input2.parse(ParseLinkWs) {
+        Some(true) => input2.parse(ParseLinkTitle),
+        _ => None,
+    };
+    input2.parse_i(ParseSpaces);
+    let title = if title.is_some() && input2.can_parse(ParseLineEnd) {
+        input2.apply();
+        title
+    } else {
+        // the title is invalid, so it isn't part of the definition
+        drop(input2);
+        input.parse_i(ParseSpaces);
+        input.parse(ParseLineEnd)?;
+        None
+    };
+    input.try_parse(ParseLineBreak(ind));
+
+    input.apply();
+    Some(LinkRefDef { name, url, title })
+}
+
+/// Parses the label of a link reference definition, which must contain a
+/// non-whitespace character and may not contain unescaped brackets or blank
+/// lines
+struct ParseLinkLabel;
+
+impl Parse for ParseLinkLabel {
+    type Output = StrSlice;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+        let len = input.len();
+
+        loop {
+            match input.peek_char()? {
+                ']' => break,
+                '[' => return None,
+                '\\' if input.rest()[1..].starts_with(['[', ']', '\\']) => {
+                    input.bump(2);
+                }
+                '\n' | '\r' => {
+                    input.parse(ParseLinkWs)?;
+                }
+                c => {
+                    input.bump(c.len_utf8());
+                }
+            }
+        }
+        let label = input.prev_slice_bytes(len - input.len());
+        if label.len() > 999 || input[label].trim().is_empty() {
+            return None;
+        }
+
+        input.apply();
+        Some(label)
+    }
+}
@@ -1,5 +1,7 @@
 use aho_corasick::AhoCorasick;
-use unidok_repr::ast::blocks::{Bullet, ListAst};
+use unidok_repr::ast::blocks::{Bullet, ListAst, TaskMarker};
+use unidok_repr::config::Dialect;
+use unidok_repr::Span;
 
 use crate::parsing_mode::ParsingMode;
 use crate::state::ParsingState;
@@ -20,24 +22,36 @@ impl Parse for ParseList<'_> {
     fn parse(&mut self, input: &mut crate::Input) -> Option<Self::Output> {
         let mut input = input.start();
 
-        let (mut indent_spaces, bullet) = input.parse(ParseBullet { first: true })?;
+        let (mut indent_spaces, bullet, first_bullet) = input.parse(ParseBullet { first: true })?;
 
         let mut items = Vec::new();
+        let mut bullets = vec![first_bullet];
+        let mut tasks = Vec::new();
+        let mut loose = false;
         loop {
             let ind = self.ind.push_indent(indent_spaces);
+            tasks.push(input.parse(ParseTaskMarker));
 
-            let content_parser =
+            let mut content_parser =
                 ParseBlock::new_multi(self.mode, ParsingState::new(ind, Context::Global, self.ac));
-            items.push(input.parse(content_parser)?);
+            let (item, blank_lines) = content_parser.parse_with_blank_lines(&mut input)?;
+            items.push(item);
+            // a blank line after the last item doesn't make the list loose
+            if blank_lines[..blank_lines.len().saturating_sub(1)].contains(&true) {
+                loose = true;
+            }
+            let last_blank_line = blank_lines.last() == Some(&true);
 
             if input.parse(ParseLineBreak(self.ind)).is_none() {
                 break;
             }
 
             let mut input2 = input.start();
-            if let Some((is, b)) = input2.parse(ParseBullet { first: false }) {
+            if let Some((is, b, span)) = input2.parse(ParseBullet { first: false }) {
                 if b.kind() == bullet.kind() {
+                    loose |= last_blank_line;
                     indent_spaces = is;
+                    bullets.push(span);
                     input2.apply();
                     continue;
                 }
@@ -45,8 +59,15 @@ impl Parse for ParseList<'_> {
             break;
         }
 
+        // in Unidok, lists are only loose when the `@LOOSE` macro is used
+        let loose = loose && input.dialect == Dialect::CommonMark;
+
         input.apply();
-        Some(ListAst { indent_spaces, bullet, items })
+        Some(ListAst { indent_spaces, bullet, items, bullets, tasks, loose })
+    }
+
+    fn can_parse(&mut self, input: &mut crate::Input) -> bool {
+        input.can_parse(ParseBullet { first: true })
     }
 }
 
@@ -56,7 +77,7 @@ struct ParseBullet {
 }
 
 impl Parse for ParseBullet {
-    type Output = (u8, Bullet);
+    type Output = (u8, Bullet, Span);
 
     fn parse(&mut self, input: &mut crate::Input) -> Option<Self::Output> {
         let mut input = input.start();
@@ -65,8 +86,9 @@ impl Parse for ParseBullet {
         if indent > (u8::MAX - 16) {
             return None;
         }
+        let start = Span::from(input.prev_slice_bytes(0));
 
-        let result = match input.peek_char() {
+        let (indent, bullet) = match input.peek_char() {
             Some('-') => {
                 input.bump(1);
                 (indent + 2, Bullet::Dash)
@@ -98,12 +120,39 @@ impl Parse for ParseBullet {
             }
             _ => return None,
         };
+        let span = start.until(input.prev_slice_bytes(0).into());
 
         if input.parse(ParseNSpaces(1)).is_none() && !input.can_parse(ParseLineEnd) {
             return None;
         }
 
         input.apply();
-        Some(result)
+        Some((indent, bullet, span))
+    }
+}
+
+/// Parses the checkbox of a task list item, `[ ]` or `[x]`, followed by a space
+struct ParseTaskMarker;
+
+impl Parse for ParseTaskMarker {
+    type Output = TaskMarker;
+
+    fn parse(&mut self, input: &mut crate::Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        let start = Span::from(input.prev_slice_bytes(0));
+        input.parse('[')?;
+        let checked = match input.peek_char()? {
+            ' ' => false,
+            'x' | 'X' => true,
+            _ => return None,
+        };
+        input.bump(1);
+        input.parse(']')?;
+        let span = start.until(input.prev_slice_bytes(0).into());
+        input.parse(ParseNSpaces(1))?;
+
+        input.apply();
+        Some(TaskMarker { checked, span })
     }
 }
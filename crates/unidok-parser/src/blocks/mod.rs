@@ -1,7 +1,10 @@
 mod blocks_impl;
 mod code_blocks;
 mod comments;
+mod footnote_defs;
 mod headings;
+pub(crate) mod html_blocks;
+mod labeled_lists;
 mod link_ref_defs;
 mod lists;
 mod quotes;
@@ -9,9 +12,12 @@ mod tables;
 mod thematic_breaks;
 
 pub(crate) use blocks_impl::ParseBlock;
-pub(crate) use code_blocks::ParseCodeBlock;
+pub(crate) use code_blocks::{ParseCodeBlock, ParseIndentedCodeBlock};
 pub(crate) use comments::ParseComment;
+pub(crate) use footnote_defs::ParseFootnoteDef;
 pub(crate) use headings::{ParseHeading, Underline};
+pub(crate) use html_blocks::ParseHtmlBlock;
+pub(crate) use labeled_lists::ParseLabeledList;
 pub(crate) use link_ref_defs::ParseLinkRefDef;
 pub(crate) use lists::ParseList;
 pub(crate) use quotes::ParseQuote;
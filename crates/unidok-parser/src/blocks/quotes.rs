@@ -1,9 +1,10 @@
 use aho_corasick::AhoCorasick;
 use unidok_repr::ast::blocks::QuoteAst;
+use unidok_repr::config::Dialect;
 
 use crate::parsing_mode::ParsingMode;
 use crate::state::ParsingState;
-use crate::utils::{ParseQuoteMarker, ParseSpacesU8};
+use crate::utils::{ParseOneWS, ParseQuoteMarker, ParseSpacesU8};
 use crate::{Context, Indents, Input, Parse};
 
 use super::ParseBlock;
@@ -20,9 +21,18 @@ impl Parse for ParseQuote<'_> {
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
         let mut input = input.start();
 
-        let ind = self.ind.push_indent(input.parse(ParseSpacesU8)?);
+        let spaces = input.parse(ParseSpacesU8)?;
+        // in CommonMark, the indentation of the `>` may differ between lines,
+        // and one space after it is part of the marker
+        let ind = match input.dialect {
+            Dialect::Unidok => self.ind.push_indent(spaces),
+            Dialect::CommonMark => self.ind,
+        };
 
         input.parse(ParseQuoteMarker)?;
+        if input.dialect == Dialect::CommonMark {
+            input.try_parse(ParseOneWS);
+        }
         let ind = ind.push_quote();
 
         let content = input.parse(ParseBlock::new_multi(
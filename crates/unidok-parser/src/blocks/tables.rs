@@ -1,9 +1,15 @@
 use aho_corasick::AhoCorasick;
-use unidok_repr::ast::blocks::{CellAlignment, CellMetaAst, TableAst, TableCellAst, TableRowAst};
+use unidok_repr::ast::blocks::{
+    BlockAst, CellAlignment, CellContentAst, CellMetaAst, TableAst, TableCellAst, TableRowAst,
+};
+use unidok_repr::Span;
 
+use crate::blocks::ParseBlock;
 use crate::inlines::Segments;
+use crate::macros::utils::ParseOpeningBrace;
 use crate::parsing_mode::ParsingMode;
-use crate::utils::{is_ws, ParseLineBreak, ParseLineEnd, ParseSpacesU8, While};
+use crate::state::ParsingState;
+use crate::utils::{is_ws, ParseLineBreak, ParseLineEnd, ParseSpaces, ParseSpacesU8, While};
 use crate::{Context, Indents, Input, Parse, ParseInfallible};
 
 pub(crate) struct ParseTable<'a> {
@@ -66,27 +72,36 @@ impl Parse for ParseTableRow<'_> {
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
         let mut input = input.start();
+        let start = Span::from(input.prev_slice_bytes(0));
         let is_header_row = input.parse('#').is_some();
         input.parse("||")?;
 
         let mut contents = Vec::new();
 
         loop {
+            let meta_start = Span::from(input.prev_slice_bytes(0));
             let meta = input.parse_i(ParseCellMeta);
-            let segments = if matches!(input.peek_char(), Some('\n' | '\r') | None) {
-                vec![]
+            let meta_span = meta_start.until(input.prev_slice_bytes(0).into());
+            let content = if matches!(input.peek_char(), Some('\n' | '\r') | None) {
+                CellContentAst::Inline(vec![])
+            } else if let Some(blocks) = input.parse(ParseBlockCell { ind: self.ind, ac: self.ac })
+            {
+                CellContentAst::Blocks(blocks)
             } else {
-                input
-                    .parse(Segments::parser(
-                        self.ind,
-                        Context::Table,
-                        ParsingMode::new_all(),
-                        self.ac,
-                    ))?
-                    .into_segments_no_underline()?
+                CellContentAst::Inline(
+                    input
+                        .parse(Segments::parser(
+                            self.ind,
+                            Context::Table,
+                            ParsingMode::new_all(),
+                            self.ac,
+                        ))?
+                        .into_segments_no_underline()?,
+                )
             };
 
-            contents.push(TableCellAst { meta, segments });
+            let span = meta_span.until(input.prev_slice_bytes(0).into());
+            contents.push(TableCellAst { meta, content, meta_span, span });
 
             if input.parse(ParseLineEnd).is_some() {
                 let mut input2 = input.start();
@@ -106,8 +121,42 @@ impl Parse for ParseTableRow<'_> {
             input.parse('|')?;
         }
 
+        let span = start.until(input.prev_slice_bytes(0).into());
         input.apply();
-        Some(TableRowAst { is_header_row, cells: contents })
+        Some(TableRowAst { is_header_row, cells: contents, span })
+    }
+}
+
+/// Parses the content of a table cell that starts with a `{` at the end of the
+/// line. It contains blocks until a `}` on its own line. The line end after the
+/// `}` isn't consumed, so the row can continue on the next line.
+struct ParseBlockCell<'a> {
+    ind: Indents<'a>,
+    ac: &'a AhoCorasick,
+}
+
+impl Parse for ParseBlockCell<'_> {
+    type Output = Vec<BlockAst>;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse_i(ParseSpaces);
+        input.parse(ParseOpeningBrace(self.ind))?;
+        let blocks = input.parse(ParseBlock::new_multi(
+            None,
+            ParsingState::new(self.ind, Context::BlockBraces, self.ac),
+        ))?;
+
+        input.parse_i(ParseSpaces);
+        input.parse('}')?;
+        input.parse_i(ParseSpaces);
+        if !input.can_parse(ParseLineEnd) {
+            return None;
+        }
+
+        input.apply();
+        Some(blocks)
     }
 }
 
@@ -175,7 +224,7 @@ impl Parse for ParseRowsAndColumns {
             }
         }
 
-        if input.rest().starts_with(is_ws) {
+        if let Some(' ' | '\t' | '\n' | '\r') | None = input.peek_char() {
             input.apply();
             Some((col_span.unwrap_or(1), row_span.unwrap_or(1)))
         } else {
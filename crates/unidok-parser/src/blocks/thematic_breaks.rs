@@ -1,14 +1,14 @@
 use unidok_repr::ast::blocks::{ThematicBreakAst, ThematicBreakKind};
+use unidok_repr::config::Dialect;
 
+use crate::input::ModifyInput;
 use crate::utils::{ParseLineEnd, ParseSpaces, While};
-use crate::{Indents, Input, Parse};
+use crate::{Input, Parse};
 
 #[derive(Debug, Default, Clone, Copy)]
-pub(crate) struct ParseThematicBreak<'a> {
-    pub ind: Indents<'a>,
-}
+pub(crate) struct ParseThematicBreak;
 
-impl Parse for ParseThematicBreak<'_> {
+impl Parse for ParseThematicBreak {
     type Output = ThematicBreakAst;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
@@ -16,6 +16,10 @@ impl Parse for ParseThematicBreak<'_> {
 
         input.parse_i(ParseSpaces);
 
+        if input.dialect == Dialect::CommonMark {
+            return parse_commonmark(input);
+        }
+
         let (kind, parser) = if input.parse("***").is_some() {
             (ThematicBreakKind::Stars, While('*'))
         } else if input.parse("___").is_some() {
@@ -35,13 +39,35 @@ impl Parse for ParseThematicBreak<'_> {
     }
 }
 
+/// In CommonMark, the characters can be separated by spaces, e.g. `* * *`
+fn parse_commonmark(mut input: ModifyInput<'_>) -> Option<ThematicBreakAst> {
+    let (kind, c) = match input.peek_char()? {
+        '*' => (ThematicBreakKind::Stars, '*'),
+        '_' => (ThematicBreakKind::Underscores, '_'),
+        '-' => (ThematicBreakKind::Dashes, '-'),
+        _ => return None,
+    };
+    let mut len = 0;
+    while input.parse(c).is_some() {
+        len += 1;
+        input.parse_i(ParseSpaces);
+    }
+    if len < 3 {
+        return None;
+    }
+    input.parse(ParseLineEnd)?;
+
+    input.apply();
+    Some(ThematicBreakAst { len, kind })
+}
+
 #[test]
 fn test_hr() {
     use crate::utils::ParseLineBreak;
     use ThematicBreakKind::*;
 
     let mut input = Input::new("  *******   \n    ---\n**\n___");
-    let parser = ParseThematicBreak::default();
+    let parser = ParseThematicBreak;
 
     assert_eq!(input.parse(parser), Some(ThematicBreakAst { len: 7, kind: Stars }));
     input.parse(ParseLineBreak::default()).unwrap();
@@ -0,0 +1,250 @@
+use unidok_repr::diagnostics::{Diagnostic, DiagnosticCode};
+use unidok_repr::metadata::{MetaValue, Metadata};
+use unidok_repr::Span;
+
+use crate::Input;
+
+/// Parses a front-matter block at the start of the document. It starts and
+/// ends with a line containing only `---`. The closing line can also be
+/// `...`. Each line in between contains either a `key: value` or a
+/// `key = value` pair. A key with an empty value can be followed by list
+/// items starting with `-`:
+///
+/// ```text
+/// ---
+/// title: "My document"
+/// draft = false
+/// tags: [foo, bar]
+/// authors:
+///   - Jane Doe
+///   - John Doe
+/// ---
+/// ```
+///
+/// The block must contain at least one of the [`KNOWN_KEYS`], so text between
+/// two thematic breaks isn't mistaken for front matter. Other keys are only
+/// available to templates, so they produce a warning.
+///
+/// Returns the span of the block if it exists. If there is no closing line,
+/// no known key, or a line in between isn't a valid `key: value` pair, list
+/// item or comment, nothing is consumed, so the `---` is parsed as a thematic
+/// break.
+pub(crate) fn parse_front_matter(
+    input: &mut Input,
+    metadata: &mut Metadata,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Span> {
+    let rest = input.rest();
+    let offset = input.idx();
+
+    let mut lines = rest.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+
+    let mut end = first.len();
+    let mut body = Vec::new();
+    loop {
+        let line = lines.next()?;
+        match line.trim_end() {
+            "---" | "..." => {
+                end += line.len();
+                break;
+            }
+            _ => body.push((offset + end, line)),
+        }
+        end += line.len();
+    }
+
+    let mut entries = Vec::new();
+    let mut list: Option<(&str, Vec<MetaValue>)> = None;
+    let mut unknown_keys = Vec::new();
+
+    for (line_start, line) in body {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix('-') {
+            if let Some((_, items)) = &mut list {
+                items.push(parse_value(item.trim())?);
+                continue;
+            }
+        }
+
+        if let Some((key, items)) = list.take() {
+            entries.push(finish_list(key, items));
+        }
+
+        let sep = trimmed.find([':', '='])?;
+        let key = trimmed[..sep].trim();
+        let value = trimmed[sep + 1..].trim();
+
+        if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-')) {
+            return None;
+        }
+        if !KNOWN_KEYS.contains(&key) {
+            let start = line_start + (line.len() - line.trim_start().len());
+            unknown_keys.push((key, Span { start: start as u32, end: (start + key.len()) as u32 }));
+        }
+
+        if value.is_empty() {
+            list = Some((key, Vec::new()));
+        } else {
+            entries.push((key, parse_value(value)?));
+        }
+    }
+
+    if let Some((key, items)) = list {
+        entries.push(finish_list(key, items));
+    }
+
+    if entries.len() == unknown_keys.len() {
+        return None;
+    }
+    for (key, span) in unknown_keys {
+        diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::UnknownMetadataKey,
+            format!("unknown metadata key `{}`, it is only available to templates", key),
+            span,
+        ));
+    }
+    for (key, value) in entries {
+        metadata.insert(key, value);
+    }
+    let block = input.bump(end);
+    Some(block.into())
+}
+
+/// The metadata keys used by Unidok and common static site generators
+pub(crate) const KNOWN_KEYS: &[&str] = &[
+    "title",
+    "description",
+    "author",
+    "authors",
+    "date",
+    "tags",
+    "keywords",
+    "lang",
+    "stylesheet",
+    "draft",
+];
+
+fn finish_list(key: &str, items: Vec<MetaValue>) -> (&str, MetaValue) {
+    if items.is_empty() {
+        (key, MetaValue::String(String::new()))
+    } else {
+        (key, MetaValue::List(items))
+    }
+}
+
+fn parse_value(value: &str) -> Option<MetaValue> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let s = rest.strip_suffix('"')?;
+        let mut result = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next()? {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    c => result.push(c),
+                },
+                '"' => return None,
+                c => result.push(c),
+            }
+        }
+        Some(MetaValue::String(result))
+    } else if let Some(rest) = value.strip_prefix('\'') {
+        let s = rest.strip_suffix('\'')?;
+        Some(MetaValue::String(s.replace("''", "'")))
+    } else if let Some(rest) = value.strip_prefix('[') {
+        let s = rest.strip_suffix(']')?.trim();
+        if s.is_empty() {
+            return Some(MetaValue::List(vec![]));
+        }
+        let items = split_list(s).into_iter().map(|item| parse_value(item.trim()));
+        Some(MetaValue::List(items.collect::<Option<_>>()?))
+    } else if value.is_empty() || value.contains([']', '{', '}']) {
+        None
+    } else {
+        Some(MetaValue::from_word(value))
+    }
+}
+
+/// Splits a list at commas that aren't in quotes or nested lists
+fn split_list(s: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut quote = None;
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('[', None) => depth += 1,
+            (']', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                result.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(&s[start..]);
+    result
+}
+
+#[test]
+fn test_front_matter() {
+    let text =
+        "---\ntitle: \"A \\\"title\\\"\"\ndraft = false\nversion: 1.5\ntags: [foo, 'bar, baz']\n\
+                authors:\n  - Jane\n  - John\n# comment\n---\n# Heading";
+    let mut input = Input::new(text);
+    let mut metadata = Metadata::new();
+    let mut diagnostics = Vec::new();
+
+    let span = parse_front_matter(&mut input, &mut metadata, &mut diagnostics);
+    assert_eq!(span, Some(Span { start: 0, end: text.len() as u32 - 9 }));
+    assert_eq!(input.rest(), "# Heading");
+
+    let diagnostics: Vec<_> = diagnostics.iter().map(|d| (d.code, d.span)).collect();
+    assert_eq!(diagnostics, [(DiagnosticCode::UnknownMetadataKey, Span { start: 39, end: 46 })]);
+    assert_eq!(metadata.get_float("version"), Some(1.5));
+
+    assert_eq!(metadata.title(), Some("A \"title\""));
+    assert_eq!(metadata.get_bool("draft"), Some(false));
+    assert_eq!(metadata.get_float("version"), Some(1.5));
+    assert_eq!(
+        metadata.get_list("tags"),
+        Some(&[MetaValue::String("foo".into()), MetaValue::String("bar, baz".into())][..])
+    );
+    assert_eq!(
+        metadata.get_list("authors"),
+        Some(&[MetaValue::String("Jane".into()), MetaValue::String("John".into())][..])
+    );
+}
+
+#[test]
+fn test_front_matter_invalid() {
+    let mut metadata = Metadata::new();
+    let mut diagnostics = Vec::new();
+
+    for text in [
+        "---\ntitle: [foo\n---\n",
+        "---\nHello world\n---\n\nText",
+        "---\ntitle: foo\n",
+        // without a known key, this is text between thematic breaks
+        "---\nNote: see below\n---\n",
+    ] {
+        let mut input = Input::new(text);
+        assert_eq!(parse_front_matter(&mut input, &mut metadata, &mut diagnostics), None);
+        assert_eq!(input.rest(), text);
+    }
+    assert!(metadata.is_empty());
+    assert!(diagnostics.is_empty());
+}
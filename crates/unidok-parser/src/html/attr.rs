@@ -23,6 +23,7 @@ impl Parse for ParseAttribute<'_> {
             let value = input.parse(QuotedString(self.ind)).or_else(|| {
                 input.parse(ParseAttrName).map(|s| s.to_str(&input.text).to_string())
             })?;
+            input.parse_i(ParseWs(self.ind));
 
             Some(value)
         } else {
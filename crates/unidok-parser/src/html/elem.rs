@@ -11,6 +11,7 @@ use crate::{Context, Indents, Input, Parse};
 
 use super::attr::ParseAttributes;
 use super::elem_name::ParseElemName;
+use super::foreign::ParseForeignContent;
 
 pub(crate) struct ParseHtmlElem<'a> {
     pub ind: Indents<'a>,
@@ -19,7 +20,7 @@ pub(crate) struct ParseHtmlElem<'a> {
 }
 
 impl ParseHtmlElem<'_> {
-    pub(crate) fn closing_tag(elem: ElemName) -> ParseClosingTag {
+    pub(crate) fn closing_tag(elem: &ElemName) -> ParseClosingTag<'_> {
         ParseClosingTag { elem }
     }
 }
@@ -32,21 +33,39 @@ impl Parse for ParseHtmlElem<'_> {
 
         input.parse('<')?;
         let name = input.parse(ParseElemName)?;
+        let name_span = input.prev_slice_bytes(name.as_str().len());
         input.parse_i(ParseSpaces);
 
         let attrs = input.parse(ParseAttributes { ind: self.ind })?;
 
         if input.parse("/>").is_some() {
             input.apply();
-            Some(HtmlElemAst { name, attrs, content: None, close: ElemClose::SelfClosing })
+            Some(HtmlElemAst {
+                name,
+                name_span,
+                attrs,
+                content: None,
+                close: ElemClose::SelfClosing,
+            })
         } else if name.is_self_closing() {
             input.parse('>')?;
             input.apply();
-            Some(HtmlElemAst { name, attrs, close: ElemClose::AutoSelfClosing, content: None })
+            Some(HtmlElemAst {
+                name,
+                name_span,
+                attrs,
+                close: ElemClose::AutoSelfClosing,
+                content: None,
+            })
         } else {
             input.parse('>')?;
 
-            let content = if name.contains_plaintext() {
+            let closed;
+            let content = if name.contains_foreign_content() {
+                let content = input.parse(ParseForeignContent { ind: self.ind })?;
+                closed = input.parse(ParseClosingTag { elem: &name }).is_some();
+                ElemContentAst::Inline(content)
+            } else if name.contains_plaintext() {
                 let mut input2 = input.start();
                 let mut content = String::new();
                 loop {
@@ -55,7 +74,7 @@ impl Parse for ParseHtmlElem<'_> {
 
                     match input2.peek_char() {
                         Some('<') => {
-                            if input2.can_parse(ParseClosingTag { elem: name }) {
+                            if input2.can_parse(ParseClosingTag { elem: &name }) {
                                 break;
                             } else {
                                 input2.bump(1);
@@ -74,16 +93,16 @@ impl Parse for ParseHtmlElem<'_> {
                     }
                 }
                 input2.apply();
-                input.try_parse(ParseClosingTag { elem: name });
+                closed = input.parse(ParseClosingTag { elem: &name }).is_some();
                 ElemContentAst::Verbatim(content)
             } else if name.must_contain_blocks()
                 || (name.can_contain_blocks() && input.parse(ParseLineBreak(self.ind)).is_some())
             {
                 let blocks = input.parse(ParseBlock::new_multi(
                     self.mode,
-                    ParsingState::new(self.ind, Context::BlockHtml(name), self.ac),
+                    ParsingState::new(self.ind, Context::BlockHtml(&name), self.ac),
                 ))?;
-                input.try_parse(ParseClosingTag { elem: name });
+                closed = input.parse(ParseClosingTag { elem: &name }).is_some();
                 ElemContentAst::Blocks(blocks)
             } else {
                 let nl = if input.can_parse(ParseLineEnd) {
@@ -93,15 +112,11 @@ impl Parse for ParseHtmlElem<'_> {
                     false
                 };
 
+                let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
                 let mut segments = input
-                    .parse(Segments::parser(
-                        self.ind,
-                        Context::InlineHtml(name),
-                        self.mode.unwrap_or_else(ParsingMode::new_all),
-                        self.ac,
-                    ))?
+                    .parse(Segments::parser(self.ind, Context::InlineHtml(&name), mode, self.ac))?
                     .into_segments_no_underline_zero()?;
-                input.try_parse(ParseClosingTag { elem: name });
+                closed = input.parse(ParseClosingTag { elem: &name }).is_some();
 
                 if nl && matches!(segments.last(), Some(SegmentAst::LineBreak)) {
                     segments.pop();
@@ -112,16 +127,17 @@ impl Parse for ParseHtmlElem<'_> {
             let content = Some(content);
 
             input.apply();
-            Some(HtmlElemAst { name, attrs, content, close: ElemClose::Normal })
+            let close = if closed { ElemClose::Normal } else { ElemClose::AutoClosing };
+            Some(HtmlElemAst { name, name_span, attrs, content, close })
         }
     }
 }
 
-pub(crate) struct ParseClosingTag {
-    elem: ElemName,
+pub(crate) struct ParseClosingTag<'a> {
+    elem: &'a ElemName,
 }
 
-impl Parse for ParseClosingTag {
+impl Parse for ParseClosingTag<'_> {
     type Output = ();
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
@@ -129,7 +145,7 @@ impl Parse for ParseClosingTag {
 
         input.parse("</")?;
         let name = input.parse(ParseElemName)?;
-        if name != self.elem {
+        if &name != self.elem {
             return None;
         }
         input.parse_i(ParseSpaces);
@@ -34,3 +34,53 @@ impl Parse for ParseHtmlEntity {
         Some(entity)
     }
 }
+
+/// Parses a numeric character reference, e.g. `&#123;` or `&#x1F600;`.
+///
+/// <https://spec.commonmark.org/0.29/#entity-and-numeric-character-references>:
+/// Decimal references have 1–7 digits, hexadecimal references have 1–6
+/// digits. Invalid code points are replaced with U+FFFD.
+pub(crate) struct ParseCharRef;
+
+impl Parse for ParseCharRef {
+    type Output = char;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse("&#")?;
+        let (radix, max_len) = if input.parse('x').is_some() || input.parse('X').is_some() {
+            (16, 6)
+        } else {
+            (10, 7)
+        };
+
+        let rest = input.rest();
+        let len = rest.find(|c: char| !c.is_digit(radix)).unwrap_or(rest.len());
+        if len == 0 || len > max_len || !rest[len..].starts_with(';') {
+            return None;
+        }
+        let code = u32::from_str_radix(&rest[..len], radix).ok()?;
+        input.bump(len + 1);
+
+        input.apply();
+        Some(match code {
+            0 => char::REPLACEMENT_CHARACTER,
+            _ => char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+        })
+    }
+}
+
+#[test]
+fn test_char_ref() {
+    let parse = |s: &str| Input::new(s).parse(ParseCharRef);
+    assert_eq!(parse("&#35;"), Some('#'));
+    assert_eq!(parse("&#x1F600;"), Some('😀'));
+    assert_eq!(parse("&#X22;"), Some('"'));
+    assert_eq!(parse("&#0;"), Some('\u{FFFD}'));
+    assert_eq!(parse("&#xD800;"), Some('\u{FFFD}'));
+    assert_eq!(parse("&#9999999;"), Some('\u{FFFD}'));
+    assert_eq!(parse("&#12345678;"), None);
+    assert_eq!(parse("&#x;"), None);
+    assert_eq!(parse("&#87"), None);
+}
@@ -0,0 +1,153 @@
+use detached_str::StrSlice;
+use unidok_repr::ast::html::{ElemClose, ElemContentAst, ElemName, HtmlElemAst, HtmlNodeAst};
+use unidok_repr::ast::segments::SegmentAst;
+
+use crate::utils::{ParseLineBreak, ParseLineEnd, ParseSpaces, Until, While};
+use crate::{Indents, Input, Parse};
+
+use super::attr::ParseAttributes;
+use super::cdata::ParseCDataSection;
+use super::comment::ParseHtmlComment;
+use super::entities::{ParseCharRef, ParseHtmlEntity};
+
+/// Parses the content of an `<svg>` or `<math>` element until a closing tag.
+/// Foreign content follows XML rules: Any element can be self-closing, and
+/// element names are case-sensitive. Text is not parsed as Unidok, only HTML
+/// entities are recognized.
+pub(crate) struct ParseForeignContent<'a> {
+    pub ind: Indents<'a>,
+}
+
+impl Parse for ParseForeignContent<'_> {
+    type Output = Vec<SegmentAst>;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+        let mut segments = Vec::new();
+
+        loop {
+            let text = input.parse_i(Until(|c| matches!(c, '<' | '&' | '\n' | '\r')));
+            if !text.is_empty() {
+                segments.push(SegmentAst::Text(text));
+            }
+
+            match input.peek_char() {
+                Some('<') => {
+                    if input.rest().starts_with("</") {
+                        break;
+                    } else if let Some(elem) =
+                        input.parse_nested(ParseForeignElem { ind: self.ind })
+                    {
+                        segments.push(SegmentAst::InlineHtml(HtmlNodeAst::Element(elem)));
+                    } else if let Some(comment) = input.parse(ParseHtmlComment { ind: self.ind }) {
+                        segments.push(SegmentAst::InlineHtml(HtmlNodeAst::Comment(comment)));
+                    } else if let Some(cdata) = input.parse(ParseCDataSection) {
+                        segments.push(SegmentAst::InlineHtml(HtmlNodeAst::CData(cdata)));
+                    } else {
+                        segments.push(SegmentAst::Text(input.bump(1)));
+                    }
+                }
+                Some('&') => {
+                    if let Some(entity) = input.parse(ParseHtmlEntity) {
+                        segments.push(SegmentAst::HtmlEntity(entity));
+                    } else if let Some(c) = input.parse(ParseCharRef) {
+                        segments.push(SegmentAst::CharRef(c));
+                    } else {
+                        segments.push(SegmentAst::Text(input.bump(1)));
+                    }
+                }
+                Some(_) => {
+                    input.parse(ParseLineBreak(self.ind))?;
+                    segments.push(SegmentAst::LineBreak);
+                }
+                None => break,
+            }
+        }
+
+        input.apply();
+        Some(segments)
+    }
+}
+
+/// Parses an element in foreign content. Its name is preserved verbatim.
+struct ParseForeignElem<'a> {
+    ind: Indents<'a>,
+}
+
+impl Parse for ParseForeignElem<'_> {
+    type Output = HtmlElemAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse('<')?;
+        let name_span = input.parse(ParseForeignName)?;
+        let name = name_span.to_str(&input.text).to_string();
+        input.parse_i(ParseSpaces);
+
+        let attrs = input.parse(ParseAttributes { ind: self.ind })?;
+
+        let (content, close) = if input.parse("/>").is_some() {
+            (None, ElemClose::SelfClosing)
+        } else {
+            input.parse('>')?;
+            let content = input.parse(ParseForeignContent { ind: self.ind })?;
+            input.try_parse(ParseForeignClosingTag { elem: &name });
+            (Some(ElemContentAst::Inline(content)), ElemClose::Normal)
+        };
+
+        input.apply();
+        let name = ElemName::Custom { name, is_block_level: false };
+        Some(HtmlElemAst { name, name_span, attrs, content, close })
+    }
+}
+
+struct ParseForeignClosingTag<'a> {
+    elem: &'a str,
+}
+
+impl Parse for ParseForeignClosingTag<'_> {
+    type Output = ();
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse("</")?;
+        let name = input.parse(ParseForeignName)?;
+        if &input[name] != self.elem {
+            return None;
+        }
+        input.parse_i(ParseSpaces);
+        input.parse('>')?;
+
+        input.apply();
+        Some(())
+    }
+}
+
+/// Parses an XML element name, which may contain a namespace prefix, e.g.
+/// `svg:rect`
+struct ParseForeignName;
+
+impl Parse for ParseForeignName {
+    type Output = StrSlice;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        if !input.rest().starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let mut input = input.start();
+
+        let name = input.parse_i(While(|c: char| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')
+        }));
+        if !matches!(input.peek_char(), Some(' ' | '\t' | '/' | '>'))
+            && !input.can_parse(ParseLineEnd)
+        {
+            return None;
+        }
+
+        input.apply();
+        Some(name)
+    }
+}
@@ -5,4 +5,6 @@ pub mod doctype;
 pub mod elem;
 pub mod elem_name;
 pub mod entities;
+pub mod foreign;
 pub mod node;
+pub mod raw;
@@ -0,0 +1,141 @@
+//! Raw HTML as specified by CommonMark, which is passed through verbatim. This
+//! is only used in the CommonMark dialect.
+
+use unidok_repr::ast::html::{HtmlNodeAst, RawHtmlAst};
+
+use crate::{Input, Parse};
+
+/// Parses an opening tag, closing tag, comment, processing instruction,
+/// declaration or CDATA section
+pub(crate) struct ParseRawHtml;
+
+impl Parse for ParseRawHtml {
+    type Output = HtmlNodeAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let len = raw_html_len(input.rest())?;
+        let span = input.bump(len);
+        let text = input[span].to_string();
+        Some(HtmlNodeAst::Raw(RawHtmlAst { text, span }))
+    }
+}
+
+fn raw_html_len(s: &str) -> Option<usize> {
+    if let Some(len) = open_tag_len(s).or_else(|| closing_tag_len(s)) {
+        Some(len)
+    } else if let Some(rest) = s.strip_prefix("<!--") {
+        if rest.starts_with('>') || rest.starts_with("->") {
+            return None;
+        }
+        let end = rest.find("--")?;
+        if !rest[end..].starts_with("-->") || rest[..end].ends_with('-') {
+            return None;
+        }
+        Some(4 + end + 3)
+    } else if let Some(rest) = s.strip_prefix("<?") {
+        Some(2 + rest.find("?>")? + 2)
+    } else if let Some(rest) = s.strip_prefix("<![CDATA[") {
+        Some(9 + rest.find("]]>")? + 3)
+    } else if let Some(rest) = s.strip_prefix("<!") {
+        let name_len = rest.find(|c: char| !c.is_ascii_uppercase()).unwrap_or(rest.len());
+        if name_len == 0 || !rest[name_len..].starts_with(|c: char| c.is_ascii_whitespace()) {
+            return None;
+        }
+        Some(2 + rest.find('>')? + 1)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the opening tag at the start of the string, e.g.
+/// `<a href="foo">` or `<br/>`
+pub(crate) fn open_tag_len(s: &str) -> Option<usize> {
+    let mut i = 1 + tag_name_len(s.strip_prefix('<')?)?;
+
+    loop {
+        let ws = ws_len(&s[i..]);
+        let rest = &s[i + ws..];
+        if rest.starts_with('>') {
+            return Some(i + ws + 1);
+        } else if rest.starts_with("/>") {
+            return Some(i + ws + 2);
+        } else if ws == 0 {
+            return None;
+        }
+        i += ws + attr_len(rest)?;
+    }
+}
+
+/// Returns the length of the closing tag at the start of the string, e.g.
+/// `</a>`
+pub(crate) fn closing_tag_len(s: &str) -> Option<usize> {
+    let i = 2 + tag_name_len(s.strip_prefix("</")?)?;
+    let i = i + ws_len(&s[i..]);
+    if s[i..].starts_with('>') {
+        Some(i + 1)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the tag name at the start of the string
+pub(crate) fn tag_name_len(s: &str) -> Option<usize> {
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(s.find(|c: char| !c.is_ascii_alphanumeric() && c != '-').unwrap_or(s.len()))
+}
+
+fn attr_len(s: &str) -> Option<usize> {
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '_' | ':')) {
+        return None;
+    }
+    let name_len = s
+        .find(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '_' | '.' | ':' | '-'))
+        .unwrap_or(s.len());
+
+    let ws = ws_len(&s[name_len..]);
+    let rest = match s[name_len + ws..].strip_prefix('=') {
+        Some(rest) => rest,
+        None => return Some(name_len),
+    };
+    let ws2 = ws_len(rest);
+    let value = &rest[ws2..];
+
+    let value_len = match value.chars().next()? {
+        q @ ('"' | '\'') => 1 + value[1..].find(q)? + 1,
+        _ => {
+            let len = value
+                .find(|c: char| {
+                    c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`')
+                })
+                .unwrap_or(value.len());
+            if len == 0 {
+                return None;
+            }
+            len
+        }
+    };
+    Some(name_len + ws + 1 + ws2 + value_len)
+}
+
+fn ws_len(s: &str) -> usize {
+    s.find(|c: char| !c.is_ascii_whitespace()).unwrap_or(s.len())
+}
+
+#[test]
+fn test_raw_html() {
+    fn parse(s: &str) -> Option<&str> {
+        raw_html_len(s).map(|len| &s[..len])
+    }
+    assert_eq!(parse("<a href='x' b=c d>foo"), Some("<a href='x' b=c d>"));
+    assert_eq!(parse("<br/>"), Some("<br/>"));
+    assert_eq!(parse("</a >b"), Some("</a >"));
+    assert_eq!(parse("<a href=\"x\"title=y>"), None);
+    assert_eq!(parse("<!-- x -->y"), Some("<!-- x -->"));
+    assert_eq!(parse("<!-- x -- y -->"), None);
+    assert_eq!(parse("<?php x ?>"), Some("<?php x ?>"));
+    assert_eq!(parse("<!DOCTYPE html>"), Some("<!DOCTYPE html>"));
+    assert_eq!(parse("<![CDATA[ <x> ]]>"), Some("<![CDATA[ <x> ]]>"));
+    assert_eq!(parse("<33>"), None);
+}
@@ -0,0 +1,222 @@
+use std::mem;
+use std::path::PathBuf;
+
+use unidok_repr::ast::blocks::{BlockAst, CellContentAst};
+use unidok_repr::ast::html::{ElemContentAst, HtmlNodeAst};
+use unidok_repr::ast::macros::{BlockMacro, BlockMacroContent, MacroArgs};
+use unidok_repr::config::{Autolink, Config, Limits};
+use unidok_repr::diagnostics::{Diagnostic, DiagnosticCode, IncludedFile};
+use unidok_repr::Span;
+
+use crate::blocks::ParseBlock;
+use crate::state::ParsingState;
+use crate::Input;
+
+/// Replaces the content of all `@INCLUDE(path)` macros with the blocks parsed
+/// from the included file.
+///
+/// The text of included files is appended to the input, so the included
+/// blocks can be converted together with the rest of the document. Nothing is
+/// read unless the unsafe config is set. Paths are resolved relative to the
+/// root directory; files outside of it are rejected, as well as includes that
+/// form a cycle.
+pub(crate) fn resolve_includes(
+    blocks: &mut [BlockAst],
+    input: &mut Input,
+    config: &Config,
+) -> Vec<Diagnostic> {
+    let mut includes = Includes {
+        root: root_dir(config),
+        text: input.text.to_string(),
+        autolink: input.autolink,
+        block_elements: input.block_elements.clone(),
+        limits: input.limits,
+        stack: Vec::new(),
+        files: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    includes.resolve_blocks(blocks);
+
+    if includes.text.len() != input.text.len() {
+        let len = includes.text.len();
+        *input = Input::new_at(includes.text, len);
+        input.autolink = includes.autolink;
+        input.block_elements = includes.block_elements;
+        input.limits = includes.limits;
+        input.includes = includes.files;
+    }
+    includes.diagnostics
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn root_dir(config: &Config) -> Option<PathBuf> {
+    let root = match &config.unsafe_config.as_ref()?.root {
+        Some(root) => root.clone(),
+        None => std::env::current_dir().ok()?,
+    };
+    root.canonicalize().ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn root_dir(_: &Config) -> Option<PathBuf> {
+    None
+}
+
+struct Includes {
+    root: Option<PathBuf>,
+    text: String,
+    autolink: Autolink,
+    block_elements: Vec<String>,
+    limits: Limits,
+    stack: Vec<PathBuf>,
+    files: Vec<IncludedFile>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Includes {
+    fn resolve_blocks(&mut self, blocks: &mut [BlockAst]) {
+        for block in blocks {
+            self.resolve_block(block);
+        }
+    }
+
+    fn resolve_block(&mut self, block: &mut BlockAst) {
+        match block {
+            BlockAst::List(l) => {
+                for item in &mut l.items {
+                    self.resolve_blocks(item);
+                }
+            }
+            BlockAst::LabeledList(l) => {
+                for item in &mut l.items {
+                    self.resolve_blocks(&mut item.content);
+                }
+            }
+            BlockAst::Quote(q) => self.resolve_blocks(&mut q.content),
+            BlockAst::Table(t) => {
+                for cell in t.rows.iter_mut().flat_map(|r| &mut r.cells) {
+                    if let CellContentAst::Blocks(b) = &mut cell.content {
+                        self.resolve_blocks(b);
+                    }
+                }
+            }
+            BlockAst::BlockMacro(m) => self.resolve_macro(m),
+            BlockAst::BlockHtml(HtmlNodeAst::Element(e)) => {
+                if let Some(ElemContentAst::Blocks(b)) = &mut e.content {
+                    self.resolve_blocks(b);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve_macro(&mut self, m: &mut BlockMacro) {
+        match &mut m.content {
+            BlockMacroContent::Prefixed(p) => self.resolve_block(p),
+            BlockMacroContent::Braces(b) => self.resolve_blocks(b),
+            BlockMacroContent::None => {}
+        }
+
+        if m.name.to_str(&self.text) != "INCLUDE" {
+            return;
+        }
+        if let Some(MacroArgs::Raw(path)) = m.args {
+            let path_str = path.to_str(&self.text).trim().to_string();
+
+            match self.include(&path_str) {
+                Ok(mut blocks) => {
+                    match mem::replace(&mut m.content, BlockMacroContent::None) {
+                        BlockMacroContent::Prefixed(p) => blocks.push(*p),
+                        BlockMacroContent::Braces(b) => blocks.extend(b),
+                        BlockMacroContent::None => {}
+                    }
+                    m.content = BlockMacroContent::Braces(blocks);
+                }
+                Err(message) => self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::IncludeFailed,
+                    message,
+                    path,
+                )),
+            }
+        }
+    }
+
+    fn include(&mut self, path_str: &str) -> Result<Vec<BlockAst>, String> {
+        let root = self.root.as_ref().ok_or("`@INCLUDE` is only allowed in unsafe mode")?;
+        let path = root
+            .join(path_str)
+            .canonicalize()
+            .map_err(|e| format!("file `{}` couldn't be found: {}", path_str, e))?;
+        if !path.starts_with(root) {
+            return Err(format!("file `{}` is outside of the root directory", path_str));
+        }
+        if self.stack.contains(&path) {
+            return Err(format!("file `{}` includes itself", path_str));
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("file `{}` couldn't be read: {}", path_str, e))?;
+
+        self.text.push('\n');
+        let start = self.text.len();
+        self.text.push_str(&content);
+        let span = Span { start: start as u32, end: self.text.len() as u32 };
+        self.files.push(IncludedFile { path: path.clone(), span });
+
+        let mut input = Input::new_at(self.text.as_str(), start);
+        input.autolink = self.autolink;
+        input.block_elements = self.block_elements.clone();
+        input.limits = self.limits;
+        let mut blocks = input
+            .parse(ParseBlock::new_multi(None, ParsingState::new_global()))
+            .ok_or_else(|| format!("file `{}` couldn't be parsed", path_str))?;
+        self.diagnostics.extend(input.limit_diagnostics());
+
+        self.stack.push(path);
+        self.resolve_blocks(&mut blocks);
+        self.stack.pop();
+
+        Ok(blocks)
+    }
+}
+
+#[test]
+fn test_include() {
+    use std::fs;
+
+    use unidok_repr::config::UnsafeConfig;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("root");
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("a.ud"), "# A\n\n[a]: https://a.com\n\n@INCLUDE(dir/b.ud)").unwrap();
+    fs::write(root.join("dir/b.ud"), "# B\n\n@INCLUDE(a.ud)").unwrap();
+    fs::write(tmp.path().join("outside.ud"), "# Outside").unwrap();
+
+    let config = Config {
+        unsafe_config: Some(UnsafeConfig { root: Some(root.clone()) }),
+        ..Config::default()
+    };
+
+    let mut input = Input::new("@INCLUDE(a.ud)\n@INCLUDE(../outside.ud)\n\n[a]");
+    let doc = crate::parse(&mut input, config);
+    let headings: Vec<_> = doc.state.headings.iter().map(|h| format!("{:?}", h.segments)).collect();
+    assert_eq!(headings, [r#"[Text("A")]"#, r#"[Text("B")]"#]);
+    assert!(format!("{:?}", doc.blocks).contains("https://a.com"));
+    let codes: Vec<_> = doc.diagnostics.iter().map(|d| d.code).collect();
+    assert_eq!(codes, [DiagnosticCode::IncludeFailed, DiagnosticCode::IncludeFailed]);
+    assert!(!input.text.contains("Outside"));
+    let included: Vec<_> = input
+        .includes
+        .iter()
+        .map(|f| &input.text[f.span.start as usize..f.span.end as usize])
+        .collect();
+    assert_eq!(
+        included,
+        [fs::read_to_string(root.join("a.ud")).unwrap(), "# B\n\n@INCLUDE(a.ud)".into()]
+    );
+
+    let mut input = Input::new("@INCLUDE(a.ud)");
+    let doc = crate::parse(&mut input, Config::default());
+    assert!(doc.state.headings.is_empty());
+    assert_eq!(doc.diagnostics.len(), 1);
+}
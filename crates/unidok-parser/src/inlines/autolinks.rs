@@ -0,0 +1,199 @@
+use detached_str::StrSlice;
+use unidok_repr::ast::segments::{LinkAst, LinkTarget, SegmentAst};
+
+use crate::{Input, Parse};
+
+/// Parses a URL or email address in angle brackets, e.g. `<https://foo.org>`
+/// or `<foo@bar.org>`
+pub(crate) struct ParseAngleAutolink;
+
+impl Parse for ParseAngleAutolink {
+    type Output = LinkAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse('<')?;
+        let rest = input.rest();
+        let len =
+            rest.find(|c: char| c == '>' || c == '<' || c.is_whitespace() || c.is_control())?;
+        if !rest[len..].starts_with('>') {
+            return None;
+        }
+        let url = &rest[..len];
+
+        let href = if is_absolute_uri(url) {
+            url.to_string()
+        } else if is_email(url) {
+            format!("mailto:{}", url)
+        } else {
+            return None;
+        };
+
+        let text = input.bump(len);
+        input.parse('>')?;
+
+        input.apply();
+        Some(autolink(text, href))
+    }
+}
+
+/// Parses a bare URL starting with `http://`, `https://`, `www.` or `mailto:`
+pub(crate) struct ParseBareAutolink;
+
+impl Parse for ParseBareAutolink {
+    type Output = LinkAst;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        match input.prev_char() {
+            Some(c) if !c.is_whitespace() && !matches!(c, '*' | '_' | '~' | '(') => return None,
+            _ => {}
+        }
+
+        let mut input = input.start();
+
+        let rest = input.rest();
+        let (len, href_prefix) = if let Some(email) = rest.strip_prefix("mailto:") {
+            (7 + email_len(email)?, "")
+        } else {
+            let (scheme, href_prefix) = if rest.starts_with("www.") {
+                ("", "http://")
+            } else if rest.starts_with("http://") {
+                ("http://", "")
+            } else if rest.starts_with("https://") {
+                ("https://", "")
+            } else {
+                return None;
+            };
+            (scheme.len() + url_len(&rest[scheme.len()..])?, href_prefix)
+        };
+
+        let text = input.bump(len);
+        let href = format!("{}{}", href_prefix, &input[text]);
+
+        input.apply();
+        Some(autolink(text, href))
+    }
+}
+
+fn autolink(text: StrSlice, href: String) -> LinkAst {
+    LinkAst {
+        text: Some(vec![SegmentAst::Text(text)]),
+        target: LinkTarget::Url { href, href_span: text, title: None },
+    }
+}
+
+/// <https://spec.commonmark.org/0.29/#absolute-uri>
+fn is_absolute_uri(s: &str) -> bool {
+    match s.find(':') {
+        Some(i @ 2..=32) => {
+            let scheme = &s[..i];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+        }
+        _ => false,
+    }
+}
+
+/// <https://spec.commonmark.org/0.29/#email-address>
+fn is_email(s: &str) -> bool {
+    let (local, domain) = match s.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let is_local_char = |c: char| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c);
+
+    !local.is_empty()
+        && local.chars().all(is_local_char)
+        && domain.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Returns the length of a bare URL after the scheme, following the rules of
+/// [GFM](https://github.github.com/gfm/#extended-autolink-path-validation)
+fn url_len(s: &str) -> Option<usize> {
+    let domain_len =
+        s.find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '-' | '_'))).unwrap_or(s.len());
+    let domain = s[..domain_len].trim_end_matches('.');
+    if !is_valid_domain(domain) {
+        return None;
+    }
+
+    let len = s.find(|c: char| c.is_whitespace() || c == '<').unwrap_or(s.len());
+    let mut url = &s[..len];
+
+    loop {
+        let unbalanced_paren = url.matches(')').count() > url.matches('(').count();
+        let trimmed = if url.ends_with(['?', '!', '.', ',', ':', '*', '_', '~', '\'', '"'])
+            || (url.ends_with(')') && unbalanced_paren)
+        {
+            &url[..url.len() - 1]
+        } else if let Some(without_semicolon) = url.strip_suffix(';') {
+            let name = without_semicolon.trim_end_matches(|c: char| c.is_ascii_alphanumeric());
+            match name.strip_suffix('&') {
+                Some(rest) if name.len() < without_semicolon.len() => rest,
+                _ => break,
+            }
+        } else {
+            break;
+        };
+        url = trimmed;
+    }
+
+    if url.len() < domain.len() {
+        return None;
+    }
+    Some(url.len())
+}
+
+/// A valid domain consists of segments of alphanumeric characters, underscores
+/// and hyphens separated by periods. There must be at least one period, and no
+/// underscores may be present in the last two segments.
+fn is_valid_domain(domain: &str) -> bool {
+    let segments: Vec<&str> = domain.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|s| !s.is_empty())
+        && segments.iter().rev().take(2).all(|s| !s.contains('_'))
+}
+
+/// Returns the length of an email address after `mailto:`
+fn email_len(s: &str) -> Option<usize> {
+    let local_len = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_')))
+        .unwrap_or(s.len());
+    if local_len == 0 || !s[local_len..].starts_with('@') {
+        return None;
+    }
+
+    let domain = &s[local_len + 1..];
+    let domain_len = domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')))
+        .unwrap_or(domain.len());
+    let domain = domain[..domain_len].trim_end_matches('.');
+    if !domain.contains('.') || domain.ends_with(['-', '_']) {
+        return None;
+    }
+
+    Some(local_len + 1 + domain.len())
+}
+
+#[test]
+fn test_url_len() {
+    assert_eq!(url_len("foo.org"), Some(7));
+    assert_eq!(url_len("foo.org/a_b?c=d."), Some(15));
+    assert_eq!(url_len("foo.org/(a)b)"), Some(12));
+    assert_eq!(url_len("foo.org/a&amp;"), Some(9));
+    assert_eq!(url_len("foo.org/a&amp;b;"), Some(16));
+    assert_eq!(url_len("foo.org/a<b"), Some(9));
+    assert_eq!(url_len("foo"), None);
+    assert_eq!(url_len("foo.b_r"), None);
+    assert_eq!(email_len("a.b@c.org."), Some(9));
+    assert_eq!(email_len("a@b"), None);
+    assert!(is_absolute_uri("irc://foo.bar"));
+    assert!(!is_absolute_uri("m:foo"));
+    assert!(is_email("foo+bar@baz.org"));
+}
@@ -19,13 +19,9 @@ impl Parse for ParseBraces<'_> {
         let mut input = input.start();
 
         input.parse('{')?;
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
         let segments = input
-            .parse(Segments::parser(
-                self.ind,
-                Context::InlineBraces,
-                self.mode.unwrap_or_else(ParsingMode::new_all),
-                self.ac,
-            ))?
+            .parse(Segments::parser(self.ind, Context::InlineBraces, mode, self.ac))?
             .into_segments_no_underline_zero()?;
         input.parse('}')?;
 
@@ -1,8 +1,12 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+
 use aho_corasick::AhoCorasick;
 use unidok_repr::ast::segments::ImageAst;
 
-use super::links::{ParseLinkTargetReference, ParseLinkTargetUrl};
+use super::links::{resolve_collapsed_reference, ParseLinkTargetReference, ParseLinkTargetUrl};
 use super::Segments;
+use crate::memo::Memoize;
 use crate::parsing_mode::ParsingMode;
 use crate::{Context, Indents, Input, Parse};
 
@@ -30,6 +34,12 @@ impl Parse for ParseImage<'_> {
     }
 }
 
+impl Memoize for ParseImage<'_> {
+    fn hash_fields(&self, state: &mut DefaultHasher) {
+        (self.ind, self.mode, self.ac as *const AhoCorasick).hash(state);
+    }
+}
+
 pub(crate) struct ParseFullImage<'a> {
     ind: Indents<'a>,
     mode: Option<ParsingMode>,
@@ -43,18 +53,17 @@ impl Parse for ParseFullImage<'_> {
         let mut input = input.start();
 
         input.parse("![")?;
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
+        let len = input.len();
         let alt = input
-            .parse(Segments::parser(
-                self.ind,
-                Context::LinkOrImg,
-                self.mode.unwrap_or_else(ParsingMode::new_all),
-                self.ac,
-            ))?
+            .parse(Segments::parser(self.ind, Context::LinkOrImg, mode, self.ac))?
             .into_segments_no_underline_zero()?;
+        let label = input.prev_slice_bytes(len - input.len());
         input.parse(']')?;
 
         let target =
             input.parse(ParseLinkTargetUrl).or_else(|| input.parse(ParseLinkTargetReference))?;
+        let target = resolve_collapsed_reference(target, label, &input);
 
         input.apply();
         Some(ImageAst { alt: Some(alt), target })
@@ -0,0 +1,140 @@
+//! Link destinations and titles as specified by CommonMark, which are used in
+//! the CommonMark dialect.
+
+use crate::html::entities::ParseCharRef;
+use crate::utils::{is_blank_line, ParseLineEnd, ParseSpaces};
+use crate::{Input, Parse};
+
+/// Parses a link destination, either `<url>` (which may contain spaces) or a
+/// URL without spaces and with balanced parentheses. Backslash escapes and
+/// numeric character references are resolved.
+pub(crate) struct ParseLinkDestination;
+
+impl Parse for ParseLinkDestination {
+    type Output = String;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+        let mut s = String::new();
+
+        if input.parse('<').is_some() {
+            loop {
+                match input.peek_char()? {
+                    '>' => {
+                        input.bump(1);
+                        break;
+                    }
+                    '<' | '\n' | '\r' => return None,
+                    _ => push_char(&mut s, &mut input),
+                }
+            }
+        } else {
+            let mut parens = 0;
+            while let Some(c) = input.peek_char() {
+                match c {
+                    _ if c.is_ascii_whitespace() || c.is_ascii_control() => break,
+                    '(' => parens += 1,
+                    ')' if parens == 0 => break,
+                    ')' => parens -= 1,
+                    _ => {}
+                }
+                push_char(&mut s, &mut input);
+            }
+            if parens > 0 {
+                return None;
+            }
+        }
+
+        input.apply();
+        Some(s)
+    }
+}
+
+/// Parses a link title, enclosed in `"`, `'` or parentheses. Backslash escapes
+/// and numeric character references are resolved.
+pub(crate) struct ParseLinkTitle;
+
+impl Parse for ParseLinkTitle {
+    type Output = String;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+        let close = match input.peek_char()? {
+            '"' => '"',
+            '\'' => '\'',
+            '(' => ')',
+            _ => return None,
+        };
+        input.bump(1);
+
+        let mut s = String::new();
+        loop {
+            match input.peek_char()? {
+                c if c == close => {
+                    input.bump(1);
+                    break;
+                }
+                '(' if close == ')' => return None,
+                '\n' | '\r' => {
+                    input.parse(ParseLinkWs)?;
+                    s.push('\n');
+                }
+                _ => push_char(&mut s, &mut input),
+            }
+        }
+
+        input.apply();
+        Some(s)
+    }
+}
+
+/// Parses spaces and tabs, including up to one line break, but not a blank
+/// line. Returns whether any whitespace was found.
+pub(crate) struct ParseLinkWs;
+
+impl Parse for ParseLinkWs {
+    type Output = bool;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+        let len = input.len();
+
+        input.parse_i(ParseSpaces);
+        if input.can_parse(ParseLineEnd) && !input.is_empty() {
+            input.parse("\r\n").or_else(|| input.parse('\n')).or_else(|| input.parse('\r'))?;
+            if is_blank_line(input.rest()) {
+                return None;
+            }
+            input.parse_i(ParseSpaces);
+        }
+
+        let found = input.len() < len;
+        input.apply();
+        Some(found)
+    }
+}
+
+/// Pushes the next character, or the character that is escaped or referenced
+/// at the current position
+fn push_char(s: &mut String, input: &mut Input) {
+    let rest = input.rest();
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some('\\'), Some(c)) if c.is_ascii_punctuation() => {
+            s.push(c);
+            input.bump(2);
+        }
+        (Some('&'), _) => match input.parse(ParseCharRef) {
+            Some(c) => s.push(c),
+            None => {
+                s.push('&');
+                input.bump(1);
+            }
+        },
+        (Some(c), _) => {
+            s.push(c);
+            input.bump(c.len_utf8());
+        }
+        (None, _) => {}
+    }
+}
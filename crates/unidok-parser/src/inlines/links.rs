@@ -1,11 +1,18 @@
+use std::any::type_name;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::mem::replace;
 
 use aho_corasick::AhoCorasick;
+use detached_str::StrSlice;
 use unidok_repr::ast::segments::{LinkAst, LinkTarget};
+use unidok_repr::config::Dialect;
 
+use super::link_destinations::{ParseLinkDestination, ParseLinkTitle, ParseLinkWs};
 use super::segments::Segments;
+use crate::input::ModifyInput;
+use crate::memo::Memoize;
 use crate::parsing_mode::ParsingMode;
-use crate::utils::Until;
 use crate::{Context, Indents, Input, Parse};
 
 pub(crate) struct ParseLink<'a> {
@@ -23,11 +30,20 @@ impl Parse for ParseLink<'_> {
         {
             Some(link)
         } else {
-            input.parse(ParseLinkTargetReference).map(|target| LinkAst { text: None, target })
+            input
+                .parse(ParseFootnoteRef)
+                .or_else(|| input.parse(ParseLinkTargetReference))
+                .map(|target| LinkAst { text: None, target })
         }
     }
 }
 
+impl Memoize for ParseLink<'_> {
+    fn hash_fields(&self, state: &mut DefaultHasher) {
+        (self.ind, self.mode, self.ac as *const AhoCorasick).hash(state);
+    }
+}
+
 pub(super) struct ParseFullLink<'a> {
     pub(super) ind: Indents<'a>,
     pub(super) mode: Option<ParsingMode>,
@@ -41,25 +57,38 @@ impl Parse for ParseFullLink<'_> {
         let mut input = input.start();
 
         input.parse('[')?;
+        let mode = self.mode.unwrap_or_else(|| ParsingMode::new_default(input.dialect));
+        let len = input.len();
         let text = input
-            .parse(Segments::parser(
-                self.ind,
-                Context::LinkOrImg,
-                self.mode.unwrap_or_else(ParsingMode::new_all),
-                self.ac,
-            ))?
+            .parse(Segments::parser(self.ind, Context::LinkOrImg, mode, self.ac))?
             .into_segments_no_underline_zero()?;
+        let label = input.prev_slice_bytes(len - input.len());
         input.parse(']')?;
 
         let target = input.parse("[^]").map(|_| LinkTarget::Footnote).or_else(|| {
             input.parse(ParseLinkTargetUrl).or_else(|| input.parse(ParseLinkTargetReference))
         })?;
+        let target = resolve_collapsed_reference(target, label, &input);
 
         input.apply();
         Some(LinkAst { text: Some(text), target })
     }
 }
 
+/// In CommonMark, `[foo][]` is a reference to `foo`
+pub(super) fn resolve_collapsed_reference(
+    target: LinkTarget,
+    label: StrSlice,
+    input: &Input,
+) -> LinkTarget {
+    match target {
+        LinkTarget::Reference(r) if r.is_empty() && input.dialect == Dialect::CommonMark => {
+            LinkTarget::Reference(label)
+        }
+        target => target,
+    }
+}
+
 pub(super) struct ParseLinkTargetUrl;
 
 impl Parse for ParseLinkTargetUrl {
@@ -69,15 +98,39 @@ impl Parse for ParseLinkTargetUrl {
         let mut input = input.start();
 
         input.parse('(')?;
+        if input.dialect == Dialect::CommonMark {
+            return parse_commonmark_target(input);
+        }
+        let len = input.len();
         let href = input.parse(ParseHref)?;
+        let href_span = input.prev_slice_bytes(len - input.len());
         let title = input.parse(ParseQuotedText);
         input.parse(')')?;
 
         input.apply();
-        Some(LinkTarget::Url { href, title })
+        Some(LinkTarget::Url { href, href_span, title })
     }
 }
 
+/// Parses the rest of an inline link target in the CommonMark dialect, e.g.
+/// `<url> "title")`
+fn parse_commonmark_target(mut input: ModifyInput<'_>) -> Option<LinkTarget> {
+    input.parse(ParseLinkWs)?;
+    let len = input.len();
+    let href = input.parse(ParseLinkDestination)?;
+    let href_span = input.prev_slice_bytes(len - input.len());
+
+    let mut title = None;
+    if input.parse(ParseLinkWs)? && !href_span.is_empty() {
+        title = input.parse(ParseLinkTitle);
+        input.parse(ParseLinkWs)?;
+    }
+    input.parse(')')?;
+
+    input.apply();
+    Some(LinkTarget::Url { href, href_span, title })
+}
+
 pub(super) struct ParseLinkTargetReference;
 
 impl Parse for ParseLinkTargetReference {
@@ -87,7 +140,8 @@ impl Parse for ParseLinkTargetReference {
         let mut input = input.start();
 
         input.parse('[')?;
-        let reference = input.parse(Until(|c| matches!(c, ']' | '\r' | '\n')))?;
+        let len = input.find_closing_bracket()?;
+        let reference = input.bump(len);
         input.parse(']')?;
 
         input.apply();
@@ -95,17 +149,47 @@ impl Parse for ParseLinkTargetReference {
     }
 }
 
+/// Parses a reference to a footnote definition, e.g. `[^foo]`
+struct ParseFootnoteRef;
+
+impl Parse for ParseFootnoteRef {
+    type Output = LinkTarget;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut input = input.start();
+
+        input.parse("[^")?;
+        let len = input.find_closing_bracket()?;
+        if len == 0 {
+            return None;
+        }
+        let name = input.bump(len);
+        input.parse(']')?;
+
+        input.apply();
+        Some(LinkTarget::NamedFootnote(name))
+    }
+}
+
 struct ParseHref;
 
 impl Parse for ParseHref {
     type Output = String;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut hasher = DefaultHasher::new();
+        type_name::<Self>().hash(&mut hasher);
+        let key = hasher.finish();
+        if input.memo.is_unclosed((input.idx(), key)) {
+            return None;
+        }
+
         let mut input = input.start();
 
         let mut s = String::new();
 
-        let mut parens = 0;
+        // the positions after the open parentheses
+        let mut parens = Vec::new();
         let mut ws = false;
         let mut esc = false;
 
@@ -131,18 +215,14 @@ impl Parse for ParseHref {
                 '(' => {
                     s.push('(');
                     if !prev_esc {
-                        parens += 1;
+                        parens.push(input.idx() + 1);
                     }
                 }
                 ')' => {
-                    if prev_esc {
-                        s.push(')');
-                    } else if parens > 0 {
-                        s.push(')');
-                        parens -= 1;
-                    } else {
+                    if !prev_esc && parens.pop().is_none() {
                         break;
                     }
+                    s.push(')');
                 }
                 '"' if prev_ws => {
                     break;
@@ -158,6 +238,12 @@ impl Parse for ParseHref {
             input.bump(c.len_utf8());
         }
 
+        if input.is_empty() {
+            // the target after one of these parentheses can't be closed
+            // either, so it isn't scanned again
+            input.memo.insert_unclosed(&parens, key);
+        }
+
         input.apply();
         Some(s)
     }
@@ -43,7 +43,7 @@ impl Parse for ParseMathContent<'_> {
                     text.push('\\');
                 }
                 text.push(c);
-                input.bump(c.len_utf8() as usize);
+                input.bump(c.len_utf8());
                 esc = false;
             } else {
                 match c {
@@ -73,7 +73,7 @@ impl Parse for ParseMathContent<'_> {
                     }
                     _ => {
                         text.push(c);
-                        input.bump(c.len_utf8() as usize);
+                        input.bump(c.len_utf8());
                     }
                 }
             }
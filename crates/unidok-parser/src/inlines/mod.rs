@@ -1,9 +1,11 @@
+pub(crate) mod autolinks;
 pub(crate) mod braces;
 pub(crate) mod code;
 pub(crate) mod escaped;
 pub(crate) mod format;
 pub(crate) mod images;
 pub(crate) mod limiters;
+pub(crate) mod link_destinations;
 pub(crate) mod links;
 pub(crate) mod math;
 pub(crate) mod segments;
@@ -1,11 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
+use std::hash::Hash;
+use std::sync::Arc;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use detached_str::StrSlice;
-use unidok_repr::ast::html::{HtmlEntity, HtmlNodeAst};
+use unidok_repr::ast::html::{ElemClose, ElemName, HtmlElemAst, HtmlEntity, HtmlNodeAst};
 use unidok_repr::ast::macros::InlineMacroAst;
 use unidok_repr::ast::segments::*;
+use unidok_repr::config::{Autolink, Dialect};
 
+use super::autolinks::{ParseAngleAutolink, ParseBareAutolink};
 use super::code::ParseCode;
 use super::escaped::ParseEscaped;
 use super::format::{is_in_word, is_not_flanking, FlankType, Flanking, FormatDelim};
@@ -14,16 +19,18 @@ use super::limiters::ParseLimiter;
 use super::links::ParseLink;
 use super::math::ParseMath;
 use crate::blocks::{
-    ParseCodeBlock, ParseComment, ParseHeading, ParseLinkRefDef, ParseList, ParseQuote, ParseTable,
-    ParseThematicBreak, Underline,
+    html_blocks, ParseCodeBlock, ParseComment, ParseFootnoteDef, ParseHeading, ParseLabeledList,
+    ParseLinkRefDef, ParseList, ParseQuote, ParseTable, ParseThematicBreak, Underline,
 };
 use crate::html::elem::ParseHtmlElem;
-use crate::html::entities::ParseHtmlEntity;
+use crate::html::entities::{ParseCharRef, ParseHtmlEntity};
 use crate::html::node::ParseHtmlNode;
+use crate::html::raw::ParseRawHtml;
 use crate::macros::utils::ParseClosingBrace;
 use crate::macros::ParseInlineMacro;
+use crate::memo::{unshare, Memoized};
 use crate::parsing_mode::ParsingMode;
-use crate::utils::{is_ws, ParseLineBreak, While};
+use crate::utils::{has_code_indent, is_blank_line, is_ws, ParseLineBreak, While};
 use crate::{Context, Indents, Input, Parse};
 
 pub fn strip_space_start(segment: &mut SegmentAst, input: &Input) -> bool {
@@ -63,7 +70,7 @@ pub(crate) enum Segments {
 impl Segments {
     pub(crate) fn parser<'a>(
         ind: Indents<'a>,
-        context: Context,
+        context: Context<'a>,
         mode: ParsingMode,
         ac: &'a AhoCorasick,
     ) -> ParseSegments<'a> {
@@ -87,7 +94,7 @@ impl Segments {
 
 pub(crate) struct ParseSegments<'a> {
     ind: Indents<'a>,
-    context: Context,
+    context: Context<'a>,
     mode: ParsingMode,
     ac: &'a AhoCorasick,
 }
@@ -96,7 +103,11 @@ impl Parse for ParseSegments<'_> {
     type Output = Segments;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
-        let (items, underline) = self.lex_items(input)?;
+        input.depth += 1;
+        let result = self.lex_items(input);
+        input.depth -= 1;
+
+        let (items, underline) = result?;
         if items.is_empty() {
             return Some(Segments::Empty);
         }
@@ -121,11 +132,12 @@ enum Item {
     },
     Code(CodeAst),
     Math(MathAst),
-    Link(LinkAst),
-    Image(ImageAst),
-    Macro(InlineMacroAst),
+    Link(Arc<LinkAst>),
+    Image(Arc<ImageAst>),
+    Macro(Arc<InlineMacroAst>),
     Html(HtmlNodeAst),
     HtmlEntity(HtmlEntity),
+    CharRef(char),
     Escaped(Escaped),
     Substitution(Substitution),
     LineBreak,
@@ -139,13 +151,8 @@ impl Item {
     }
 
     fn can_appear_before_limiter(&self) -> bool {
-        matches!(
-            self,
-            Item::Code(_)
-                | Item::Escaped(_)
-                | Item::FormatDelim { .. }
-                | Item::Link(LinkAst { text: None, .. })
-        )
+        matches!(self, Item::Code(_) | Item::Escaped(_) | Item::FormatDelim { .. })
+            || matches!(self, Item::Link(l) if l.text.is_none())
     }
 }
 
@@ -178,6 +185,7 @@ enum StackItem {
     Macro(InlineMacroAst),
     Html(HtmlNodeAst),
     HtmlEntity(HtmlEntity),
+    CharRef(char),
     Escaped(Escaped),
     Substitution(Substitution),
     LineBreak,
@@ -216,11 +224,12 @@ fn parse_paragraph_items(items: Vec<Item>) -> Vec<StackItem> {
             }
             Item::Code(c) => stack.push(StackItem::Code(c)),
             Item::Math(m) => stack.push(StackItem::Math(m)),
-            Item::Link(l) => stack.push(StackItem::Link(l)),
-            Item::Image(i) => stack.push(StackItem::Image(i)),
-            Item::Macro(m) => stack.push(StackItem::Macro(m)),
+            Item::Link(l) => stack.push(StackItem::Link(unshare(l))),
+            Item::Image(i) => stack.push(StackItem::Image(unshare(i))),
+            Item::Macro(m) => stack.push(StackItem::Macro(unshare(m))),
             Item::Html(h) => stack.push(StackItem::Html(h)),
             Item::HtmlEntity(e) => stack.push(StackItem::HtmlEntity(e)),
+            Item::CharRef(c) => stack.push(StackItem::CharRef(c)),
             Item::Escaped(e) => stack.push(StackItem::Escaped(e)),
             Item::Substitution(s) => stack.push(StackItem::Substitution(s)),
             Item::LineBreak => stack.push(StackItem::LineBreak),
@@ -278,6 +287,7 @@ fn stack_to_segments(stack: Vec<StackItem>) -> Vec<SegmentAst> {
             StackItem::Macro(m) => SegmentAst::InlineMacro(m),
             StackItem::Html(h) => SegmentAst::InlineHtml(h),
             StackItem::HtmlEntity(e) => SegmentAst::HtmlEntity(e),
+            StackItem::CharRef(c) => SegmentAst::CharRef(c),
             StackItem::Escaped(e) => SegmentAst::Escaped(e),
             StackItem::Substitution(e) => SegmentAst::Substitution(e),
             StackItem::LineBreak => SegmentAst::LineBreak,
@@ -343,7 +353,8 @@ fn is_compatible(left: Flanking, right: Flanking, left_count: u8, right_count: u
 
 pub(crate) static PATTERNS: &[&str] = &[
     "*", "_", "~", "^", "#", "`", "%{", "|", "[", "]", "{", "}", "!", "@", "\\", "$", "&", "\n",
-    "\r", "'", "\"", "...", "--", "->", "<-", "<", "(C)", "(R)", "(TM)",
+    "\r", "'", "\"", "...", "--", "->", "<-", "<", "(C)", "(R)", "(TM)", "http://", "https://",
+    "www.", "mailto:", "::",
 ];
 
 pub(crate) fn get_global_patterns() -> AhoCorasick {
@@ -380,12 +391,19 @@ mod patterns {
     pub const CIRCLE_C: u32 = 26;
     pub const CIRCLE_R: u32 = 27;
     pub const TRADEMARK: u32 = 28;
+    pub const HTTP: u32 = 29;
+    pub const HTTPS: u32 = 30;
+    pub const WWW: u32 = 31;
+    pub const MAILTO: u32 = 32;
+    pub const DOUBLE_COLON: u32 = 33;
 }
 
-fn pattern_to_format_delim(n: u32) -> Option<FormatDelim> {
+fn pattern_to_format_delim(n: u32, dialect: Dialect) -> Option<FormatDelim> {
     Some(match n {
         patterns::STAR => FormatDelim::Star,
         patterns::UNDERSCORE => FormatDelim::Underscore,
+        // CommonMark only supports emphasis
+        _ if dialect == Dialect::CommonMark => return None,
         patterns::TILDE => FormatDelim::Tilde,
         patterns::CARET => FormatDelim::Caret,
         patterns::NUMBER_SIGN => FormatDelim::NumberSign,
@@ -394,12 +412,73 @@ fn pattern_to_format_delim(n: u32) -> Option<FormatDelim> {
 }
 
 impl ParseSegments<'_> {
-    fn lex_items(&self, input: &mut Input) -> Option<(Vec<Item>, Option<Underline>)> {
+    fn lex_items(&mut self, input: &mut Input) -> Option<(Vec<Item>, Option<Underline>)> {
+        let start = input.idx();
+        let mode = self.mode;
+        let unclosed_key = self.unclosed_key(mode, input);
+        if let Some(key) = unclosed_key {
+            if input.memo.is_unclosed((start, key)) {
+                return None;
+            }
+        }
+
         let mut items = Vec::new();
-        let mut open_brackets = 0;
-        let mut open_braces = 0;
+        let mut open_brackets = Vec::new();
+        let mut open_braces = Vec::new();
+        let result = self.lex_loop(input, &mut items, &mut open_brackets, &mut open_braces);
+
+        if let Some(key) = unclosed_key {
+            let closer = if self.context == Context::LinkOrImg { ']' } else { '}' };
+            if result.is_none() || !input.rest().starts_with(closer) {
+                // if the step budget was exceeded in the meantime, the content
+                // after these positions wasn't parsed in the same way
+                if self.unclosed_key(mode, input) == Some(key) {
+                    let open = if closer == ']' { &open_brackets } else { &open_braces };
+                    input.memo.insert_unclosed(open, key);
+                }
+                return None;
+            }
+        }
+        result?;
 
+        let underline = if let Some(&Item::Underline(u)) = items.last() {
+            items.pop();
+            Some(u)
+        } else {
+            None
+        };
+
+        Some((items, underline))
+    }
+
+    /// Returns the key under which unclosed content of a link, image or
+    /// braces is recorded. When the content isn't closed, the content after
+    /// an opening bracket or brace that is still open can't be closed either,
+    /// so it isn't scanned again. This makes parsing unclosed brackets take
+    /// linear time.
+    fn unclosed_key(&self, mode: ParsingMode, input: &Input) -> Option<u64> {
+        if !matches!(self.context, Context::LinkOrImg | Context::InlineBraces) {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        (self.context == Context::LinkOrImg, self.ind, mode, self.ac as *const AhoCorasick)
+            .hash(&mut hasher);
+        Some(input.state_hash(hasher))
+    }
+
+    fn lex_loop(
+        &mut self,
+        input: &mut Input,
+        items: &mut Vec<Item>,
+        open_brackets: &mut Vec<usize>,
+        open_braces: &mut Vec<usize>,
+    ) -> Option<()> {
         loop {
+            if input.is_restricted() {
+                // the rest is parsed as plain text
+                self.mode = ParsingMode::new_nothing();
+            }
+
             let r#match = self.ac.find(input.rest());
             let (skip_bytes, sym) =
                 r#match.map(|m| (m.start(), m.pattern())).unwrap_or_else(|| (input.len(), 0));
@@ -409,23 +488,18 @@ impl ParseSegments<'_> {
             }
 
             if input.is_empty() {
-                break;
+                // a code span must be closed
+                return match self.context {
+                    Context::Code(_) => None,
+                    _ => Some(()),
+                };
             }
 
             let sym = sym as u32;
-            if self.handle_char(input, &mut items, sym, &mut open_brackets, &mut open_braces)? {
-                break;
+            if self.handle_char(input, items, sym, open_brackets, open_braces)? {
+                return Some(());
             }
         }
-
-        let underline = if let Some(&Item::Underline(u)) = items.last() {
-            items.pop();
-            Some(u)
-        } else {
-            None
-        };
-
-        Some((items, underline))
     }
 
     /// Returns `true` if the loop should be exited
@@ -434,14 +508,14 @@ impl ParseSegments<'_> {
         input: &mut Input,
         items: &mut Vec<Item>,
         sym: u32,
-        open_brackets: &mut u32,
-        open_braces: &mut u32,
+        open_brackets: &mut Vec<usize>,
+        open_braces: &mut Vec<usize>,
     ) -> Option<bool> {
         let ind = self.ind;
         let context = self.context;
 
         if self.mode.is(ParsingMode::INLINE) {
-            if let Some(delim) = pattern_to_format_delim(sym) {
+            if let Some(delim) = pattern_to_format_delim(sym, input.dialect) {
                 let c = input.peek_char().unwrap();
 
                 let left = input.prev_char();
@@ -488,68 +562,72 @@ impl ParseSegments<'_> {
                 items.push(Item::Text(input.parse_i(While('`'))));
                 return Some(false);
             }
-            patterns::PERCENT_BRACE => {
-                if self.mode.is(ParsingMode::MATH) {
-                    if let Some(math) = input.parse(ParseMath { ind }) {
-                        items.push(Item::Math(math));
-                        return Some(false);
-                    }
+            patterns::PERCENT_BRACE if self.mode.is(ParsingMode::MATH) => {
+                if let Some(math) = input.parse(ParseMath { ind }) {
+                    items.push(Item::Math(math));
+                    return Some(false);
                 }
             }
-            patterns::EXCL_MARK => {
-                if self.mode.is(ParsingMode::LINKS_IMAGES) {
-                    if let Some(img) =
-                        input.parse(ParseImage { ind, ac: self.ac, mode: Some(self.mode) })
-                    {
-                        items.push(Item::Image(img));
-                        return Some(false);
-                    }
+            patterns::EXCL_MARK if self.mode.is(ParsingMode::LINKS_IMAGES) => {
+                if let Some(img) =
+                    input.parse(Memoized(ParseImage { ind, ac: self.ac, mode: Some(self.mode) }))
+                {
+                    items.push(Item::Image(img));
+                    return Some(false);
                 }
             }
-            patterns::AT => {
-                if self.mode.is(ParsingMode::MACROS) {
-                    if let Some(mac) =
-                        input.parse(ParseInlineMacro { ind, mode: Some(self.mode), ac: self.ac })
-                    {
-                        items.push(Item::Macro(mac));
-                        return Some(false);
-                    }
+            patterns::AT if self.mode.is(ParsingMode::MACROS) => {
+                if let Some(mac) = input.parse(Memoized(ParseInlineMacro {
+                    ind,
+                    mode: Some(self.mode),
+                    ac: self.ac,
+                })) {
+                    items.push(Item::Macro(mac));
+                    return Some(false);
                 }
             }
-            patterns::BACKSLASH => {
-                if self.mode.is(ParsingMode::INLINE) {
-                    if let Some(esc) = input.parse(ParseEscaped) {
-                        items.push(Item::Escaped(esc));
-                        return Some(false);
-                    }
+            patterns::BACKSLASH if self.mode.is(ParsingMode::INLINE) => {
+                if let Some(esc) = input.parse(ParseEscaped) {
+                    items.push(Item::Escaped(esc));
+                    return Some(false);
                 }
             }
-            patterns::DOLLAR => {
-                if self.mode.is(ParsingMode::LIMITER) {
-                    let parser_state = {
-                        if matches!(items.last(), Some(i) if i.can_appear_before_limiter())
-                            || matches!(input.rest()[1..].chars().next(),
+            patterns::DOLLAR if self.mode.is(ParsingMode::LIMITER) => {
+                let parser_state = {
+                    if matches!(items.last(), Some(i) if i.can_appear_before_limiter())
+                        || matches!(input.rest()[1..].chars().next(),
                                 Some(c) if FormatDelim::try_from(c).is_ok())
-                        {
-                            Some(false)
-                        } else {
-                            match items.iter().rev().find(|i| !i.is_blank_text(input)) {
-                                Some(Item::LineBreak) | None => Some(true),
-                                _ => None,
-                            }
+                    {
+                        Some(false)
+                    } else {
+                        match items.iter().rev().find(|i| !i.is_blank_text(input)) {
+                            Some(Item::LineBreak) | None => Some(true),
+                            _ => None,
                         }
-                    };
+                    }
+                };
 
-                    if let Some(require_line_end) = parser_state {
-                        if input.parse(ParseLimiter { require_line_end }).is_some() {
-                            items.push(Item::Limiter);
-                            return Some(false);
-                        }
+                if let Some(require_line_end) = parser_state {
+                    if input.parse(ParseLimiter { require_line_end }).is_some() {
+                        items.push(Item::Limiter);
+                        return Some(false);
                     }
                 }
             }
             patterns::OPEN_ANGLE => {
-                if self.mode.is(ParsingMode::HTML) {
+                if self.mode.is(ParsingMode::LINKS_IMAGES) {
+                    if let Some(link) = input.parse(ParseAngleAutolink) {
+                        items.push(Item::Link(Arc::new(link)));
+                        return Some(false);
+                    }
+                }
+
+                if self.mode.is(ParsingMode::HTML) && input.dialect == Dialect::CommonMark {
+                    if let Some(html) = input.parse(ParseRawHtml) {
+                        items.push(Item::Html(html));
+                        return Some(false);
+                    }
+                } else if self.mode.is(ParsingMode::HTML) {
                     if let Some(html) =
                         input.parse(ParseHtmlNode { ind, mode: Some(self.mode), ac: self.ac })
                     {
@@ -564,65 +642,84 @@ impl ParseSegments<'_> {
                     }
                 }
             }
-            patterns::AMPERSAND => {
-                if self.mode.is(ParsingMode::HTML) {
-                    if let Some(entity) = input.parse(ParseHtmlEntity) {
-                        items.push(Item::HtmlEntity(entity));
-                        return Some(false);
-                    }
+            patterns::HTTP | patterns::HTTPS | patterns::WWW | patterns::MAILTO
+                if self.mode.is(ParsingMode::LINKS_IMAGES)
+                    && input.autolink == Autolink::Bare
+                    && context != Context::LinkOrImg =>
+            {
+                if let Some(link) = input.parse(ParseBareAutolink) {
+                    items.push(Item::Link(Arc::new(link)));
+                    return Some(false);
                 }
             }
-
-            patterns::PIPE => {
-                if context == Context::Table {
-                    return Some(true);
+            patterns::DOUBLE_COLON
+                if context == Context::Label
+                    && matches!(
+                        input.rest()[2..].chars().next(),
+                        Some(' ' | '\t' | '\n' | '\r') | None
+                    ) =>
+            {
+                return Some(true);
+            }
+            patterns::AMPERSAND if self.mode.is(ParsingMode::HTML) => {
+                if let Some(entity) = input.parse(ParseHtmlEntity) {
+                    items.push(Item::HtmlEntity(entity));
+                    return Some(false);
+                }
+                if let Some(c) = input.parse(ParseCharRef) {
+                    items.push(Item::CharRef(c));
+                    return Some(false);
                 }
             }
 
+            patterns::PIPE if context == Context::Table => {
+                return Some(true);
+            }
+
             patterns::OPEN_BRACKET => {
                 if self.mode.is(ParsingMode::LINKS_IMAGES) {
                     if let Some(link) =
-                        input.parse(ParseLink { ind, ac: self.ac, mode: Some(self.mode) })
+                        input.parse(Memoized(ParseLink { ind, ac: self.ac, mode: Some(self.mode) }))
                     {
                         items.push(Item::Link(link));
                         return Some(false);
                     }
                 }
 
-                *open_brackets += 1;
+                open_brackets.push(input.idx() + 1);
             }
             patterns::CLOSE_BRACKET => {
-                if context == Context::LinkOrImg && *open_brackets == 0 {
+                if context == Context::LinkOrImg && open_brackets.is_empty() {
                     return Some(true);
                 }
 
-                if *open_brackets > 0 {
-                    *open_brackets -= 1;
-                }
+                open_brackets.pop();
             }
 
             patterns::OPEN_BRACE => {
-                *open_braces += 1;
+                open_braces.push(input.idx() + 1);
             }
             patterns::CLOSE_BRACE => {
-                if context == Context::InlineBraces && *open_braces == 0 {
+                if context == Context::InlineBraces && open_braces.is_empty() {
                     return Some(true);
                 }
 
                 if context == Context::BlockBraces
-                    && *open_braces == 0
-                    && matches!(items.last(), Some(Item::LineBreak) | None)
+                    && open_braces.is_empty()
+                    && is_at_line_start(items, input)
                     && input.can_parse(ParseClosingBrace(ind))
                 {
                     return Some(true);
                 }
 
-                if *open_braces > 0 {
-                    *open_braces -= 1;
-                }
+                open_braces.pop();
             }
 
             patterns::SINGLE_QUOTE => {
+                if context == Context::QuotedArg('\'') {
+                    return Some(true);
+                }
+
                 if self.mode.is(ParsingMode::SUBSTITUTIONS) {
                     let prev = input.prev_char();
                     let s = input.bump(1);
@@ -644,6 +741,10 @@ impl ParseSegments<'_> {
                 }
             }
             patterns::DOUBLE_QUOTE => {
+                if context == Context::QuotedArg('"') {
+                    return Some(true);
+                }
+
                 if self.mode.is(ParsingMode::SUBSTITUTIONS) {
                     let prev = input.prev_char();
                     let s = input.bump(1);
@@ -662,59 +763,49 @@ impl ParseSegments<'_> {
                     return Some(false);
                 }
             }
-            patterns::ELLIPSIS => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(3);
-                    items.push(Item::Substitution(Substitution::Text("…")));
-                    return Some(false);
-                }
+            patterns::ELLIPSIS if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(3);
+                items.push(Item::Substitution(Substitution::Text("…")));
+                return Some(false);
             }
-            patterns::EM_DASH => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(2);
-                    items.push(Item::Substitution(Substitution::Text("—")));
-                    return Some(false);
-                }
+            patterns::EM_DASH if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(2);
+                items.push(Item::Substitution(Substitution::Text("—")));
+                return Some(false);
             }
-            patterns::ARROW_R => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(2);
-                    items.push(Item::Substitution(Substitution::Text("→")));
-                    return Some(false);
-                }
+            patterns::ARROW_R if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(2);
+                items.push(Item::Substitution(Substitution::Text("→")));
+                return Some(false);
             }
-            patterns::ARROW_L => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(2);
-                    items.push(Item::Substitution(Substitution::Text("←")));
-                    return Some(false);
-                }
+            patterns::ARROW_L if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(2);
+                items.push(Item::Substitution(Substitution::Text("←")));
+                return Some(false);
             }
-            patterns::CIRCLE_C => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(3);
-                    items.push(Item::Substitution(Substitution::Text("©")));
-                    return Some(false);
-                }
+            patterns::CIRCLE_C if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(3);
+                items.push(Item::Substitution(Substitution::Text("©")));
+                return Some(false);
             }
-            patterns::CIRCLE_R => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(3);
-                    items.push(Item::Substitution(Substitution::Text("®")));
-                    return Some(false);
-                }
+            patterns::CIRCLE_R if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(3);
+                items.push(Item::Substitution(Substitution::Text("®")));
+                return Some(false);
             }
-            patterns::TRADEMARK => {
-                if self.mode.is(ParsingMode::SUBSTITUTIONS) {
-                    input.bump(4);
-                    items.push(Item::Substitution(Substitution::Text("™")));
-                    return Some(false);
-                }
+            patterns::TRADEMARK if self.mode.is(ParsingMode::SUBSTITUTIONS) => {
+                input.bump(4);
+                items.push(Item::Substitution(Substitution::Text("™")));
+                return Some(false);
             }
 
             patterns::LINE_FEED | patterns::CARRIAGE_RETURN => {
-                if let Context::Table | Context::LinkOrImg | Context::Heading | Context::CodeBlock =
-                    context
+                if let Context::Table
+                | Context::LinkOrImg
+                | Context::Heading
+                | Context::Label
+                | Context::QuotedArg(_)
+                | Context::CodeBlock = context
                 {
                     return Some(true);
                 }
@@ -722,7 +813,9 @@ impl ParseSegments<'_> {
                 if input.parse(ParseLineBreak(ind)).is_some() {
                     items.push(Item::LineBreak);
 
-                    if let Context::Global | Context::BlockBraces = context {
+                    if matches!(context, Context::Global | Context::BlockBraces)
+                        && self.mode.is(ParsingMode::HEADINGS)
+                    {
                         if let Some(u) = input.parse(Underline::parser(ind)) {
                             items.pop();
                             items.push(Item::Underline(u));
@@ -734,6 +827,14 @@ impl ParseSegments<'_> {
                         return Some(true);
                     }
 
+                    if input.dialect == Dialect::CommonMark {
+                        insert_hard_line_break(items, input);
+                    }
+                    return Some(false);
+                } else if input.dialect == Dialect::CommonMark && self.parse_lazy_line_break(input)
+                {
+                    items.push(Item::LineBreak);
+                    insert_hard_line_break(items, input);
                     return Some(false);
                 } else {
                     return Some(true);
@@ -746,24 +847,90 @@ impl ParseSegments<'_> {
         Some(false)
     }
 
+    /// In CommonMark, a paragraph in a quote or list item can be continued on
+    /// a line without the `>` or the indentation, called a lazy continuation
+    /// line
+    fn parse_lazy_line_break(&self, input: &mut Input) -> bool {
+        if !matches!(self.context, Context::Global | Context::BlockBraces) {
+            return false;
+        }
+        let mut input = input.start();
+        if input.parse(ParseLineBreak(Indents::new())).is_none()
+            || is_blank_line(input.rest())
+            || self.can_interrupt_paragraph(&mut input)
+        {
+            return false;
+        }
+        input.apply();
+        true
+    }
+
     fn can_interrupt_paragraph(&self, input: &mut Input) -> bool {
         use ParsingMode as P;
 
         let ind = self.ind;
         let ac = self.ac;
 
+        if input.dialect == Dialect::CommonMark && has_code_indent(input.rest()) {
+            return false;
+        }
+
         self.mode.is(P::CODE_BLOCKS) && input.can_parse(ParseCodeBlock { ind, mode: None, ac })
             || self.mode.is(P::COMMENTS) && input.can_parse(ParseComment)
+            || self.mode.is(P::HTML)
+                && input.dialect == Dialect::CommonMark
+                && html_blocks::can_interrupt_paragraph(input)
             || self.mode.is(P::HEADINGS) && input.can_parse(ParseHeading { ind, ac })
             || self.mode.is(P::TABLES) && input.can_parse(ParseTable { ind, ac })
             || self.mode.is(P::LISTS) && input.can_parse(ParseList { ind, ac, mode: None })
-            || self.mode.is(P::THEMATIC_BREAKS) && input.can_parse(ParseThematicBreak { ind })
+            || self.mode.is(P::LISTS) && input.can_parse(ParseLabeledList { ind, ac, mode: None })
+            || self.mode.is(P::THEMATIC_BREAKS) && input.can_parse(ParseThematicBreak)
             || self.mode.is(P::QUOTES) && input.can_parse(ParseQuote { ind, ac, mode: None })
-            || self.mode.is(P::LINKS_IMAGES) && input.can_parse(ParseLinkRefDef { ind })
+            || self.mode.is(P::LINKS_IMAGES)
+                && input.dialect == Dialect::Unidok
+                && input.can_parse(ParseLinkRefDef { ind })
+            || self.mode.is(P::LINKS_IMAGES)
+                && input.can_parse(ParseFootnoteDef {
+                    ind,
+                    context: self.context,
+                    mode: self.mode,
+                    ac,
+                })
+    }
+}
+
+/// In CommonMark, a backslash or at least 2 spaces at the end of a line are a
+/// hard line break, which is represented as a `<br>` element
+fn insert_hard_line_break(items: &mut Vec<Item>, input: &Input) {
+    let line_break = items.pop();
+    if let Some(Item::Text(text)) = items.last_mut() {
+        let s = &input[*text];
+        let content = if s.ends_with('\\') {
+            text.get(..s.len() - 1)
+        } else {
+            text.trim_end_matches(|c| c == ' ', &input.text)
+        };
+
+        if s.ends_with('\\') || s.len() - content.len() >= 2 {
+            let name_span = text.get(content.len()..);
+            *text = content;
+            items.push(Item::Html(HtmlNodeAst::Element(HtmlElemAst {
+                name: ElemName::Br,
+                name_span,
+                attrs: vec![],
+                content: None,
+                close: ElemClose::AutoSelfClosing,
+            })));
+        }
     }
+    items.extend(line_break);
 }
 
-fn is_blank_line(s: &str) -> bool {
-    let s = s.trim_start_matches(is_ws);
-    matches!(s.bytes().next(), Some(b'\n' | b'\r') | None)
+/// Returns whether only whitespace was lexed since the last line break
+fn is_at_line_start(items: &[Item], input: &Input) -> bool {
+    let rest = match items.split_last() {
+        Some((last, rest)) if last.is_blank_text(input) => rest,
+        _ => items,
+    };
+    matches!(rest.last(), Some(Item::LineBreak) | None)
 }
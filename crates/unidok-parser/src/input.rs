@@ -1,18 +1,65 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut, Index};
 
 use detached_str::{Str, StrSlice};
+use unidok_repr::config::{Autolink, Dialect, Limits};
+use unidok_repr::diagnostics::{Diagnostic, DiagnosticCode, IncludedFile};
 
+use crate::memo::MemoTable;
 use crate::{Parse, ParseInfallible};
 
 #[derive(Debug, Clone)]
 pub struct Input {
     pub text: Str,
     idx: usize,
+    /// The files included with `@INCLUDE`, whose text was appended to `text`
+    pub includes: Vec<IncludedFile>,
+    /// Set from the config, and changed by `@CONFIG(autolink=…)` while parsing
+    pub(crate) autolink: Autolink,
+    /// Set from the config, and changed by `@CONFIG(block_elements=…)` while
+    /// parsing
+    pub(crate) block_elements: Vec<String>,
+    /// Set from the config
+    pub(crate) limits: Limits,
+    /// Set from the config
+    pub(crate) dialect: Dialect,
+    /// The nesting depth of blocks and inline content at the current position
+    pub(crate) depth: u32,
+    /// The number of parsers invoked and bytes consumed so far. Bytes are
+    /// counted again when they are parsed again after backtracking.
+    steps: u64,
+    /// The positions where the nesting limit and the step budget were first
+    /// exceeded
+    exceeded: Exceeded,
+    pub(crate) memo: MemoTable,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Exceeded {
+    nesting: Option<usize>,
+    steps: Option<usize>,
 }
 
 impl Input {
     pub fn new(text: impl ToString) -> Self {
-        Input { text: text.to_string().into(), idx: 0 }
+        Input::new_at(text.to_string(), 0)
+    }
+
+    pub(crate) fn new_at(text: impl Into<Str>, idx: usize) -> Self {
+        Input {
+            text: text.into(),
+            idx,
+            includes: Vec::new(),
+            autolink: Autolink::default(),
+            block_elements: Vec::new(),
+            limits: Limits::default(),
+            dialect: Dialect::default(),
+            depth: 0,
+            steps: 0,
+            exceeded: Exceeded::default(),
+            memo: MemoTable::default(),
+        }
     }
 
     #[must_use]
@@ -30,12 +77,12 @@ impl Input {
     }
 
     pub fn rest(&self) -> &str {
-        &self.text[self.idx as usize..]
+        &self.text[self.idx..]
     }
 
     #[cfg(test)]
     pub fn prev(&self) -> &str {
-        &self.text[..self.idx as usize]
+        &self.text[..self.idx]
     }
 
     pub fn prev_slice_bytes(&self, bytes: usize) -> StrSlice {
@@ -44,15 +91,93 @@ impl Input {
 
     pub fn bump(&mut self, bytes: usize) -> StrSlice {
         self.idx += bytes;
+        self.steps += bytes as u64;
         self.text.get(self.idx - bytes..self.idx)
     }
 
+    /// Removes the text after `len` bytes
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.text = self.text[..len].into();
+        self.idx = self.idx.min(len);
+    }
+
+    pub(crate) fn idx(&self) -> usize {
+        self.idx
+    }
+
+    /// Moves forward to a position that was parsed before, e.g. when a cached
+    /// result is reused. Unlike [`Input::bump`], this doesn't count as work.
+    pub(crate) fn skip_to(&mut self, idx: usize) {
+        self.idx = idx;
+    }
+
+    /// Returns the number of bytes before the next `]` in the current line, if
+    /// there is one
+    pub(crate) fn find_closing_bracket(&mut self) -> Option<usize> {
+        let end = self.memo.closing_bracket(&self.text[..], self.idx)?;
+        Some(end - self.idx)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    fn steps_exceeded(&self) -> bool {
+        matches!(self.limits.max_steps, Some(max) if self.steps > max)
+    }
+
+    /// Finishes the hash of a parser for caching. The nesting depth and
+    /// whether the step budget is exceeded are included, because they affect
+    /// the result.
+    pub(crate) fn state_hash(&self, mut hasher: DefaultHasher) -> u64 {
+        (self.depth, self.steps_exceeded()).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns whether nested content should be parsed as plain text, because
+    /// the nesting limit or the step budget is exceeded
+    pub(crate) fn is_restricted(&mut self) -> bool {
+        if self.steps_exceeded() {
+            self.exceeded.steps.get_or_insert(self.idx);
+            true
+        } else if matches!(self.limits.max_nesting, Some(max) if self.depth > max) {
+            self.exceeded.nesting.get_or_insert(self.idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns diagnostics for the limits that were exceeded while parsing
+    pub(crate) fn limit_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let (Some(idx), Some(max)) = (self.exceeded.nesting, self.limits.max_nesting) {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::LimitExceeded,
+                format!("content nested more than {} levels deep is parsed as plain text", max),
+                self.text.get(idx..idx),
+            ));
+        }
+        if let (Some(idx), Some(max)) = (self.exceeded.steps, self.limits.max_steps) {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::LimitExceeded,
+                format!(
+                    "parsing took more than {} steps, so the rest is parsed as plain text",
+                    max
+                ),
+                self.text.get(idx..idx),
+            ));
+        }
+        diagnostics
+    }
+
     pub fn peek_char(&self) -> Option<char> {
         self.rest().chars().next()
     }
 
     pub fn prev_char(&self) -> Option<char> {
-        let parsed = &self.text[..self.idx as usize];
+        let parsed = &self.text[..self.idx];
         parsed.chars().last()
     }
 
@@ -61,9 +186,19 @@ impl Input {
     /// if `None` is returned.
     #[must_use]
     pub fn parse<P: Parse>(&mut self, mut parser: P) -> Option<P::Output> {
+        self.steps += 1;
         parser.parse(self)
     }
 
+    /// Parses nested content one level deeper. This fails if the nesting limit
+    /// or the step budget is exceeded.
+    pub(crate) fn parse_nested<P: Parse>(&mut self, parser: P) -> Option<P::Output> {
+        self.depth += 1;
+        let result = if self.is_restricted() { None } else { self.parse(parser) };
+        self.depth -= 1;
+        result
+    }
+
     /// This parses the specified parser and returns the result. The parser
     /// can't fail.
     pub fn parse_i<P: ParseInfallible>(&mut self, parser: P) -> P::Output {
@@ -73,12 +208,14 @@ impl Input {
     /// This tries to parse the specified parser. If it doesn't succeed, nothing
     /// happens.
     pub fn try_parse<P: Parse>(&mut self, mut parser: P) {
+        self.steps += 1;
         parser.parse(self);
     }
 
     /// This returns whether the parser can be successfully parsed. For
     /// correctness, the parser should NOT be bumped.
     pub fn can_parse<P: Parse>(&mut self, mut parser: P) -> bool {
+        self.steps += 1;
         parser.can_parse(self)
     }
 }
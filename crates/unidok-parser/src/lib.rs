@@ -4,25 +4,34 @@ mod test_macros;
 
 mod accumulate;
 mod blocks;
+mod front_matter;
 mod html;
+mod include;
 mod inlines;
 mod input;
 mod macros;
+mod memo;
 mod parse;
 mod parsing_mode;
 mod state;
 mod utils;
 
+use std::mem;
+
 use crate::blocks::ParseBlock;
 use crate::parse::{Parse, ParseInfallible};
 use crate::state::{Context, ParsingState};
 use crate::utils::Indents;
 
+use unidok_repr::ast::blocks::{BlockAst, ParagraphAst};
+use unidok_repr::ast::segments::SegmentAst;
 use unidok_repr::ast::AstData;
-use unidok_repr::config::Config;
+use unidok_repr::config::{Config, Dialect};
+use unidok_repr::diagnostics::{Diagnostic, DiagnosticCode};
 use unidok_repr::ir::blocks::AnnBlock;
 use unidok_repr::ir::IrState;
-use unidok_repr::{IntoIR, SyntaxSpan, ToSpans};
+use unidok_repr::metadata::Metadata;
+use unidok_repr::{IntoIR, SyntaxKind, SyntaxSpan, ToSpans};
 
 pub use crate::input::Input;
 
@@ -30,23 +39,214 @@ pub struct Doc<'a> {
     pub blocks: Vec<AnnBlock<'a>>,
     pub state: IrState<'a>,
     pub spans: Vec<SyntaxSpan>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A parsed document before it is converted to the IR. It contains the
+/// accumulated document data, e.g. headings and link reference definitions.
+pub struct AstDoc {
+    pub blocks: Vec<BlockAst>,
+    pub data: AstData,
+    pub spans: Vec<SyntaxSpan>,
+}
+
+impl Doc<'_> {
+    /// Returns the metadata from the front matter and `@META` macros
+    pub fn metadata(&self) -> &Metadata {
+        &self.state.metadata
+    }
+}
+
+impl AstDoc {
+    pub fn into_doc(mut self, text: &str) -> Doc<'_> {
+        let blocks = self.blocks.into_ir(text, &mut self.data);
+        self.data.check_unused_footnotes(text);
+        let mut diagnostics = mem::take(&mut self.data.diagnostics);
+        diagnostics.sort_by_key(|d| d.span.start);
+        let state = IrState::new(text, self.data);
+        Doc { blocks, state, spans: self.spans, diagnostics }
+    }
 }
 
 pub fn parse(input: &mut Input, config: Config) -> Doc<'_> {
-    let parsed = input.parse(ParseBlock::new_multi(None, ParsingState::new_global())).unwrap();
-    assert!(input.is_empty());
+    parse_ast(input, config).into_doc(&input.text)
+}
+
+pub fn parse_ast(input: &mut Input, config: Config) -> AstDoc {
+    let mut metadata = Metadata::new();
+    let mut diagnostics = Vec::new();
+    input.autolink = config.autolink;
+    input.block_elements = config.block_elements.clone();
+    input.limits = config.limits;
+    input.dialect = config.dialect;
+    truncate_input(input, &mut diagnostics);
+    let front_matter = match config.dialect {
+        Dialect::Unidok => front_matter::parse_front_matter(input, &mut metadata, &mut diagnostics),
+        Dialect::CommonMark => None,
+    };
+
+    let mut parsed =
+        input.parse(ParseBlock::new_multi(None, ParsingState::new_global())).unwrap_or_default();
+    parse_remaining_text(input, &mut parsed, &mut diagnostics);
+    diagnostics.extend(input.limit_diagnostics());
 
     let mut spans = Vec::new();
     if config.retrieve_spans {
+        if let Some(span) = front_matter {
+            spans.push(span.with(SyntaxKind::FrontMatter));
+        }
         for p in &parsed {
             p.to_spans(&mut spans);
         }
     }
 
+    diagnostics.extend(include::resolve_includes(&mut parsed, input, &config));
+
     let mut data = AstData::new(config);
+    data.metadata = metadata;
+    data.diagnostics = diagnostics;
     accumulate::accumulate_block_data(&parsed, &mut data, false, &input.text);
 
-    let blocks = parsed.into_ir(&input.text, &mut data);
-    let state = IrState::new(&input.text, data);
-    Doc { blocks, state, spans }
+    AstDoc { blocks: parsed, data, spans }
+}
+
+/// The block parser should always consume the whole input. If it doesn't due
+/// to a bug, the rest is added as plain text, so no content is lost.
+fn parse_remaining_text(
+    input: &mut Input,
+    parsed: &mut Vec<BlockAst>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if input.is_empty() {
+        return;
+    }
+    let rest = input.bump(input.len());
+    parsed.push(BlockAst::Paragraph(ParagraphAst { segments: vec![SegmentAst::Text(rest)] }));
+    diagnostics.push(Diagnostic::error(
+        DiagnosticCode::InternalError,
+        "the parser stopped unexpectedly, so the rest is parsed as plain text",
+        rest,
+    ));
+}
+
+/// Truncates the input if it is larger than the configured limit
+fn truncate_input(input: &mut Input, diagnostics: &mut Vec<Diagnostic>) {
+    let max = match input.limits.max_input_size {
+        Some(max) if input.text.len() > max => max,
+        _ => return,
+    };
+    let mut end = max;
+    while !input.text.is_char_boundary(end) {
+        end -= 1;
+    }
+    input.truncate(end);
+    diagnostics.push(Diagnostic::error(
+        DiagnosticCode::LimitExceeded,
+        format!("the input is larger than {} bytes, so the rest is ignored", max),
+        input.text.get(end..end),
+    ));
+}
+
+#[test]
+fn test_diagnostics() {
+    use unidok_repr::diagnostics::{DiagnosticCode, Severity};
+    use unidok_repr::Span;
+
+    let mut input = Input::new(
+        "@FOO\n[bar]\n\n@CONFIG(foo=bar)\n\n@BULLET(a=b)\n- a\n\n@CONFIG(x)\n\n\
+         @CONFIG(lang=xx autolink=bare)\n\n@META(x)\n",
+    );
+    let doc = parse(&mut input, Config::default());
+    // invalid configuration values are ignored
+    assert_eq!(doc.state.config.autolink, unidok_repr::config::Autolink::Bare);
+    let diagnostics: Vec<_> =
+        doc.diagnostics.iter().map(|d| (d.severity, d.code, d.span)).collect();
+
+    assert_eq!(
+        diagnostics,
+        [
+            (Severity::Error, DiagnosticCode::UnknownMacro, Span { start: 1, end: 4 }),
+            (Severity::Warning, DiagnosticCode::UnresolvedLinkRef, Span { start: 6, end: 9 }),
+            (Severity::Error, DiagnosticCode::UnknownConfigKey, Span { start: 20, end: 23 }),
+            (Severity::Error, DiagnosticCode::InvalidMacroArgs, Span { start: 31, end: 37 }),
+            (Severity::Error, DiagnosticCode::InvalidMacroArgs, Span { start: 49, end: 55 }),
+            (Severity::Error, DiagnosticCode::InvalidConfigValue, Span { start: 68, end: 72 }),
+            (Severity::Error, DiagnosticCode::InvalidMacroArgs, Span { start: 93, end: 97 }),
+        ]
+    );
+}
+
+#[test]
+fn test_metadata() {
+    use unidok_repr::metadata::MetaValue;
+
+    let mut input =
+        Input::new("---\ntitle: Foo\ndraft: true\n---\n@META(title=Bar tags=[a 2])\n\nText");
+    let doc = parse(&mut input, Config::default());
+    assert_eq!(doc.diagnostics, vec![]);
+
+    let metadata = doc.metadata();
+    assert_eq!(metadata.title(), Some("Bar"));
+    assert_eq!(metadata.get_bool("draft"), Some(true));
+    assert_eq!(
+        metadata.get_list("tags"),
+        Some(&[MetaValue::String("a".into()), MetaValue::Int(2)][..])
+    );
+}
+
+#[test]
+fn test_footnote_diagnostics() {
+    use unidok_repr::diagnostics::DiagnosticCode;
+    use unidok_repr::Span;
+
+    let mut input = Input::new("[^a] [^b] [^a]\n\n[^a]: A\n[^c]: C");
+    let doc = parse(&mut input, Config::default());
+    let diagnostics: Vec<_> = doc.diagnostics.iter().map(|d| (d.code, d.span)).collect();
+
+    assert_eq!(
+        diagnostics,
+        [
+            (DiagnosticCode::UndefinedFootnote, Span { start: 7, end: 8 }),
+            (DiagnosticCode::UnusedFootnote, Span { start: 26, end: 27 }),
+        ]
+    );
+}
+
+#[test]
+fn test_limits() {
+    use unidok_repr::config::Limits;
+    use unidok_repr::diagnostics::DiagnosticCode;
+
+    let parse_with = |text: String, limits: Limits| {
+        let mut input = Input::new(text);
+        let doc = parse(&mut input, Config { limits, ..Config::default() });
+        let codes: Vec<_> = doc.diagnostics.iter().map(|d| d.code).collect();
+        (codes, doc.blocks.len(), input.text.len())
+    };
+
+    // the work must grow linearly with the input, even when nothing is closed
+    let steps = |text: &str, n: usize| {
+        let mut input = Input::new(text.repeat(n));
+        let limits = Limits { max_nesting: Some(10), ..Limits::none() };
+        parse(&mut input, Config { limits, ..Config::default() });
+        input.steps()
+    };
+    for text in ["[", "![", "@A{", "|| [", "[](", "*[", "[^", "[a](b"] {
+        let (small, large) = (steps(text, 1000), steps(text, 4000));
+        assert!(large < small * 5, "{:?}: {} steps for 1000, {} for 4000", text, small, large);
+    }
+    let lists: String = (0..60).map(|i| format!("{}- a\n", "  ".repeat(i))).collect();
+    assert_eq!(parse_with(lists, Limits::default()).0, []);
+
+    let limits = Limits { max_nesting: Some(3), ..Limits::none() };
+    let (codes, _, _) = parse_with("> > > > > a".into(), limits);
+    assert_eq!(codes, [DiagnosticCode::LimitExceeded]);
+
+    let limits = Limits { max_steps: Some(10_000), ..Limits::none() };
+    let (codes, blocks, _) = parse_with("[a".repeat(5000), limits);
+    assert_eq!((&codes[..], blocks), (&[DiagnosticCode::LimitExceeded][..], 1));
+
+    let limits = Limits { max_input_size: Some(2), ..Limits::none() };
+    let (codes, _, len) = parse_with("aäb".into(), limits);
+    assert_eq!((&codes[..], len), (&[DiagnosticCode::LimitExceeded][..], 1));
 }
@@ -23,10 +23,12 @@ impl Parse for ParseMacroArgs<'_> {
             return Some(None);
         }
         let content = match self.name {
-            "LOAD" => MacroArgs::Raw(input.parse_i(ParseRaw)),
-            _ => MacroArgs::TokenTrees(
-                input.parse(ParseTokenTrees { ind: self.ind.push_indent(2), ac: self.ac })?,
-            ),
+            "LOAD" | "INCLUDE" => MacroArgs::Raw(input.parse_i(ParseRaw)),
+            _ => MacroArgs::TokenTrees(input.parse(ParseTokenTrees {
+                ind: self.ind.push_indent(2),
+                ac: self.ac,
+                segments: matches!(self.name, "DETAILS" | "CAPTION" | "FIGURE"),
+            })?),
         };
 
         input.try_parse(ParseLineBreak(self.ind));
@@ -8,7 +8,7 @@ use crate::utils::{ParseLineBreak, ParseLineEnd, ParseSpaces, ParseSpacesU8};
 use crate::{Context, Input, Parse};
 
 use super::args::ParseMacroArgs;
-use super::utils::{get_parsing_mode, ParseClosingBrace, ParseOpeningBrace};
+use super::utils::{apply_config, get_parsing_mode, ParseClosingBrace, ParseOpeningBrace};
 
 pub(crate) struct ParseBlockMacro<'a> {
     mode: Option<ParsingMode>,
@@ -42,6 +42,10 @@ impl Parse for ParseBlockMacro<'_> {
 
         input.parse_i(ParseSpaces);
 
+        if name_str == "CONFIG" {
+            apply_config(&args, &mut input);
+        }
+
         let mode = get_parsing_mode(&name_str, &args, &input)?.or(self.mode);
 
         let content = if input.parse(ParseLineEnd).is_some() {
@@ -1,3 +1,6 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+
 use aho_corasick::AhoCorasick;
 use unidok_repr::ast::html::HtmlNodeAst;
 use unidok_repr::ast::macros::InlineMacroAst;
@@ -9,6 +12,7 @@ use crate::inlines::code::ParseCode;
 use crate::inlines::images::ParseImage;
 use crate::inlines::links::ParseLink;
 use crate::inlines::math::ParseMath;
+use crate::memo::Memoize;
 use crate::parsing_mode::ParsingMode;
 use crate::{Indents, Input, Parse};
 
@@ -63,3 +67,9 @@ impl Parse for ParseInlineMacro<'_> {
         Some(InlineMacroAst { name, args, segment })
     }
 }
+
+impl Memoize for ParseInlineMacro<'_> {
+    fn hash_fields(&self, state: &mut DefaultHasher) {
+        (self.ind, self.mode, self.ac as *const AhoCorasick).hash(state);
+    }
+}
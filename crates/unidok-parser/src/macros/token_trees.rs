@@ -2,13 +2,22 @@ use aho_corasick::AhoCorasick;
 use unidok_repr::ast::macros::{TokenTree, TokenTreeAtom};
 
 use crate::inlines::braces::ParseBraces;
+use crate::inlines::Segments;
+use crate::parsing_mode::ParsingMode;
 use crate::utils::{is_ws, Indents, ParseSpaces, ParseWsNoBlankLinkes, QuotedStringWithEscapes};
-use crate::{Input, Parse};
+use crate::{Context, Input, Parse};
 
 #[derive(Clone, Copy)]
 pub(crate) struct ParseTokenTree<'a> {
     ind: Indents<'a>,
     ac: &'a AhoCorasick,
+    segments: bool,
+}
+
+impl ParseTokenTree<'_> {
+    fn atom_parser(&self) -> ParseTokenTreeAtom<'_> {
+        ParseTokenTreeAtom { ind: self.ind, ac: self.ac, segments: self.segments }
+    }
 }
 
 impl Parse for ParseTokenTree<'_> {
@@ -19,15 +28,14 @@ impl Parse for ParseTokenTree<'_> {
 
         match input.peek_char() {
             Some('[' | '{' | '"' | '\'') => {
-                let atom = input.parse(ParseTokenTreeAtom { ind: self.ind, ac: self.ac })?;
+                let atom = input.parse(self.atom_parser())?;
                 input.apply();
                 Some(TokenTree::Atom(atom))
             }
             Some(']' | '}' | ')') => None,
             Some(_) => {
                 let rest = input.rest();
-                let idx =
-                    rest.find(|c| matches!(c, '=' | ' ' | '\t' | '\n' | '\r' | ')' | ']' | '}'));
+                let idx = rest.find(['=', ' ', '\t', '\n', '\r', ')', ']', '}']);
                 if let Some(idx) = idx {
                     let remaining = rest[idx..].trim_start_matches(is_ws);
                     if remaining.starts_with('=') {
@@ -35,14 +43,13 @@ impl Parse for ParseTokenTree<'_> {
                         input.parse_i(ParseSpaces);
                         input.parse('=').unwrap();
                         input.parse_i(ParseSpaces);
-                        let value =
-                            input.parse(ParseTokenTreeAtom { ind: self.ind, ac: self.ac })?;
+                        let value = input.parse(self.atom_parser())?;
                         input.apply();
                         return Some(TokenTree::KV(key, value));
                     }
                 }
 
-                let atom = input.parse(ParseTokenTreeAtom { ind: self.ind, ac: self.ac })?;
+                let atom = input.parse(self.atom_parser())?;
                 input.apply();
                 Some(TokenTree::Atom(atom))
             }
@@ -54,6 +61,7 @@ impl Parse for ParseTokenTree<'_> {
 pub(crate) struct ParseTokenTreeAtom<'a> {
     ind: Indents<'a>,
     ac: &'a AhoCorasick,
+    segments: bool,
 }
 
 impl Parse for ParseTokenTreeAtom<'_> {
@@ -65,7 +73,11 @@ impl Parse for ParseTokenTreeAtom<'_> {
         match input.peek_char() {
             Some('[') => {
                 input.bump(1);
-                let tuple = input.parse(ParseTokenTrees { ind: self.ind, ac: self.ac })?;
+                let tuple = input.parse_nested(ParseTokenTrees {
+                    ind: self.ind,
+                    ac: self.ac,
+                    segments: self.segments,
+                })?;
                 input.parse(']')?;
                 input.apply();
                 Some(TokenTreeAtom::Tuple(tuple))
@@ -74,17 +86,27 @@ impl Parse for ParseTokenTreeAtom<'_> {
                 input.bump(1);
                 let braces = input.parse(ParseBraces { ind: self.ind, ac: self.ac, mode: None })?;
                 input.parse('}')?;
-                input.apply();
-                Some(TokenTreeAtom::Braces(braces))
+                let span = input.apply();
+                Some(TokenTreeAtom::Braces(braces, span))
+            }
+            Some(quote @ ('"' | '\'')) if self.segments => {
+                input.bump(1);
+                let mode = ParsingMode::new_all();
+                let segments = input
+                    .parse(Segments::parser(self.ind, Context::QuotedArg(quote), mode, self.ac))?
+                    .into_segments_no_underline_zero()?;
+                input.parse(quote)?;
+                let span = input.apply();
+                Some(TokenTreeAtom::QuotedSegments(segments, span))
             }
             Some('"' | '\'') => {
                 let content = input.parse(QuotedStringWithEscapes(self.ind))?;
-                input.apply();
-                Some(TokenTreeAtom::QuotedWord(content))
+                let span = input.apply();
+                Some(TokenTreeAtom::QuotedWord(content, span))
             }
             Some(_) => {
                 let rest = input.rest();
-                let idx = rest.find(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | ')' | ']' | '}'))?;
+                let idx = rest.find([' ', '\t', '\n', '\r', ')', ']', '}'])?;
                 let word = input.bump(idx);
                 input.apply();
                 Some(TokenTreeAtom::Word(word))
@@ -97,13 +119,15 @@ impl Parse for ParseTokenTreeAtom<'_> {
 pub(crate) struct ParseTokenTrees<'a> {
     pub ind: Indents<'a>,
     pub ac: &'a AhoCorasick,
+    /// Whether quoted strings are parsed as inline content
+    pub segments: bool,
 }
 
 impl Parse for ParseTokenTrees<'_> {
     type Output = Vec<TokenTree>;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
-        let parser = ParseTokenTree { ind: self.ind, ac: self.ac };
+        let parser = ParseTokenTree { ind: self.ind, ac: self.ac, segments: self.segments };
         let mut token_trees = Vec::new();
 
         input.parse(ParseWsNoBlankLinkes(self.ind))?;
@@ -1,5 +1,6 @@
 use detached_str::StrSlice;
-use unidok_repr::ast::macros::MacroArgs;
+use unidok_repr::ast::macros::{MacroArgs, TokenTree, TokenTreeAtom};
+use unidok_repr::config::{parse_elem_list, Autolink};
 
 use crate::parsing_mode::ParsingMode;
 use crate::utils::{ParseLineBreak, ParseLineEnd, ParseSpaces};
@@ -45,6 +46,38 @@ pub(super) fn get_parsing_mode(
     })
 }
 
+/// Applies the parts of `@CONFIG` that affect parsing. They apply to the
+/// rest of the document. Invalid values are reported when the macro is
+/// converted to the IR.
+pub(super) fn apply_config(args: &Option<MacroArgs>, input: &mut Input) {
+    let args = match args {
+        Some(MacroArgs::TokenTrees(tts)) => tts,
+        _ => return,
+    };
+    for tt in args {
+        if let TokenTree::KV(key, value) = tt {
+            let value = match value {
+                TokenTreeAtom::Word(w) => &input[*w],
+                TokenTreeAtom::QuotedWord(w, _) => w.as_str(),
+                _ => continue,
+            };
+            match &input[*key] {
+                "autolink" => {
+                    input.autolink = match value {
+                        "angle" => Autolink::Angle,
+                        "bare" => Autolink::Bare,
+                        _ => continue,
+                    };
+                }
+                "block_elements" => input.block_elements = parse_elem_list(value),
+                _ => continue,
+            }
+            // cached results may depend on the old configuration
+            input.memo.clear();
+        }
+    }
+}
+
 pub(crate) struct ParseMacroName;
 
 impl Parse for ParseMacroName {
@@ -0,0 +1,176 @@
+use std::any::{type_name, Any};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::{Input, Parse};
+
+/// The number of entries after which the entries that weren't used recently
+/// are dropped, so memory usage stays bounded for large documents
+const MAX_ENTRIES: usize = 1 << 16;
+
+/// A parser whose result only depends on its fields and the input at the
+/// current position, so it can be cached.
+pub(crate) trait Memoize: Parse {
+    /// Hashes the fields that affect the result
+    fn hash_fields(&self, state: &mut DefaultHasher);
+}
+
+/// Caches the result of a parser, so that it isn't parsed again at the same
+/// position when an enclosing parser backtracks. For example, in `[[[[a`,
+/// every `[` is tried as the start of a link by each enclosing link attempt,
+/// and then again as text. Without caching, this takes exponential time.
+///
+/// The result is shared with the cache; use [`unshare`] to get the value.
+pub(crate) struct Memoized<P>(pub P);
+
+impl<P> Parse for Memoized<P>
+where
+    P: Memoize,
+    P::Output: Send + Sync + 'static,
+{
+    type Output = Arc<P::Output>;
+
+    fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
+        let mut hasher = DefaultHasher::new();
+        type_name::<P>().hash(&mut hasher);
+        self.0.hash_fields(&mut hasher);
+        let key = (input.idx(), input.state_hash(hasher));
+
+        if let Some((result, end)) = input.memo.get::<Arc<P::Output>>(key) {
+            input.skip_to(end);
+            return result;
+        }
+
+        let result = self.0.parse(input).map(Arc::new);
+        input.memo.insert(key, result.clone(), input.idx());
+        result
+    }
+}
+
+/// Returns the value of a cached result. It is only cloned if it is still
+/// shared with the cache.
+pub(crate) fn unshare<T: Clone>(result: Arc<T>) -> T {
+    Arc::try_unwrap(result).unwrap_or_else(|result| (*result).clone())
+}
+
+/// The cached results, indexed by the input position and a hash of the
+/// parser and the parsing state. It is cleared when the parser configuration
+/// changes, e.g. with `@CONFIG(autolink=…)`.
+#[derive(Default)]
+pub(crate) struct MemoTable {
+    map: BoundedMap<(Box<dyn Any + Send + Sync>, usize)>,
+    /// Positions where the content of a link, image or braces can't be
+    /// closed, indexed by a hash of the segments parser
+    unclosed: BoundedMap<()>,
+    /// The last position for which the next `]` in the line was searched,
+    /// and the result
+    closing_bracket: Option<(usize, usize, Option<usize>)>,
+}
+
+impl MemoTable {
+    fn get<T: Clone + 'static>(&mut self, key: (usize, u64)) -> Option<(Option<T>, usize)> {
+        let (result, end) = self.map.get(key)?;
+        Some((result.downcast_ref::<Option<T>>()?.clone(), *end))
+    }
+
+    fn insert<T: Send + Sync + 'static>(
+        &mut self,
+        key: (usize, u64),
+        result: Option<T>,
+        end: usize,
+    ) {
+        self.map.insert(key, (Box::new(result), end));
+    }
+
+    /// Returns whether the content starting at this position was found to be
+    /// unclosed before
+    pub(crate) fn is_unclosed(&mut self, key: (usize, u64)) -> bool {
+        self.unclosed.get(key).is_some()
+    }
+
+    /// Records that the content starting at these positions can't be closed.
+    /// This is the case for every opening bracket or brace that is still open
+    /// when the content of an enclosing link, image or braces ends, since
+    /// parsing the content again from there would reach the same end.
+    pub(crate) fn insert_unclosed(&mut self, positions: &[usize], key: u64) {
+        for &idx in positions {
+            self.unclosed.insert((idx, key), ());
+        }
+    }
+
+    /// Returns the position of the next `]` in the line. The result is reused
+    /// for later positions before that `]` or line break, so that scanning
+    /// for it from each `[` in a line takes linear time.
+    pub(crate) fn closing_bracket(&mut self, text: &str, idx: usize) -> Option<usize> {
+        match self.closing_bracket {
+            Some((start, end, result)) if start <= idx && idx <= end => result,
+            _ => {
+                let end = text[idx..].find(['\r', '\n', ']']).map_or(text.len(), |i| idx + i);
+                let result = Some(end).filter(|&end| text[end..].starts_with(']'));
+                self.closing_bracket = Some((idx, end, result));
+                result
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+        self.unclosed.clear();
+        self.closing_bracket = None;
+    }
+}
+
+/// A map with at most `2 * MAX_ENTRIES` entries. When it is full, the entries
+/// that weren't used since the last time it was full are dropped.
+struct BoundedMap<V> {
+    recent: HashMap<(usize, u64), V>,
+    old: HashMap<(usize, u64), V>,
+}
+
+impl<V> Default for BoundedMap<V> {
+    fn default() -> Self {
+        BoundedMap { recent: HashMap::new(), old: HashMap::new() }
+    }
+}
+
+impl<V> BoundedMap<V> {
+    fn get(&mut self, key: (usize, u64)) -> Option<&V> {
+        if !self.recent.contains_key(&key) {
+            let value = self.old.remove(&key)?;
+            self.insert(key, value);
+        }
+        self.recent.get(&key)
+    }
+
+    fn insert(&mut self, key: (usize, u64), value: V) {
+        if self.recent.len() >= MAX_ENTRIES {
+            self.old = std::mem::take(&mut self.recent);
+        }
+        self.recent.insert(key, value);
+    }
+
+    fn len(&self) -> usize {
+        self.recent.len() + self.old.len()
+    }
+
+    fn clear(&mut self) {
+        self.recent.clear();
+        self.old.clear();
+    }
+}
+
+impl Clone for MemoTable {
+    /// The cache is not cloned, since it can be recomputed
+    fn clone(&self) -> Self {
+        MemoTable::default()
+    }
+}
+
+impl fmt::Debug for MemoTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MemoTable({} entries)", self.map.len())
+    }
+}
@@ -1,6 +1,8 @@
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use unidok_repr::config::Dialect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct ParsingMode(u16);
 
 impl ParsingMode {
@@ -37,6 +39,21 @@ impl ParsingMode {
         Self(0b11_1111_1111_1111)
     }
 
+    /// Everything that is supported by the dialect. CommonMark has no
+    /// macros, math, tables, limiters, substitutions or comments.
+    pub fn new_default(dialect: Dialect) -> Self {
+        match dialect {
+            Dialect::Unidok => Self::new_all(),
+            Dialect::CommonMark => Self::new_all()
+                .unset(Self::MACROS)
+                .unset(Self::MATH)
+                .unset(Self::TABLES)
+                .unset(Self::LIMITER)
+                .unset(Self::SUBSTITUTIONS)
+                .unset(Self::COMMENTS),
+        }
+    }
+
     pub fn new_nothing() -> Self {
         Self(0)
     }
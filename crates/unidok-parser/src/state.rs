@@ -6,7 +6,7 @@ use crate::inlines::segments;
 use crate::utils::Indents;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum Context {
+pub(crate) enum Context<'a> {
     InlineBraces,
     BlockBraces,
     Table,
@@ -14,22 +14,27 @@ pub(crate) enum Context {
     Code(u8),
     CodeBlock,
     Heading,
-    InlineHtml(ElemName),
-    BlockHtml(ElemName),
+    /// The label of a labeled list item, which ends with `::`
+    Label,
+    /// A quoted macro argument containing inline content, which ends with the
+    /// given quote character
+    QuotedArg(char),
+    InlineHtml(&'a ElemName),
+    BlockHtml(&'a ElemName),
     Global,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct ParsingState<'a> {
     indentation: Indents<'a>,
-    context: Context,
+    context: Context<'a>,
     special_chars: &'a AhoCorasick,
 }
 
 impl<'a> ParsingState<'a> {
     pub(crate) fn new(
         indentation: Indents<'a>,
-        context: Context,
+        context: Context<'a>,
         special_chars: &'a AhoCorasick,
     ) -> Self {
         ParsingState { indentation, context, special_chars }
@@ -42,11 +47,11 @@ impl<'a> ParsingState<'a> {
         ParsingState { indentation: Indents::new(), context: Context::Global, special_chars }
     }
 
-    pub(crate) fn ind(&self) -> Indents {
+    pub(crate) fn ind(&self) -> Indents<'_> {
         self.indentation
     }
 
-    pub(crate) fn context(&self) -> Context {
+    pub(crate) fn context(&self) -> Context<'a> {
         self.context
     }
 
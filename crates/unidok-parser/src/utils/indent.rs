@@ -1,9 +1,11 @@
 use std::num::NonZeroU8;
 
-use super::{is_ws, ParseAtMostNSpaces, ParseWsAndLineEnd};
+use unidok_repr::config::Dialect;
+
+use super::{is_ws, ParseAtMostNSpaces, ParseOneWS, ParseWsAndLineEnd};
 use crate::{Input, Parse};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Indentation {
     Spaces(NonZeroU8),
     QuoteMarker,
@@ -42,7 +44,7 @@ impl Parse for ParseQuoteMarker {
 /// - Indentation of _n_ spaces
 /// - Quote indentation (The `>` character must be repeated in every line at the
 ///   correct indentation level)
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub(crate) struct Indents<'a> {
     root: INode<'a>,
 }
@@ -91,18 +93,16 @@ impl Parse for ParseLineBreak<'_> {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 enum INode<'a> {
-    Node { ind: Indentation, next: &'a INode<'a> },
+    Node {
+        ind: Indentation,
+        next: &'a INode<'a>,
+    },
+    #[default]
     Tail,
 }
 
-impl Default for INode<'_> {
-    fn default() -> Self {
-        INode::Tail
-    }
-}
-
 enum State {
     Continue,
     Done,
@@ -123,6 +123,19 @@ fn parse_indentation_rec(node: INode<'_>, input: &mut Input) -> State {
                             _ => State::Error,
                         }
                     }
+                    // in CommonMark, a blank line without `>` ends the quote
+                    Indentation::QuoteMarker if input.dialect == Dialect::CommonMark => {
+                        let mut input = input.start();
+                        input.try_parse(ParseAtMostNSpaces(3));
+                        match input.parse(ParseQuoteMarker) {
+                            Some(_) => {
+                                input.try_parse(ParseOneWS);
+                                input.apply();
+                                State::Continue
+                            }
+                            None => State::Error,
+                        }
+                    }
                     Indentation::QuoteMarker => match input.parse(ParseQuoteMarker) {
                         None if input.parse(ParseWsAndLineEnd).is_some() => State::Done,
                         Some(_) => State::Continue,
@@ -7,7 +7,7 @@ impl Parse for char {
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
         if input.rest().starts_with(*self) {
-            Some(input.bump(self.len_utf8() as usize))
+            Some(input.bump(self.len_utf8()))
         } else {
             None
         }
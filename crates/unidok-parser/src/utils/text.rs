@@ -8,7 +8,7 @@ use super::Indents;
 /// Parses ASCII text case-insensitively
 pub(crate) struct AsciiCI<T>(pub T);
 
-impl<'a> Parse for AsciiCI<&'a str> {
+impl Parse for AsciiCI<&str> {
     type Output = StrSlice;
 
     fn parse(&mut self, input: &mut Input) -> Option<Self::Output> {
@@ -75,7 +75,7 @@ impl Parse for QuotedString<'_> {
 
         loop {
             let rest = input.rest();
-            let idx = rest.find(|c| matches!(c, '"' | '\'' | '\n' | '\r'))?;
+            let idx = rest.find(['"', '\'', '\n', '\r'])?;
             match rest[idx..].bytes().next() {
                 Some(b'"') if quote == QuoteMarkType::Double => {
                     content.push_str(&rest[..idx]);
@@ -117,8 +117,8 @@ impl Parse for QuotedStringWithEscapes<'_> {
 
         loop {
             let rest = input.rest();
-            let idx = rest.find(|c| matches!(c, '"' | '\'' | '\\' | '\n' | '\r'))?;
-            match rest[idx..].as_bytes() {
+            let idx = rest.find(['"', '\'', '\\', '\n', '\r'])?;
+            match &rest.as_bytes()[idx..] {
                 [b'"', ..] if quote == QuoteMarkType::Double => {
                     content.push_str(&rest[..idx]);
                     input.bump(idx);
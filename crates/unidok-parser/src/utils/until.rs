@@ -53,7 +53,7 @@ impl ParseInfallible for Until<char> {
     }
 }
 
-impl<'a> ParseInfallible for Until<&'a str> {
+impl ParseInfallible for Until<&str> {
     type Output = StrSlice;
 
     #[inline]
@@ -83,7 +83,7 @@ impl Parse for Until<char> {
     }
 }
 
-impl<'a> Parse for Until<&'a str> {
+impl Parse for Until<&str> {
     type Output = StrSlice;
 
     #[inline]
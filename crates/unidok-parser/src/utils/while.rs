@@ -14,7 +14,7 @@ impl<F: Fn(char) -> bool> ParseInfallible for While<F> {
         loop {
             match input.peek_char() {
                 Some(c) if self.0(c) => {
-                    input.bump(c.len_utf8() as usize);
+                    input.bump(c.len_utf8());
                 }
                 _ => break,
             };
@@ -31,7 +31,7 @@ impl ParseInfallible for While<char> {
         loop {
             match input.peek_char() {
                 Some(c) if c == self.0 => {
-                    input.bump(c.len_utf8() as usize);
+                    input.bump(c.len_utf8());
                 }
                 _ => break,
             };
@@ -11,6 +11,28 @@ pub(crate) fn is_ws(c: char) -> bool {
     matches!(c, ' ' | '\t')
 }
 
+/// Returns whether the line contains only spaces and tabs.
+pub(crate) fn is_blank_line(s: &str) -> bool {
+    let s = s.trim_start_matches(is_ws);
+    matches!(s.bytes().next(), Some(b'\n' | b'\r') | None)
+}
+
+/// Returns whether the line starts with at least 4 columns of indentation and
+/// isn't blank. In the CommonMark dialect, such a line is an indented code
+/// block, unless it continues a paragraph.
+pub(crate) fn has_code_indent(s: &str) -> bool {
+    let mut columns = 0;
+    for c in s.bytes() {
+        match c {
+            b' ' => columns += 1,
+            b'\t' => columns += 4 - columns % 4,
+            b'\n' | b'\r' => return false,
+            _ => return columns >= 4,
+        }
+    }
+    false
+}
+
 /// Parses 0-255 spaces or tabs. One tab counts as 4 spaces. This parser never
 /// fails.
 pub(crate) struct ParseSpacesU8;
@@ -116,6 +138,9 @@ impl Parse for ParseAtMostNSpaces {
         let mut bytes = 0;
 
         for c in input.rest().bytes() {
+            if visual_spaces >= self.0 {
+                break;
+            }
             match c {
                 b' ' => {
                     visual_spaces += 1;
@@ -127,9 +152,6 @@ impl Parse for ParseAtMostNSpaces {
                 }
                 _ => break,
             }
-            if visual_spaces == self.0 {
-                break;
-            }
         }
 
         if bytes > 0 {
@@ -13,12 +13,14 @@ pub enum BlockAst {
     Table(TableAst),
     ThematicBreak(ThematicBreakAst),
     List(ListAst),
+    LabeledList(LabeledListAst),
     Quote(QuoteAst),
     BlockMacro(BlockMacro),
     BlockHtml(HtmlNodeAst),
 
     Comment(Comment),
     LinkRefDef(LinkRefDef),
+    FootnoteDef(FootnoteDef),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -36,6 +38,9 @@ pub struct CodeBlockAst {
 pub enum FenceType {
     Backticks(u32),
     Tildes(u32),
+    /// A code block indented by 4 spaces, which only exists in the CommonMark
+    /// dialect
+    Indented,
 }
 
 impl FenceType {
@@ -58,6 +63,7 @@ pub struct HeadingAst {
     pub level: u8,
     pub kind: HeadingKind,
     pub segments: Vec<SegmentAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -80,6 +86,13 @@ pub struct LinkRefDef {
     pub title: Option<String>,
 }
 
+/// A footnote definition, e.g. `[^foo]: Footnote text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FootnoteDef {
+    pub name: StrSlice,
+    pub segments: Vec<SegmentAst>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableAst {
     pub rows: Vec<TableRowAst>,
@@ -89,12 +102,24 @@ pub struct TableAst {
 pub struct TableRowAst {
     pub is_header_row: bool,
     pub cells: Vec<TableCellAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableCellAst {
     pub meta: CellMetaAst,
-    pub segments: Vec<SegmentAst>,
+    pub content: CellContentAst,
+    pub meta_span: Span,
+    /// The span of the cell, including the meta, but not the leading `|`
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellContentAst {
+    Inline(Vec<SegmentAst>),
+    /// A cell containing blocks, which starts with a `{` at the end of the
+    /// line and ends with a `}` on its own line
+    Blocks(Vec<BlockAst>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -144,6 +169,20 @@ pub struct ListAst {
     pub indent_spaces: u8,
     pub bullet: Bullet,
     pub items: Vec<Vec<BlockAst>>,
+    /// The span of each item's bullet
+    pub bullets: Vec<Span>,
+    /// The checkbox of each item, if it is a task list item
+    pub tasks: Vec<Option<TaskMarker>>,
+    /// Whether the items or their blocks are separated by blank lines. This is
+    /// only determined in the CommonMark dialect.
+    pub loose: bool,
+}
+
+/// The checkbox of a task list item, e.g. `[ ]` or `[x]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskMarker {
+    pub checked: bool,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -176,6 +215,20 @@ pub enum ListKind {
     Parens,
 }
 
+/// A labeled list, e.g. `Term:: Definition`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledListAst {
+    pub items: Vec<LabeledItemAst>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledItemAst {
+    pub label: Vec<SegmentAst>,
+    /// The span of the `::` after the label
+    pub marker: Span,
+    pub content: Vec<BlockAst>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct QuoteAst {
     pub content: Vec<BlockAst>,
@@ -1,20 +1,29 @@
 use std::collections::HashMap;
 
-use super::blocks::{HeadingAst, LinkRefDef};
+use super::blocks::{FootnoteDef, HeadingAst, LinkRefDef};
 use super::segments::LinkAst;
-use crate::config::Config;
+use crate::config::{Config, Dialect};
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use crate::metadata::Metadata;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AstData {
     pub link_ref_defs: HashMap<String, LinkRefDef>,
     pub headings: Vec<HeadingAst>,
     pub contains_math: bool,
+    pub math_script_in_head: bool,
 
     pub footnotes: Vec<LinkAst>,
     pub next_footnote: u32,
     pub next_footnote_def: u32,
+    pub footnote_defs: HashMap<String, FootnoteDef>,
+    pub named_footnotes: HashMap<String, NamedFootnote>,
+    /// The next number for each kind of figure, indexed by `FigureKind`
+    pub next_figure: [u32; 3],
 
     pub config: Config,
+    pub metadata: Metadata,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl AstData {
@@ -23,10 +32,51 @@ impl AstData {
             link_ref_defs: HashMap::new(),
             headings: Vec::new(),
             contains_math: false,
+            math_script_in_head: false,
             footnotes: Vec::new(),
             next_footnote: 1,
             next_footnote_def: 1,
+            footnote_defs: HashMap::new(),
+            named_footnotes: HashMap::new(),
+            next_figure: [1; 3],
             config,
+            metadata: Metadata::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+/// A footnote definition that was referenced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedFootnote {
+    pub num: u32,
+    /// The number of references to the footnote
+    pub refs: u32,
+}
+
+impl AstData {
+    /// Returns the key of a link reference definition with this label. In the
+    /// CommonMark dialect, labels are case-insensitive and consecutive
+    /// whitespace is collapsed.
+    pub fn link_ref_key(&self, label: &str) -> String {
+        match self.config.dialect {
+            Dialect::Unidok => label.to_string(),
+            Dialect::CommonMark => {
+                label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+            }
+        }
+    }
+
+    /// Adds a warning for every footnote definition that is never referenced
+    pub fn check_unused_footnotes(&mut self, text: &str) {
+        for (name, def) in &self.footnote_defs {
+            if !self.named_footnotes.contains_key(name) {
+                self.diagnostics.push(Diagnostic::warning(
+                    DiagnosticCode::UnusedFootnote,
+                    format!("footnote `{}` is never used", def.name.to_str(text)),
+                    def.name,
+                ));
+            }
         }
     }
 }
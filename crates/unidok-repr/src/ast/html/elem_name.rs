@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ElemName {
     // region: Base document structure
     Html,
@@ -198,10 +198,18 @@ pub enum ElemName {
     Noscript,
     Template,
     // endregion
+    /// A custom element or an unknown tag, e.g. `<my-widget>`. The name is
+    /// lowercase, except in SVG and MathML, where it is preserved verbatim.
+    /// It is block-level if it was configured with
+    /// `@CONFIG(block_elements=…)`.
+    Custom {
+        name: String,
+        is_block_level: bool,
+    },
 }
 
 impl ElemName {
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             ElemName::Html => "html",
             ElemName::Head => "head",
@@ -348,6 +356,7 @@ impl ElemName {
             ElemName::Ins => "ins",
             ElemName::Noscript => "noscript",
             ElemName::Template => "template",
+            ElemName::Custom { name, .. } => name,
         }
     }
 
@@ -363,6 +372,9 @@ impl ElemName {
     pub fn is_block_level(&self) -> bool {
         use ElemName::*;
 
+        if let Custom { is_block_level, .. } = self {
+            return *is_block_level;
+        }
         !matches!(self, Br | A | Abbr | B | Bdi | Bdo | Cite | Code | Data | Dfn | Em
             | I | Kbd | Mark | Q | Rb | Rp | Rt | Rtc | Ruby | S | Samp | Small
             | Span | Strong | Sub | Sup | Time | U | Var)
@@ -383,7 +395,8 @@ impl ElemName {
         use ElemName::*;
 
         matches!(self, Html | Head | Body | Address | Article | Aside | Header | Footer | Main | Nav
-            | Section | Pre | Td | Th | Div | Blockquote | Ul | Li | Form | Details | Canvas | Noscript)
+            | Section | Pre | Td | Th | Div | Blockquote | Ul | Li | Form | Details | Canvas | Noscript
+            | Custom { .. })
     }
 
     #[rustfmt::skip]
@@ -393,6 +406,12 @@ impl ElemName {
         matches!(self, Script | Style)
     }
 
+    /// Returns whether the element contains SVG or MathML, which is parsed
+    /// following XML rules
+    pub fn contains_foreign_content(&self) -> bool {
+        matches!(self, ElemName::Svg | ElemName::Math)
+    }
+
     #[rustfmt::skip]
     pub fn must_contain_blocks(&self) -> bool {
         use ElemName::*;
@@ -401,6 +420,24 @@ impl ElemName {
             | Audio | Video)
     }
 
+    /// Returns the element with the given name. Known names are matched
+    /// case-insensitively. Other names become [`ElemName::Custom`] if they are
+    /// valid custom element names, i.e. they start with an ASCII letter and
+    /// contain a `-`, so text like `Vec<String>` isn't parsed as HTML. Custom
+    /// elements are block-level if their name is in `block_elements`.
+    pub fn from_name(s: &str, block_elements: &[String]) -> Option<Self> {
+        let name = s.to_ascii_lowercase();
+        if let Some(elem) = ElemName::try_from(&name) {
+            Some(elem)
+        } else if name.starts_with(|c: char| c.is_ascii_alphabetic()) && name.contains('-') {
+            let is_block_level = block_elements.iter().any(|b| b.eq_ignore_ascii_case(&name));
+            Some(ElemName::Custom { name, is_block_level })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the known element with the given lowercase name
     pub fn try_from(s: &'_ str) -> Option<Self> {
         if let Some(c) = s.chars().next() {
             Some(match c {
@@ -14,11 +14,21 @@ pub enum HtmlNodeAst {
     CData(CDataSectionAst),
     Comment(HtmlCommentAst),
     Doctype(DoctypeAst),
+    /// HTML that is passed through verbatim. This only exists in the
+    /// CommonMark dialect.
+    Raw(RawHtmlAst),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawHtmlAst {
+    pub text: String,
+    pub span: StrSlice,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct HtmlElemAst {
     pub name: ElemName,
+    pub name_span: StrSlice,
     pub attrs: Vec<AttrAst>,
     pub content: Option<ElemContentAst>,
     pub close: ElemClose,
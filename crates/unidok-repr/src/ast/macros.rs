@@ -3,6 +3,32 @@ use detached_str::StrSlice;
 use crate::ast::blocks::BlockAst;
 use crate::ast::segments::{BracesAst, SegmentAst};
 
+/// The names of all built-in macros, except for the unnamed macro `@()`.
+pub const MACRO_NAMES: &[&str] = &[
+    "TOC",
+    "NOTOC",
+    "NOTXT",
+    "LOOSE",
+    "BULLET",
+    "MATH_SCRIPT",
+    "BLANK",
+    "FOOTNOTES",
+    "CONFIG",
+    "META",
+    "INCLUDE",
+    "PASS",
+    "NOPASS",
+    "NOTE",
+    "TIP",
+    "WARNING",
+    "CAUTION",
+    "IMPORTANT",
+    "DETAILS",
+    "CAPTION",
+    "FIGURE",
+    "COLS",
+];
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlockMacro {
     pub name: StrSlice,
@@ -38,6 +64,14 @@ impl MacroArgs {
             None
         }
     }
+
+    pub fn into_token_trees(self) -> Option<Vec<TokenTree>> {
+        if let MacroArgs::TokenTrees(t) = self {
+            Some(t)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -59,9 +93,14 @@ impl TokenTree {
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenTreeAtom {
     Word(StrSlice),
-    QuotedWord(String),
+    /// The content of a quoted string, and the string including the quotes
+    QuotedWord(String, StrSlice),
     Tuple(Vec<TokenTree>), // [foo=bar, baz="", quux]
-    Braces(BracesAst),
+    /// The content, and the braces including the content
+    Braces(BracesAst, StrSlice),
+    /// "foo *bar*", only in `@DETAILS`, `@CAPTION` and `@FIGURE`. The
+    /// string including the quotes is also stored.
+    QuotedSegments(Vec<SegmentAst>, StrSlice),
 }
 
 impl TokenTreeAtom {
@@ -74,7 +113,7 @@ impl TokenTreeAtom {
     }
 
     pub fn as_quoted_word(&self) -> Option<&str> {
-        if let Self::QuotedWord(v) = self {
+        if let Self::QuotedWord(v, _) = self {
             Some(v)
         } else {
             None
@@ -84,7 +123,7 @@ impl TokenTreeAtom {
     pub fn as_str<'a>(&'a self, text: &'a str) -> Option<&'a str> {
         match self {
             TokenTreeAtom::Word(w) => Some(w.to_str(text)),
-            TokenTreeAtom::QuotedWord(w) => Some(w),
+            TokenTreeAtom::QuotedWord(w, _) => Some(w),
             _ => None,
         }
     }
@@ -4,4 +4,4 @@ pub mod macros;
 pub mod segments;
 
 mod data;
-pub use data::AstData;
+pub use data::{AstData, NamedFootnote};
@@ -19,6 +19,8 @@ pub enum SegmentAst {
     InlineMacro(InlineMacroAst),
     InlineHtml(HtmlNodeAst),
     HtmlEntity(HtmlEntity),
+    /// A numeric character reference, e.g. `&#123;` or `&#x1F600;`
+    CharRef(char),
     Format(InlineFormatAst),
     Code(CodeAst),
 }
@@ -62,9 +64,16 @@ pub struct ImageAst {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum LinkTarget {
-    Url { href: String, title: Option<String> },
+    Url {
+        href: String,
+        /// The position of the URL in the source text
+        href_span: StrSlice,
+        title: Option<String>,
+    },
     Reference(StrSlice),
     Footnote,
+    /// A reference to a footnote definition, e.g. `[^foo]`
+    NamedFootnote(StrSlice),
 }
 
 #[derive(Debug, Clone, PartialEq)]
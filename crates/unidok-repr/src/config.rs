@@ -2,12 +2,29 @@
 use std::path::PathBuf;
 
 use crate::quotes::QuoteStyle;
+use crate::sanitizer::Sanitizer;
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Config {
     pub heading_anchor: HeadingAnchor,
+    pub autolink: Autolink,
     pub quote_style: QuoteStyle,
+    /// The language set with `@CONFIG(lang=…)`, e.g. `german`
+    pub lang: Option<String>,
     pub retrieve_spans: bool,
+    /// Highlight code blocks on the server side. This requires the `syntect`
+    /// feature of `unidok-to-html`
+    pub highlight: bool,
+    /// Custom elements that are block-level, set with
+    /// `@CONFIG(block_elements=…)`. Other custom elements are inline.
+    pub block_elements: Vec<String>,
+    /// Safe mode: HTML elements, attributes and URLs that aren't allowed by
+    /// the sanitizer are removed
+    pub sanitizer: Option<Sanitizer>,
+    /// Limits against pathological input, e.g. deeply nested brackets
+    pub limits: Limits,
+    /// The syntax that is parsed, either Unidok or CommonMark
+    pub dialect: Dialect,
 
     pub unsafe_config: Option<UnsafeConfig>,
 }
@@ -18,15 +35,78 @@ pub struct UnsafeConfig {
     pub root: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum HeadingAnchor {
+    #[default]
     None,
     Start,
     End,
 }
 
-impl Default for HeadingAnchor {
+/// Which URLs are converted to links automatically
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Autolink {
+    /// Only URLs and email addresses in angle brackets, e.g. `<https://foo.org>`
+    #[default]
+    Angle,
+    /// Also bare URLs starting with `http://`, `https://`, `www.` or `mailto:`
+    Bare,
+}
+
+/// The markup language of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Unidok,
+    /// Standard Markdown as specified by [CommonMark](https://spec.commonmark.org/).
+    /// Unidok-specific syntax such as macros, tables and math is disabled,
+    /// and some constructs are parsed differently, e.g. emphasis, indented
+    /// code blocks and raw HTML blocks.
+    CommonMark,
+}
+
+/// Resource limits for parsing untrusted input. When a limit is exceeded, a
+/// diagnostic is emitted, and the affected content is parsed as plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of the input in bytes. Longer input is truncated.
+    pub max_input_size: Option<usize>,
+    /// Maximum nesting depth of blocks and inline content. Content that is
+    /// nested more deeply is parsed as plain text.
+    pub max_nesting: Option<u32>,
+    /// Maximum number of parsing steps. Each parser invocation and each byte
+    /// that is read counts as a step; bytes are counted again when they are
+    /// re-read after backtracking. When the budget is exhausted, the rest of
+    /// the document is parsed as plain text.
+    pub max_steps: Option<u64>,
+}
+
+impl Limits {
+    /// No limits, not even for the nesting depth. Deeply nested input can
+    /// overflow the stack.
+    pub fn none() -> Self {
+        Limits { max_input_size: None, max_nesting: None, max_steps: None }
+    }
+
+    /// Limits suitable for untrusted input, e.g. comments on a website
+    pub fn untrusted() -> Self {
+        Limits { max_input_size: Some(1 << 20), max_nesting: Some(32), max_steps: Some(20_000_000) }
+    }
+}
+
+impl Default for Limits {
     fn default() -> Self {
-        HeadingAnchor::None
+        // only the nesting depth is limited by default, to prevent stack
+        // overflows
+        Limits { max_nesting: Some(100), ..Limits::none() }
     }
 }
+
+/// Parses a list of element names separated by spaces or commas, as in
+/// `@CONFIG(block_elements="my-widget, x-card")`
+pub fn parse_elem_list(s: &str) -> Vec<String> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use crate::Span;
+
+/// An error or warning found while parsing or converting a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn error(code: DiagnosticCode, message: impl Into<String>, span: impl Into<Span>) -> Self {
+        Diagnostic { severity: Severity::Error, code, message: message.into(), span: span.into() }
+    }
+
+    pub fn warning(
+        code: DiagnosticCode,
+        message: impl Into<String>,
+        span: impl Into<Span>,
+    ) -> Self {
+        Diagnostic { severity: Severity::Warning, code, message: message.into(), span: span.into() }
+    }
+}
+
+/// A file included with `@INCLUDE`. Its text is appended to the input, so
+/// diagnostics with a span inside of `span` belong to this file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludedFile {
+    pub path: PathBuf,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticCode {
+    UnknownMacro,
+    InvalidMacroArgs,
+    UnknownConfigKey,
+    InvalidConfigValue,
+    UnresolvedLinkRef,
+    IncludeFailed,
+    InvalidMetadata,
+    UnknownMetadataKey,
+    UndefinedFootnote,
+    UnusedFootnote,
+    DisallowedHtml,
+    DisallowedUrl,
+    LimitExceeded,
+    HeadingLevelOutOfRange,
+    InternalError,
+}
+
+impl DiagnosticCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiagnosticCode::UnknownMacro => "unknown-macro",
+            DiagnosticCode::InvalidMacroArgs => "invalid-macro-args",
+            DiagnosticCode::UnknownConfigKey => "unknown-config-key",
+            DiagnosticCode::InvalidConfigValue => "invalid-config-value",
+            DiagnosticCode::UnresolvedLinkRef => "unresolved-link-ref",
+            DiagnosticCode::IncludeFailed => "include-failed",
+            DiagnosticCode::InvalidMetadata => "invalid-metadata",
+            DiagnosticCode::UnknownMetadataKey => "unknown-metadata-key",
+            DiagnosticCode::UndefinedFootnote => "undefined-footnote",
+            DiagnosticCode::UnusedFootnote => "unused-footnote",
+            DiagnosticCode::DisallowedHtml => "disallowed-html",
+            DiagnosticCode::DisallowedUrl => "disallowed-url",
+            DiagnosticCode::LimitExceeded => "limit-exceeded",
+            DiagnosticCode::HeadingLevelOutOfRange => "heading-level-out-of-range",
+            DiagnosticCode::InternalError => "internal-error",
+        }
+    }
+}
+
+#[cfg(feature = "serde-spans")]
+mod serde_impls {
+    use serde::ser::SerializeStruct;
+    use serde::Serialize;
+
+    use super::Diagnostic;
+
+    impl Serialize for Diagnostic {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut s = serializer.serialize_struct("Diagnostic", 4)?;
+            s.serialize_field("severity", self.severity.as_str())?;
+            s.serialize_field("code", self.code.as_str())?;
+            s.serialize_field("message", &self.message)?;
+            s.serialize_field("span", &self.span)?;
+            s.end()
+        }
+    }
+}
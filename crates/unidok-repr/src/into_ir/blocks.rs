@@ -1,8 +1,11 @@
 use crate::ast::blocks::*;
 use crate::ast::AstData;
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
 use crate::ir::blocks::*;
+use crate::ir::macros::Macro;
 use crate::{IntoIR, ToPlaintext};
 
+use super::sanitize;
 use super::utils::collapse_text;
 
 impl<'a> IntoIR<'a> for BlockAst {
@@ -16,13 +19,22 @@ impl<'a> IntoIR<'a> for BlockAst {
             BlockAst::Table(b) => Block::Table(b.into_ir(text, data)),
             BlockAst::ThematicBreak(b) => Block::ThematicBreak(b.into_ir(text, data)),
             BlockAst::List(b) => Block::List(b.into_ir(text, data)),
+            BlockAst::LabeledList(b) => Block::LabeledList(b.into_ir(text, data)),
             BlockAst::Quote(b) => Block::Quote(b.into_ir(text, data)),
             BlockAst::BlockMacro(block) => {
                 return block.into_ir(text, data);
             }
-            BlockAst::BlockHtml(h) => Block::BlockHtml(h.into_ir(text, data)),
+            BlockAst::BlockHtml(h) => {
+                if sanitize::allows_html(&h, data) {
+                    Block::BlockHtml(h.into_ir(text, data))
+                } else {
+                    Block::Empty
+                }
+            }
 
-            BlockAst::Comment(_) | BlockAst::LinkRefDef(_) => Block::Empty,
+            BlockAst::Comment(_) | BlockAst::LinkRefDef(_) | BlockAst::FootnoteDef(_) => {
+                Block::Empty
+            }
         };
         AnnBlock { macros: vec![], block }
     }
@@ -71,7 +83,16 @@ impl<'a> IntoIR<'a> for HeadingAst {
         }
         let slug = slug::slugify(plaintext);
 
-        Heading { level: self.level, segments, slug }
+        let level = self.level.clamp(1, 6);
+        if level != self.level {
+            data.diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::HeadingLevelOutOfRange,
+                format!("invalid heading level {}, using {} instead", self.level, level),
+                self.span,
+            ));
+        }
+
+        Heading { level, segments, slug }
     }
 }
 
@@ -103,9 +124,17 @@ impl<'a> IntoIR<'a> for TableCellAst {
     type IR = TableCell<'a>;
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
-        TableCell {
-            meta: self.meta.into_ir(text, data),
-            segments: collapse_text(self.segments).into_ir(text, data),
+        TableCell { meta: self.meta.into_ir(text, data), content: self.content.into_ir(text, data) }
+    }
+}
+
+impl<'a> IntoIR<'a> for CellContentAst {
+    type IR = CellContent<'a>;
+
+    fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
+        match self {
+            CellContentAst::Inline(i) => CellContent::Inline(collapse_text(i).into_ir(text, data)),
+            CellContentAst::Blocks(b) => CellContent::Blocks(b.into_ir(text, data)),
         }
     }
 }
@@ -128,7 +157,31 @@ impl<'a> IntoIR<'a> for ListAst {
     type IR = List<'a>;
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
-        List { bullet: self.bullet, items: self.items.into_ir(text, data), macros: vec![] }
+        List {
+            bullet: self.bullet,
+            items: self.items.into_ir(text, data),
+            checked: self.tasks.iter().map(|t| t.map(|t| t.checked)).collect(),
+            macros: if self.loose { vec![Macro::Loose] } else { vec![] },
+        }
+    }
+}
+
+impl<'a> IntoIR<'a> for LabeledListAst {
+    type IR = LabeledList<'a>;
+
+    fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
+        LabeledList { items: self.items.into_ir(text, data), macros: vec![] }
+    }
+}
+
+impl<'a> IntoIR<'a> for LabeledItemAst {
+    type IR = LabeledItem<'a>;
+
+    fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
+        LabeledItem {
+            label: collapse_text(self.label).into_ir(text, data),
+            content: self.content.into_ir(text, data),
+        }
     }
 }
 
@@ -3,6 +3,7 @@ use crate::ast::AstData;
 use crate::ir::html::*;
 use crate::IntoIR;
 
+use super::sanitize;
 use super::utils::collapse_text;
 
 impl<'a> IntoIR<'a> for HtmlNodeAst {
@@ -14,6 +15,7 @@ impl<'a> IntoIR<'a> for HtmlNodeAst {
             HtmlNodeAst::CData(c) => HtmlNode::CData(c.into_ir(text, data)),
             HtmlNodeAst::Comment(c) => HtmlNode::Comment(c.text),
             HtmlNodeAst::Doctype(d) => HtmlNode::Doctype(d.into_ir(text, data)),
+            HtmlNodeAst::Raw(r) => HtmlNode::Raw(r.text),
         }
     }
 }
@@ -41,7 +43,14 @@ impl<'a> IntoIR<'a> for HtmlElemAst {
         HtmlElem {
             macros: vec![],
             name: self.name,
-            attrs: self.attrs.into_ir(text, data),
+            attrs: self
+                .attrs
+                .into_iter()
+                .filter(|a| {
+                    sanitize::allows_attr(a.key.to_str(text), a.value.as_deref(), a.key, data)
+                })
+                .collect::<Vec<_>>()
+                .into_ir(text, data),
             content: self.content.into_ir(text, data),
             close: self.close,
         }
@@ -2,25 +2,69 @@ use std::{iter, mem};
 
 use detached_str::StrSlice;
 
+use crate::ast::blocks::CellAlignment;
 use crate::ast::macros::*;
 use crate::ast::AstData;
-use crate::config::HeadingAnchor;
+use crate::config::{parse_elem_list, Autolink, HeadingAnchor};
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use crate::ir::blocks::Table;
 use crate::ir::blocks::{AnnBlock, Block};
 use crate::ir::html::HtmlNode;
-use crate::ir::macros::{Attr, AttrValue, Footnote, Macro};
+use crate::ir::macros::{
+    AdmonitionKind, Attr, AttrValue, Caption, Column, FigureKind, Footnote, Macro,
+};
 use crate::ir::segments::Segment;
+use crate::metadata::MetaValue;
 use crate::IntoIR;
 
+use super::sanitize;
+use super::utils::collapse_text;
+
 impl<'a> IntoIR<'a> for BlockMacro {
     type IR = AnnBlock<'a>;
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
         let mut block = self.content.into_ir(text, data);
-        let r#macro = MacroAst { name: self.name, args: self.args }.into_ir(text, data);
+        let mut r#macro = MacroAst { name: self.name, args: self.args }.into_ir(text, data);
+
+        if let Macro::Caption(caption) = &mut r#macro {
+            caption.kind = match block.block {
+                Block::Table(_) => FigureKind::Table,
+                Block::CodeBlock(_) => FigureKind::Listing,
+                _ => FigureKind::Figure,
+            };
+            let next = &mut data.next_figure[caption.kind as usize];
+            caption.number = *next;
+            *next += 1;
+            caption.label = caption.kind.label(data.config.lang.as_deref());
+        }
+
+        if let Macro::Cols(columns) = &r#macro {
+            if let Block::Table(table) = &mut block.block {
+                apply_column_alignment(columns, table);
+            } else {
+                data.diagnostics.push(Diagnostic::warning(
+                    DiagnosticCode::InvalidMacroArgs,
+                    "`@COLS` can only be applied to tables",
+                    self.name,
+                ));
+            }
+        }
+
+        if matches!(r#macro, Macro::HtmlAttrs(_)) && !can_have_attrs(&block) {
+            data.diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::InvalidMacroArgs,
+                "`@()` can't be applied to HTML comments, CDATA sections or doctypes",
+                self.name,
+            ));
+            return block;
+        }
 
         if r#macro.is_for_list() {
-            if let AnnBlock { block: Block::List(list), .. } = &mut block {
-                list.macros.push(r#macro);
+            match &mut block.block {
+                Block::List(list) => list.macros.push(r#macro),
+                Block::LabeledList(list) => list.macros.push(r#macro),
+                _ => {}
             }
         } else {
             block.macros.push(r#macro);
@@ -30,6 +74,29 @@ impl<'a> IntoIR<'a> for BlockMacro {
     }
 }
 
+/// Returns whether HTML attributes can be added to the block. Blocks with other
+/// macros are allowed, since the macros may wrap them in an element.
+fn can_have_attrs(block: &AnnBlock<'_>) -> bool {
+    if !block.macros.is_empty() {
+        return true;
+    }
+    match &block.block {
+        Block::BlockHtml(node) => matches!(node, HtmlNode::Element(_)),
+        Block::Paragraph(p) => {
+            let mut segments = p.segments.iter().filter(|s| !matches!(s, Segment::LineBreak));
+            match (segments.next(), segments.next()) {
+                (Some(Segment::InlineHtml(node)), None) => matches!(node, HtmlNode::Element(_)),
+                _ => true,
+            }
+        }
+        Block::Braces(blocks) => match blocks.as_slice() {
+            [b] => can_have_attrs(b),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
 impl<'a> IntoIR<'a> for InlineMacroAst {
     type IR = Segment<'a>;
 
@@ -73,86 +140,36 @@ impl<'a> IntoIR<'a> for MacroAst {
     type IR = Macro<'a>;
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
-        match self.name.to_str(text) {
+        let name = self.name;
+        match name.to_str(text) {
             "" => {
                 if let Some(MacroArgs::TokenTrees(tts)) = self.args {
                     if tts.is_empty() {
-                        return Macro::Invalid;
+                        return invalid_args(data, name, "`@()` requires at least one argument");
                     }
-                    let mut result = Vec::new();
-
-                    for tt in tts {
-                        match tt {
-                            TokenTree::Atom(TokenTreeAtom::Word(arg)) => {
-                                let arg = arg.to_str(text);
-                                if let Some(arg) = arg.strip_prefix('.') {
-                                    result.push(Attr {
-                                        key: "class",
-                                        value: Some(AttrValue::Word(arg)),
-                                    });
-                                } else if let Some(arg) = arg.strip_prefix('#') {
-                                    result.push(Attr {
-                                        key: "id",
-                                        value: Some(AttrValue::Word(arg)),
-                                    });
-                                } else {
-                                    result.push(Attr { key: arg, value: None })
-                                }
-                            }
-                            TokenTree::Atom(TokenTreeAtom::QuotedWord(word)) => result.push(Attr {
-                                key: "style",
-                                value: Some(AttrValue::QuotedWord(word)),
-                            }),
-                            TokenTree::KV(key, TokenTreeAtom::Word(word)) => {
-                                let key = key.to_str(text);
-                                let word = word.to_str(text);
-                                result.push(Attr { key, value: Some(AttrValue::Word(word)) })
-                            }
-                            TokenTree::KV(key, TokenTreeAtom::QuotedWord(word)) => {
-                                let key = key.to_str(text);
-                                result.push(Attr { key, value: Some(AttrValue::QuotedWord(word)) })
-                            }
-                            _ => return Macro::Invalid,
+                    match tts.into_iter().map(|tt| html_attr(tt, text)).collect() {
+                        Some(mut attrs) => {
+                            sanitize_attrs(&mut attrs, name, data);
+                            Macro::HtmlAttrs(attrs)
                         }
+                        None => invalid_args(
+                            data,
+                            name,
+                            "`@()` only accepts words, strings and `key=value` pairs",
+                        ),
                     }
-
-                    Macro::HtmlAttrs(result)
                 } else {
-                    Macro::Invalid
-                }
-            }
-            "TOC" => {
-                if self.args.is_none() {
-                    Macro::Toc
-                } else {
-                    Macro::Invalid
-                }
-            }
-            "NOTOC" => {
-                if self.args.is_none() {
-                    Macro::NoToc
-                } else {
-                    Macro::Invalid
-                }
-            }
-            "NOTXT" => {
-                if self.args.is_none() {
-                    Macro::NoText
-                } else {
-                    Macro::Invalid
-                }
-            }
-            "LOOSE" => {
-                if self.args.is_none() {
-                    Macro::Loose
-                } else {
-                    Macro::Invalid
+                    invalid_args(data, name, "`@()` requires arguments in parentheses")
                 }
             }
+            "TOC" => no_args(self.args, Macro::Toc, data, name, text),
+            "NOTOC" => no_args(self.args, Macro::NoToc, data, name, text),
+            "NOTXT" => no_args(self.args, Macro::NoText, data, name, text),
+            "LOOSE" => no_args(self.args, Macro::Loose, data, name, text),
             "BULLET" => {
                 if let Some(MacroArgs::TokenTrees(tts)) = self.args {
                     if tts.is_empty() {
-                        return Macro::Invalid;
+                        return invalid_args(data, name, "`@BULLET` requires a list style");
                     }
                     let mut style = String::new();
 
@@ -163,7 +180,7 @@ impl<'a> IntoIR<'a> for MacroAst {
                                     style.push_str(word.to_str(text));
                                     style.push(' ');
                                 }
-                                TokenTreeAtom::QuotedWord(word) => {
+                                TokenTreeAtom::QuotedWord(word, _) => {
                                     style.push('"');
                                     style.extend(word.chars().flat_map(|c| {
                                         iter::once('\\')
@@ -172,10 +189,20 @@ impl<'a> IntoIR<'a> for MacroAst {
                                     }));
                                     style.push_str("\" ");
                                 }
-                                _ => return Macro::Invalid,
+                                _ => {
+                                    return invalid_args(
+                                        data,
+                                        name,
+                                        "`@BULLET` only accepts words and strings",
+                                    )
+                                }
                             }
                         } else {
-                            return Macro::Invalid;
+                            return invalid_args(
+                                data,
+                                name,
+                                "`@BULLET` doesn't accept `key=value` pairs",
+                            );
                         }
                     }
                     if style.ends_with(' ') {
@@ -184,23 +211,18 @@ impl<'a> IntoIR<'a> for MacroAst {
 
                     Macro::ListStyle(style)
                 } else {
-                    Macro::Invalid
+                    invalid_args(data, name, "`@BULLET` requires a list style")
                 }
             }
-            "MATH_SCRIPT" => {
-                if self.args.is_none() {
-                    Macro::MathScript
-                } else {
-                    Macro::Invalid
+            "MATH_SCRIPT" => match self.args.as_ref().and_then(MacroArgs::as_token_trees) {
+                None if self.args.is_none() => Macro::MathScript,
+                Some([TokenTree::Atom(TokenTreeAtom::Word(w))]) if w.to_str(text) == "head" => {
+                    data.math_script_in_head = true;
+                    Macro::MathScriptInHead
                 }
-            }
-            "BLANK" => {
-                if self.args.is_none() {
-                    Macro::Blank
-                } else {
-                    Macro::Invalid
-                }
-            }
+                _ => invalid_args(data, name, "`@MATH_SCRIPT` only accepts the argument `head`"),
+            },
+            "BLANK" => no_args(self.args, Macro::Blank, data, name, text),
             "FOOTNOTES" => {
                 if self.args.is_none() {
                     if data.footnotes.is_empty() {
@@ -219,7 +241,7 @@ impl<'a> IntoIR<'a> for MacroAst {
                         Macro::Footnotes(footnotes)
                     }
                 } else {
-                    Macro::Invalid
+                    no_args(self.args, Macro::Invalid, data, name, text)
                 }
             }
             "CONFIG" => {
@@ -237,36 +259,407 @@ impl<'a> IntoIR<'a> for MacroAst {
                                     Some("none" | "no" | "false") => {
                                         data.config.heading_anchor = HeadingAnchor::None
                                     }
-                                    _ => {}
+                                    _ => data.diagnostics.push(Diagnostic::error(
+                                        DiagnosticCode::InvalidConfigValue,
+                                        "`heading_anchor` must be `start`, `end` or `none`",
+                                        key,
+                                    )),
                                 },
-                                "lang" => {
-                                    if let Some(value) = value.as_str(text) {
-                                        if let Ok(quote_style) = value.parse() {
+                                "autolink" => match value.as_str(text) {
+                                    Some("angle") => data.config.autolink = Autolink::Angle,
+                                    Some("bare") => data.config.autolink = Autolink::Bare,
+                                    _ => data.diagnostics.push(Diagnostic::error(
+                                        DiagnosticCode::InvalidConfigValue,
+                                        "`autolink` must be `angle` or `bare`",
+                                        key,
+                                    )),
+                                },
+                                "lang" => match value.as_str(text) {
+                                    Some(value) => match value.parse() {
+                                        Ok(quote_style) => {
                                             data.config.quote_style = quote_style;
-                                        } else {
-                                            return Macro::Invalid;
+                                            data.config.lang = Some(value.to_string());
                                         }
-                                    } else {
-                                        return Macro::Invalid;
+                                        Err(_) => data.diagnostics.push(Diagnostic::error(
+                                            DiagnosticCode::InvalidConfigValue,
+                                            format!("unknown language `{}`", value),
+                                            key,
+                                        )),
+                                    },
+                                    None => data.diagnostics.push(Diagnostic::error(
+                                        DiagnosticCode::InvalidConfigValue,
+                                        "`lang` must be a word or a string",
+                                        key,
+                                    )),
+                                },
+                                "block_elements" => match value.as_str(text) {
+                                    Some(value) => {
+                                        data.config.block_elements = parse_elem_list(value)
                                     }
-                                }
-                                _ => return Macro::Invalid,
+                                    None => data.diagnostics.push(Diagnostic::error(
+                                        DiagnosticCode::InvalidConfigValue,
+                                        "`block_elements` must be a word or a string",
+                                        key,
+                                    )),
+                                },
+                                "highlight" => match value.as_str(text) {
+                                    Some("true" | "yes") => data.config.highlight = true,
+                                    Some("false" | "no") => data.config.highlight = false,
+                                    _ => data.diagnostics.push(Diagnostic::error(
+                                        DiagnosticCode::InvalidConfigValue,
+                                        "`highlight` must be `true` or `false`",
+                                        key,
+                                    )),
+                                },
+                                k => data.diagnostics.push(Diagnostic::error(
+                                    DiagnosticCode::UnknownConfigKey,
+                                    format!("unknown configuration key `{}`", k),
+                                    key,
+                                )),
                             }
+                        } else {
+                            data.diagnostics.push(Diagnostic::error(
+                                DiagnosticCode::InvalidMacroArgs,
+                                "`@CONFIG` arguments must be `key=value` pairs",
+                                name,
+                            ));
                         }
                     }
                     Macro::Config
                 } else {
-                    Macro::Invalid
+                    invalid_args(data, name, "`@CONFIG` requires arguments in parentheses")
+                }
+            }
+            "META" => {
+                if let Some(MacroArgs::TokenTrees(args)) = self.args {
+                    for arg in args {
+                        if let TokenTree::KV(key, value) = arg {
+                            match meta_value(value, text) {
+                                Some(value) => {
+                                    data.metadata.insert(key.to_str(text), value);
+                                }
+                                None => data.diagnostics.push(Diagnostic::error(
+                                    DiagnosticCode::InvalidMetadata,
+                                    "metadata values must be words, strings or lists",
+                                    key,
+                                )),
+                            }
+                        } else {
+                            data.diagnostics.push(Diagnostic::error(
+                                DiagnosticCode::InvalidMacroArgs,
+                                "`@META` arguments must be `key=value` pairs",
+                                name,
+                            ));
+                        }
+                    }
+                    Macro::Meta
+                } else {
+                    invalid_args(data, name, "`@META` requires arguments in parentheses")
                 }
             }
             "INCLUDE" => {
                 if let Some(MacroArgs::Raw(path)) = self.args {
                     Macro::Include(path.to_str(text))
                 } else {
-                    Macro::Invalid
+                    invalid_args(data, name, "`@INCLUDE` requires a file path")
+                }
+            }
+            "NOTE" | "TIP" | "WARNING" | "CAUTION" | "IMPORTANT" => {
+                let kind = AdmonitionKind::from_macro_name(name.to_str(text)).unwrap();
+                let title = match self.args.as_ref().and_then(MacroArgs::as_token_trees) {
+                    None if self.args.is_none() => {
+                        kind.title(data.config.lang.as_deref()).to_string()
+                    }
+                    Some([TokenTree::Atom(atom)]) => match atom.as_str(text) {
+                        Some(title) => title.to_string(),
+                        None => return invalid_admonition_args(data, name, text),
+                    },
+                    _ => return invalid_admonition_args(data, name, text),
+                };
+                Macro::Admonition(kind, title)
+            }
+            "DETAILS" => {
+                let mut summary = None;
+                let mut open = false;
+                for tt in self.args.into_iter().filter_map(MacroArgs::into_token_trees).flatten() {
+                    match tt {
+                        TokenTree::Atom(TokenTreeAtom::Word(w)) if w.to_str(text) == "open" => {
+                            open = true;
+                        }
+                        TokenTree::Atom(TokenTreeAtom::QuotedSegments(s, _))
+                            if summary.is_none() =>
+                        {
+                            summary = Some(collapse_text(s).into_ir(text, data));
+                        }
+                        _ => {
+                            return invalid_args(
+                                data,
+                                name,
+                                "`@DETAILS` only accepts a quoted summary and the `open` flag",
+                            )
+                        }
+                    }
                 }
+                Macro::Details { summary: summary.unwrap_or_default(), open }
+            }
+            "CAPTION" | "FIGURE" => {
+                let mut args = self.args.and_then(MacroArgs::into_token_trees);
+                let caption = match args.as_deref_mut() {
+                    None if name.to_str(text) == "FIGURE" => vec![],
+                    Some([TokenTree::Atom(TokenTreeAtom::QuotedSegments(s, _))]) => {
+                        collapse_text(mem::take(s)).into_ir(text, data)
+                    }
+                    _ => {
+                        let message = format!("`@{}` expects a quoted caption", name.to_str(text));
+                        return invalid_args(data, name, message);
+                    }
+                };
+                let kind = FigureKind::Figure;
+                Macro::Caption(Caption { kind, number: 0, label: kind.label(None), text: caption })
+            }
+            "COLS" => {
+                let tts = match self.args.and_then(MacroArgs::into_token_trees) {
+                    Some(tts) if !tts.is_empty() => tts,
+                    _ => {
+                        return invalid_args(data, name, "`@COLS` requires an argument per column")
+                    }
+                };
+                let mut columns = Vec::with_capacity(tts.len());
+                for tt in tts {
+                    let tts = match strip_comma(tt, text) {
+                        TokenTree::Atom(TokenTreeAtom::Word(w)) if w.is_empty() => continue,
+                        TokenTree::Atom(TokenTreeAtom::Tuple(tts)) => tts,
+                        tt => vec![tt],
+                    };
+                    match column(tts, text) {
+                        Ok((mut column, width)) => {
+                            sanitize_attrs(&mut column.attrs, name, data);
+                            // the width was validated, so it is allowed in safe mode
+                            if let Some(width) = width {
+                                let style = AttrValue::QuotedWord(format!("width: {}", width));
+                                column.attrs.push(Attr { key: "style", value: Some(style) });
+                            }
+                            columns.push(column);
+                        }
+                        Err(message) => return invalid_args(data, name, message),
+                    }
+                }
+                Macro::Cols(columns)
+            }
+            // these are evaluated by the parser
+            "PASS" | "NOPASS" => Macro::Invalid,
+            n => {
+                data.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::UnknownMacro,
+                    format!("unknown macro `@{}`", n),
+                    name,
+                ));
+                Macro::Invalid
             }
-            _ => Macro::Invalid,
         }
     }
 }
+
+fn no_args<'a>(
+    args: Option<MacroArgs>,
+    r#macro: Macro<'a>,
+    data: &mut AstData,
+    name: StrSlice,
+    text: &str,
+) -> Macro<'a> {
+    if args.is_none() {
+        r#macro
+    } else {
+        let message = format!("`@{}` doesn't accept arguments", name.to_str(text));
+        invalid_args(data, name, message)
+    }
+}
+
+fn invalid_admonition_args(data: &mut AstData, name: StrSlice, text: &str) -> Macro<'static> {
+    let message = format!("`@{}` only accepts a title", name.to_str(text));
+    invalid_args(data, name, message)
+}
+
+/// Converts an argument of `@()` to an HTML attribute: `.foo` is a class,
+/// `#foo` is an id and a quoted string is a style
+fn html_attr(tt: TokenTree, text: &str) -> Option<Attr<'_>> {
+    Some(match tt {
+        TokenTree::Atom(TokenTreeAtom::Word(arg)) => {
+            let arg = arg.to_str(text);
+            if let Some(arg) = arg.strip_prefix('.') {
+                Attr { key: "class", value: Some(AttrValue::Word(arg)) }
+            } else if let Some(arg) = arg.strip_prefix('#') {
+                Attr { key: "id", value: Some(AttrValue::Word(arg)) }
+            } else {
+                Attr { key: arg, value: None }
+            }
+        }
+        TokenTree::Atom(TokenTreeAtom::QuotedWord(word, _)) => {
+            Attr { key: "style", value: Some(AttrValue::QuotedWord(word)) }
+        }
+        TokenTree::KV(key, TokenTreeAtom::Word(word)) => {
+            Attr { key: key.to_str(text), value: Some(AttrValue::Word(word.to_str(text))) }
+        }
+        TokenTree::KV(key, TokenTreeAtom::QuotedWord(word, _)) => {
+            Attr { key: key.to_str(text), value: Some(AttrValue::QuotedWord(word)) }
+        }
+        _ => return None,
+    })
+}
+
+/// Removes the attributes of `@()` or `@COLS` that aren't allowed in safe mode
+fn sanitize_attrs(attrs: &mut Vec<Attr<'_>>, name: StrSlice, data: &mut AstData) {
+    attrs.retain(|a| {
+        sanitize::allows_attr(a.key, a.value.as_ref().map(AttrValue::as_str), name, data)
+    });
+}
+
+/// Converts the arguments for one column of `@COLS`. They work like the
+/// arguments of `@()`, except for `align`, `valign`, `width` and `span`. The
+/// width is returned separately, so it isn't removed in safe mode.
+fn column(tts: Vec<TokenTree>, text: &str) -> Result<(Column<'_>, Option<String>), &'static str> {
+    let mut width = None;
+    let mut column = Column {
+        attrs: vec![],
+        span: 1,
+        alignment: CellAlignment::Unset,
+        vertical_alignment: CellAlignment::Unset,
+    };
+
+    for tt in tts {
+        let attr = html_attr(strip_comma(tt, text), text)
+            .ok_or("`@COLS` only accepts words, strings, `key=value` pairs and lists of them")?;
+        let value = match &attr.value {
+            Some(AttrValue::Word(w)) => Some(*w),
+            Some(AttrValue::QuotedWord(w)) => Some(w.as_str()),
+            None => None,
+        };
+
+        match (attr.key, value) {
+            ("", None) => {}
+            ("align", value) => {
+                column.alignment = match value {
+                    Some("left") => CellAlignment::LeftTop,
+                    Some("right") => CellAlignment::RightBottom,
+                    Some("center") => CellAlignment::Center,
+                    _ => return Err("`align` must be `left`, `right` or `center`"),
+                }
+            }
+            ("valign", value) => {
+                column.vertical_alignment = match value {
+                    Some("top") => CellAlignment::LeftTop,
+                    Some("bottom") => CellAlignment::RightBottom,
+                    Some("middle" | "center") => CellAlignment::Center,
+                    _ => return Err("`valign` must be `top`, `bottom` or `middle`"),
+                }
+            }
+            ("width", value) => {
+                width = match value {
+                    Some(w) if is_css_length(w) => Some(w.to_string()),
+                    _ => return Err("`width` must be a length such as `20%`, `10em` or `100px`"),
+                }
+            }
+            ("span", value) => {
+                column.span = match value.and_then(|v| v.parse().ok()) {
+                    Some(span) if span > 0 => span,
+                    _ => return Err("`span` must be a positive number"),
+                };
+                column.attrs.push(attr);
+            }
+            _ => column.attrs.push(attr),
+        }
+    }
+
+    Ok((column, width))
+}
+
+/// Returns whether the string is a non-negative CSS length or percentage,
+/// e.g. `20%` or `1.5em`
+fn is_css_length(s: &str) -> bool {
+    const UNITS: &[&str] = &[
+        "%", "px", "em", "rem", "ex", "ch", "vw", "vh", "vmin", "vmax", "cm", "mm", "in", "pt",
+        "pc",
+    ];
+    let unit_start = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+    number.parse::<f64>().is_ok() && !number.ends_with('.') && UNITS.contains(&unit)
+}
+
+/// Removes a trailing comma from a word, since the columns of `@COLS` may be
+/// separated by commas
+fn strip_comma(tt: TokenTree, text: &str) -> TokenTree {
+    let strip = |w: StrSlice| match w.to_str(text).strip_suffix(',') {
+        Some(s) => w.get(..s.len()),
+        None => w,
+    };
+    match tt {
+        TokenTree::Atom(TokenTreeAtom::Word(w)) => TokenTree::Atom(TokenTreeAtom::Word(strip(w))),
+        TokenTree::KV(k, TokenTreeAtom::Word(w)) => TokenTree::KV(k, TokenTreeAtom::Word(strip(w))),
+        tt => tt,
+    }
+}
+
+/// Sets the alignment of every table cell that doesn't specify one to the
+/// default alignment of its column. Cells spanning multiple rows or columns
+/// are taken into account.
+fn apply_column_alignment(columns: &[Column], table: &mut Table) {
+    let alignments: Vec<_> = columns
+        .iter()
+        .flat_map(|c| (0..c.span).map(move |_| (c.alignment, c.vertical_alignment)))
+        .collect();
+
+    // the number of rows that are still occupied by a cell in each column
+    let mut occupied: Vec<u16> = Vec::new();
+
+    for row in &mut table.rows {
+        let mut col = 0;
+        for cell in &mut row.cells {
+            while matches!(occupied.get(col), Some(&rows) if rows > 0) {
+                col += 1;
+            }
+            if let Some(&(alignment, vertical_alignment)) = alignments.get(col) {
+                if cell.meta.alignment == CellAlignment::Unset {
+                    cell.meta.alignment = alignment;
+                }
+                if cell.meta.vertical_alignment == CellAlignment::Unset {
+                    cell.meta.vertical_alignment = vertical_alignment;
+                }
+            }
+
+            let colspan = cell.meta.colspan.max(1) as usize;
+            if occupied.len() < col + colspan {
+                occupied.resize(col + colspan, 0);
+            }
+            for rows in &mut occupied[col..col + colspan] {
+                *rows = cell.meta.rowspan.max(1);
+            }
+            col += colspan;
+        }
+
+        for rows in &mut occupied {
+            *rows = rows.saturating_sub(1);
+        }
+    }
+}
+
+fn meta_value(atom: TokenTreeAtom, text: &str) -> Option<MetaValue> {
+    Some(match atom {
+        TokenTreeAtom::Word(word) => MetaValue::from_word(word.to_str(text)),
+        TokenTreeAtom::QuotedWord(word, _) => MetaValue::String(word),
+        TokenTreeAtom::Tuple(items) => MetaValue::List(
+            items
+                .into_iter()
+                .map(|item| match item {
+                    TokenTree::Atom(atom) => meta_value(atom, text),
+                    TokenTree::KV(..) => None,
+                })
+                .collect::<Option<_>>()?,
+        ),
+        TokenTreeAtom::Braces(..) | TokenTreeAtom::QuotedSegments(..) => return None,
+    })
+}
+
+fn invalid_args(data: &mut AstData, name: StrSlice, message: impl Into<String>) -> Macro<'static> {
+    data.diagnostics.push(Diagnostic::error(DiagnosticCode::InvalidMacroArgs, message, name));
+    Macro::Invalid
+}
@@ -2,6 +2,7 @@ mod blocks;
 mod foreign_impls;
 mod html;
 mod macros;
+mod sanitize;
 mod segments;
 mod utils;
 
@@ -0,0 +1,75 @@
+use crate::ast::html::HtmlNodeAst;
+use crate::ast::AstData;
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use crate::Span;
+
+/// Returns whether the HTML node is allowed in safe mode. If it isn't, a
+/// warning is emitted, and the node should be removed.
+pub(super) fn allows_html(node: &HtmlNodeAst, data: &mut AstData) -> bool {
+    match (node, &data.config.sanitizer) {
+        (HtmlNodeAst::Element(e), Some(s)) if !s.allows_element(e.name.as_str()) => {
+            data.diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::DisallowedHtml,
+                format!("`<{}>` is not allowed in safe mode", e.name),
+                e.name_span,
+            ));
+            false
+        }
+        (HtmlNodeAst::Raw(r), Some(_)) => {
+            data.diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::DisallowedHtml,
+                "raw HTML is not allowed in safe mode",
+                r.span,
+            ));
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Returns whether the HTML attribute is allowed in safe mode. If it isn't,
+/// a warning is emitted, and the attribute should be removed.
+pub(super) fn allows_attr(
+    key: &str,
+    value: Option<&str>,
+    span: impl Into<Span>,
+    data: &mut AstData,
+) -> bool {
+    let s = match &data.config.sanitizer {
+        Some(s) => s,
+        None => return true,
+    };
+    if !s.allows_attribute(key) {
+        data.diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::DisallowedHtml,
+            format!("attribute `{}` is not allowed in safe mode", key),
+            span,
+        ));
+        false
+    } else if matches!(value, Some(value) if !s.allows_attribute_value(key, value)) {
+        data.diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::DisallowedUrl,
+            format!("URL in attribute `{}` is not allowed in safe mode", key),
+            span,
+        ));
+        false
+    } else {
+        true
+    }
+}
+
+/// Returns whether the URL of a link or image is allowed in safe mode. If it
+/// isn't, a warning is emitted, and the URL should be removed.
+pub(super) fn allows_url(url: &str, span: impl Into<Span>, data: &mut AstData) -> bool {
+    match &data.config.sanitizer {
+        Some(s) if !s.allows_url(url) => {
+            data.diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::DisallowedUrl,
+                "URL scheme is not allowed in safe mode",
+                span,
+            ));
+            false
+        }
+        _ => true,
+    }
+}
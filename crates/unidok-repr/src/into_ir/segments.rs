@@ -1,9 +1,13 @@
+use detached_str::StrSlice;
+
 use crate::ast::segments::*;
-use crate::ast::AstData;
+use crate::ast::{AstData, NamedFootnote};
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
 use crate::ir::segments::*;
 use crate::quotes::ClosingQuotes;
 use crate::IntoIR;
 
+use super::sanitize;
 use super::utils::collapse_text;
 
 impl<'a> IntoIR<'a> for SegmentAst {
@@ -23,8 +27,14 @@ impl<'a> IntoIR<'a> for SegmentAst {
             SegmentAst::Link(b) => Segment::Link(b.into_ir(text, data)),
             SegmentAst::Image(b) => Segment::Image(b.into_ir(text, data)),
             SegmentAst::InlineMacro(b) => b.into_ir(text, data),
-            SegmentAst::InlineHtml(h) => Segment::InlineHtml(h.into_ir(text, data)),
+            SegmentAst::InlineHtml(h) => {
+                if !sanitize::allows_html(&h, data) {
+                    return Segment::Text("");
+                }
+                Segment::InlineHtml(h.into_ir(text, data))
+            }
             SegmentAst::HtmlEntity(e) => Segment::HtmlEntity(e),
+            SegmentAst::CharRef(c) => Segment::CharRef(c),
             SegmentAst::Format(b) => Segment::Format(b.into_ir(text, data)),
             SegmentAst::Code(b) => Segment::Code(b.into_ir(text, data)),
         }
@@ -59,11 +69,12 @@ impl<'a> IntoIR<'a> for LinkAst {
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
         match self.target {
-            LinkTarget::Url { href, title } => {
+            LinkTarget::Url { href, href_span, title } => {
                 let segments = self.text.unwrap_or_else(|| vec![SegmentAst::Text3(href.clone())]);
+                let href = Some(href).filter(|href| sanitize::allows_url(href, href_span, data));
                 Link {
                     macros: vec![],
-                    href: Some(href),
+                    href,
                     text: collapse_text(segments).into_ir(text, data),
                     title,
                     footnote: None,
@@ -71,21 +82,25 @@ impl<'a> IntoIR<'a> for LinkAst {
             }
             LinkTarget::Reference(r) => {
                 let reference = r.to_str(text);
-                match data.link_ref_defs.get(reference) {
+                match data.link_ref_defs.get(&data.link_ref_key(reference)) {
                     Some(lrd) => {
-                        let href = lrd.url.to_str(text);
+                        let url = lrd.url;
                         let segments = self.text.unwrap_or_else(|| vec![SegmentAst::Text(r)]);
 
                         let title = lrd.title.clone();
+                        let href = url.to_str(text);
+                        let href = Some(href.to_string())
+                            .filter(|href| sanitize::allows_url(href, url, data));
                         Link {
                             macros: vec![],
-                            href: Some(href.to_string()),
+                            href,
                             text: collapse_text(segments).into_ir(text, data),
                             title,
                             footnote: None,
                         }
                     }
                     None => {
+                        data.diagnostics.push(unresolved_reference(reference, r));
                         let text = if let Some(mut segments) = self.text {
                             let len = segments.len();
                             segments.push(SegmentAst::Text2("["));
@@ -109,9 +124,10 @@ impl<'a> IntoIR<'a> for LinkAst {
                     href: Some(format!("#{}", n)),
                     text: vec![Segment::Text2(format!("[{}]", n))],
                     title: None,
-                    footnote: Some(n),
+                    footnote: Some(FootnoteRef { num: n, index: 1 }),
                 }
             }
+            LinkTarget::NamedFootnote(name) => named_footnote(name, text, data),
         }
     }
 }
@@ -121,31 +137,36 @@ impl<'a> IntoIR<'a> for ImageAst {
 
     fn into_ir(self, text: &'a str, data: &mut AstData) -> Self::IR {
         match self.target {
-            LinkTarget::Url { href, title } => {
+            LinkTarget::Url { href, href_span, title } => {
                 let segments = self.alt.unwrap_or_else(|| vec![SegmentAst::Text3(href.clone())]);
+                let href = Some(href).filter(|href| sanitize::allows_url(href, href_span, data));
                 Image {
                     macros: vec![],
-                    href: Some(href),
+                    href,
                     alt: collapse_text(segments).into_ir(text, data),
                     title,
                 }
             }
             LinkTarget::Reference(r) => {
                 let reference = r.to_str(text);
-                match data.link_ref_defs.get(reference) {
+                match data.link_ref_defs.get(&data.link_ref_key(reference)) {
                     Some(lrd) => {
-                        let href = lrd.url.to_str(text);
+                        let url = lrd.url;
                         let segments = self.alt.unwrap_or_else(|| vec![SegmentAst::Text(r)]);
 
                         let title = lrd.title.clone();
+                        let href = url.to_str(text);
+                        let href = Some(href.to_string())
+                            .filter(|href| sanitize::allows_url(href, url, data));
                         Image {
                             macros: vec![],
-                            href: Some(href.to_string()),
+                            href,
                             alt: collapse_text(segments).into_ir(text, data),
                             title,
                         }
                     }
                     None => {
+                        data.diagnostics.push(unresolved_reference(reference, r));
                         let alt = if let Some(mut segments) = self.alt {
                             let len = segments.len();
                             segments.push(SegmentAst::Text2("!["));
@@ -159,11 +180,62 @@ impl<'a> IntoIR<'a> for ImageAst {
                     }
                 }
             }
-            LinkTarget::Footnote => panic!("Images can't refer to a footnote"),
+            // the parser doesn't produce images that refer to a footnote
+            LinkTarget::Footnote | LinkTarget::NamedFootnote(_) => Image {
+                macros: vec![],
+                href: None,
+                alt: collapse_text(self.alt.unwrap_or_default()).into_ir(text, data),
+                title: None,
+            },
         }
     }
 }
 
+/// Converts a reference to a footnote definition. The first reference assigns
+/// a number to the footnote, and adds it to the pending footnotes that are
+/// displayed by the next `@FOOTNOTES` macro.
+fn named_footnote<'a>(name: StrSlice, text: &'a str, data: &mut AstData) -> Link<'a> {
+    let name_str = name.to_str(text);
+
+    let (num, index) = if let Some(f) = data.named_footnotes.get_mut(name_str) {
+        f.refs += 1;
+        (f.num, f.refs)
+    } else if let Some(def) = data.footnote_defs.get(name_str) {
+        let num = data.next_footnote;
+        data.next_footnote += 1;
+        data.footnotes.push(LinkAst {
+            text: Some(def.segments.clone()),
+            target: LinkTarget::NamedFootnote(name),
+        });
+        data.named_footnotes.insert(name_str.to_string(), NamedFootnote { num, refs: 1 });
+        (num, 1)
+    } else {
+        data.diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::UndefinedFootnote,
+            format!("footnote `{}` is not defined", name_str),
+            name,
+        ));
+        let text = vec![Segment::Text2(format!("[^{}]", name_str))];
+        return Link { macros: vec![], href: None, text, title: None, footnote: None };
+    };
+
+    Link {
+        macros: vec![],
+        href: Some(format!("#{}", num)),
+        text: vec![Segment::Text2(format!("[{}]", num))],
+        title: None,
+        footnote: Some(FootnoteRef { num, index }),
+    }
+}
+
+fn unresolved_reference(reference: &str, span: StrSlice) -> Diagnostic {
+    Diagnostic::warning(
+        DiagnosticCode::UnresolvedLinkRef,
+        format!("link reference `{}` is not defined", reference),
+        span,
+    )
+}
+
 impl<'a> IntoIR<'a> for InlineFormatAst {
     type IR = InlineFormat<'a>;
 
@@ -10,8 +10,9 @@ pub struct AnnBlock<'a> {
     pub block: Block<'a>,
 }
 
-/// A block. This can be a container (list or blockquote) or a leaf block (code
-/// block, comment, heading, table, thematic break, block macro or paragraph).
+/// A block. This can be a container (list, labeled list or blockquote) or a
+/// leaf block (code block, comment, heading, table, thematic break, block macro
+/// or paragraph).
 #[derive(Debug, Clone, PartialEq)]
 pub enum Block<'a> {
     CodeBlock(CodeBlock<'a>),
@@ -20,6 +21,7 @@ pub enum Block<'a> {
     Table(Table<'a>),
     ThematicBreak(ThematicBreak),
     List(List<'a>),
+    LabeledList(LabeledList<'a>),
     Quote(Quote<'a>),
     BlockHtml(HtmlNode<'a>),
     Braces(Vec<AnnBlock<'a>>),
@@ -66,7 +68,13 @@ pub struct TableRow<'a> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableCell<'a> {
     pub meta: CellMeta,
-    pub segments: Vec<Segment<'a>>,
+    pub content: CellContent<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellContent<'a> {
+    Inline(Vec<Segment<'a>>),
+    Blocks(Vec<AnnBlock<'a>>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -83,6 +91,20 @@ pub struct List<'a> {
     pub macros: Vec<Macro<'a>>,
     pub bullet: Bullet,
     pub items: Vec<Vec<AnnBlock<'a>>>,
+    /// Whether each item is checked, if it is a task list item
+    pub checked: Vec<Option<bool>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledList<'a> {
+    pub macros: Vec<Macro<'a>>,
+    pub items: Vec<LabeledItem<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledItem<'a> {
+    pub label: Vec<Segment<'a>>,
+    pub content: Vec<AnnBlock<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,6 +10,7 @@ pub enum HtmlNode<'a> {
     CData(&'a str),
     Comment(String),
     Doctype(&'a str),
+    Raw(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -21,6 +22,14 @@ pub struct HtmlElem<'a> {
     pub close: ElemClose,
 }
 
+impl HtmlElem<'_> {
+    /// Returns whether the element is block-level. Inline custom elements
+    /// are block-level as well when they contain blocks.
+    pub fn is_block_level(&self) -> bool {
+        self.name.is_block_level() || matches!(self.content, Some(ElemContent::Blocks(_)))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Attr<'a> {
     pub key: &'a str,
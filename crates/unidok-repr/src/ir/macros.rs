@@ -1,4 +1,5 @@
 use super::segments::Segment;
+use crate::ast::blocks::CellAlignment;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Macro<'a> {
@@ -18,14 +19,37 @@ pub enum Macro<'a> {
     Footnotes(Vec<Footnote<'a>>),
     /// `@MATH_SCRIPT`
     MathScript,
+    /// `@MATH_SCRIPT(head)`
+    MathScriptInHead,
     /// `@BLANK`
     Blank,
     /// `@CONFIG()`
     Config,
+    /// `@META()`
+    Meta,
 
     /// `@INCLUDE(...)`
     Include(&'a str),
 
+    /// `@NOTE`, `@TIP`, `@WARNING`, `@CAUTION` or `@IMPORTANT`, with the
+    /// custom or localized title
+    Admonition(AdmonitionKind, String),
+
+    /// `@DETAILS(summary)`, which is collapsible and initially closed unless
+    /// the `open` flag is set
+    Details {
+        summary: Vec<Segment<'a>>,
+        open: bool,
+    },
+
+    /// `@CAPTION(text)` or `@FIGURE`. The kind and number are determined when
+    /// the annotated block is known.
+    Caption(Caption<'a>),
+
+    /// `@COLS(...)`, with the attributes and default alignment of each column
+    /// of a table
+    Cols(Vec<Column<'a>>),
+
     Invalid,
 }
 
@@ -35,6 +59,114 @@ impl Macro<'_> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdmonitionKind {
+    Note,
+    Tip,
+    Warning,
+    Caution,
+    Important,
+}
+
+impl AdmonitionKind {
+    pub fn from_macro_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "NOTE" => AdmonitionKind::Note,
+            "TIP" => AdmonitionKind::Tip,
+            "WARNING" => AdmonitionKind::Warning,
+            "CAUTION" => AdmonitionKind::Caution,
+            "IMPORTANT" => AdmonitionKind::Important,
+            _ => return None,
+        })
+    }
+
+    /// The CSS class of the admonition
+    pub fn class(self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Warning => "warning",
+            AdmonitionKind::Caution => "caution",
+            AdmonitionKind::Important => "important",
+        }
+    }
+
+    /// The default title in the language configured with `@CONFIG(lang=…)`.
+    /// Falls back to English for languages without a translation.
+    pub fn title(self, lang: Option<&str>) -> &'static str {
+        let titles = match lang {
+            Some("german" | "swiss") => ["Hinweis", "Tipp", "Warnung", "Vorsicht", "Wichtig"],
+            Some("french") => ["Remarque", "Astuce", "Avertissement", "Attention", "Important"],
+            Some("spanish") => ["Nota", "Consejo", "Advertencia", "Precaución", "Importante"],
+            Some("italian") => ["Nota", "Suggerimento", "Avvertenza", "Attenzione", "Importante"],
+            Some("portuguese" | "brazilian") => ["Nota", "Dica", "Aviso", "Cuidado", "Importante"],
+            Some("dutch") => ["Opmerking", "Tip", "Waarschuwing", "Let op", "Belangrijk"],
+            Some("russian") => ["Примечание", "Совет", "Предупреждение", "Осторожно", "Важно"],
+            _ => ["Note", "Tip", "Warning", "Caution", "Important"],
+        };
+        titles[self as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption<'a> {
+    pub kind: FigureKind,
+    pub number: u32,
+    /// The localized label, e.g. `Figure`
+    pub label: &'static str,
+    pub text: Vec<Segment<'a>>,
+}
+
+impl Caption<'_> {
+    /// The generated id, which can be used to link to the figure
+    pub fn id(&self) -> String {
+        format!("{}-{}", self.kind.id_prefix(), self.number)
+    }
+}
+
+/// Figures are numbered separately for each kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FigureKind {
+    Figure,
+    Table,
+    Listing,
+}
+
+impl FigureKind {
+    pub fn id_prefix(self) -> &'static str {
+        match self {
+            FigureKind::Figure => "figure",
+            FigureKind::Table => "table",
+            FigureKind::Listing => "listing",
+        }
+    }
+
+    /// The label in the language configured with `@CONFIG(lang=…)`. Falls back
+    /// to English for languages without a translation.
+    pub fn label(self, lang: Option<&str>) -> &'static str {
+        let labels = match lang {
+            Some("german" | "swiss") => ["Abbildung", "Tabelle", "Listing"],
+            Some("french") => ["Figure", "Tableau", "Listing"],
+            Some("spanish") => ["Figura", "Tabla", "Listado"],
+            Some("italian") => ["Figura", "Tabella", "Listato"],
+            Some("portuguese" | "brazilian") => ["Figura", "Tabela", "Listagem"],
+            Some("dutch") => ["Figuur", "Tabel", "Listing"],
+            Some("russian") => ["Рисунок", "Таблица", "Листинг"],
+            _ => ["Figure", "Table", "Listing"],
+        };
+        labels[self as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column<'a> {
+    pub attrs: Vec<Attr<'a>>,
+    /// The number of columns this applies to, set with `span=N`
+    pub span: u16,
+    pub alignment: CellAlignment,
+    pub vertical_alignment: CellAlignment,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Attr<'a> {
     pub key: &'a str,
@@ -47,6 +179,15 @@ pub enum AttrValue<'a> {
     QuotedWord(String),
 }
 
+impl AttrValue<'_> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AttrValue::Word(w) => w,
+            AttrValue::QuotedWord(w) => w,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Footnote<'a> {
     pub num: u32,
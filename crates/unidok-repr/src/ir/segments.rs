@@ -17,6 +17,7 @@ pub enum Segment<'a> {
     Image(Image<'a>),
     InlineHtml(HtmlNode<'a>),
     HtmlEntity(HtmlEntity),
+    CharRef(char),
     Format(InlineFormat<'a>),
     Code(Code<'a>),
 }
@@ -39,7 +40,16 @@ pub struct Link<'a> {
     pub href: Option<String>,
     pub text: Vec<Segment<'a>>,
     pub title: Option<String>,
-    pub footnote: Option<u32>,
+    pub footnote: Option<FootnoteRef>,
+}
+
+/// A reference to a footnote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FootnoteRef {
+    pub num: u32,
+    /// The index of the reference, starting at 1. Named footnotes can be
+    /// referenced more than once.
+    pub index: u32,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use crate::ast::AstData;
 use crate::config::Config;
 use crate::ir::blocks::Heading;
+use crate::metadata::Metadata;
 use crate::IntoIR;
 
 use super::segments::Link;
@@ -9,12 +12,17 @@ use super::segments::Link;
 pub struct IrState<'a> {
     pub headings: Vec<Heading<'a>>,
     pub contains_math: bool,
+    pub math_script_in_head: bool,
 
     pub footnotes: Vec<Link<'a>>,
     pub footnote_index: usize,
     pub footnote_number: u32,
+    /// The number of references to each named footnote, by footnote number.
+    /// This includes references after the `@FOOTNOTES` macro.
+    pub footnote_refs: HashMap<u32, u32>,
 
     pub config: Config,
+    pub metadata: Metadata,
 }
 
 impl<'a> IrState<'a> {
@@ -22,14 +30,18 @@ impl<'a> IrState<'a> {
         let headings = state.headings.clone().into_ir(text, &mut state);
         let footnotes = state.footnotes.clone().into_ir(text, &mut state);
         let contains_math = state.contains_math;
+        let footnote_refs = state.named_footnotes.values().map(|f| (f.num, f.refs)).collect();
 
         IrState {
             headings,
             contains_math,
+            math_script_in_head: state.math_script_in_head,
             footnotes,
             footnote_index: 0,
             footnote_number: 1,
+            footnote_refs,
             config: state.config,
+            metadata: state.metadata,
         }
     }
 }
@@ -1,7 +1,10 @@
 pub mod ast;
 pub mod config;
+pub mod diagnostics;
 pub mod ir;
+pub mod metadata;
 pub mod quotes;
+pub mod sanitizer;
 pub mod try_reduce;
 
 mod into_ir;
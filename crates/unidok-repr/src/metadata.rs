@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+/// Document metadata, e.g. the title, author, date or tags. It is specified in
+/// a front-matter block at the start of the document, or with the `@META`
+/// macro.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    entries: BTreeMap<String, MetaValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    List(Vec<MetaValue>),
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Metadata::default()
+    }
+
+    /// Inserts a value. If the key already exists, the previous value is
+    /// replaced and returned.
+    pub fn insert(&mut self, key: impl Into<String>, value: MetaValue) -> Option<MetaValue> {
+        self.entries.insert(key.into(), value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.entries.get(key)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_int()
+    }
+
+    pub fn get_float(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_float()
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&[MetaValue]> {
+        self.get(key)?.as_list()
+    }
+
+    /// Returns the `title` entry, if it is a string
+    pub fn title(&self) -> Option<&str> {
+        self.get_str("title")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Iterates over the entries, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetaValue)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+impl MetaValue {
+    /// Converts an unquoted word to a value. `true` and `false` become
+    /// booleans, numbers become integers or floats, and everything else
+    /// becomes a string.
+    pub fn from_word(word: &str) -> Self {
+        match word {
+            "true" => MetaValue::Bool(true),
+            "false" => MetaValue::Bool(false),
+            _ => {
+                // this excludes `inf` and `NaN`
+                let is_numeric =
+                    word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.');
+
+                if let Ok(n) = word.parse() {
+                    MetaValue::Int(n)
+                } else if let Some(n) = word.parse().ok().filter(|_| is_numeric) {
+                    MetaValue::Float(n)
+                } else {
+                    MetaValue::String(word.to_string())
+                }
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let MetaValue::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let MetaValue::Bool(b) = *self {
+            Some(b)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        if let MetaValue::Int(n) = *self {
+            Some(n)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number as a float. This also works for integers.
+    pub fn as_float(&self) -> Option<f64> {
+        match *self {
+            MetaValue::Float(n) => Some(n),
+            MetaValue::Int(n) => Some(n as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[MetaValue]> {
+        if let MetaValue::List(l) = self {
+            Some(l)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "serde-spans")]
+mod serde_impls {
+    use serde::ser::SerializeMap;
+    use serde::Serialize;
+
+    use super::{MetaValue, Metadata};
+
+    impl Serialize for Metadata {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut map = serializer.serialize_map(Some(self.len()))?;
+            for (key, value) in self.iter() {
+                map.serialize_entry(key, value)?;
+            }
+            map.end()
+        }
+    }
+
+    impl Serialize for MetaValue {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            match self {
+                MetaValue::String(s) => serializer.serialize_str(s),
+                &MetaValue::Bool(b) => serializer.serialize_bool(b),
+                &MetaValue::Int(n) => serializer.serialize_i64(n),
+                &MetaValue::Float(n) => serializer.serialize_f64(n),
+                MetaValue::List(l) => l.serialize(serializer),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_from_word() {
+    assert_eq!(MetaValue::from_word("true"), MetaValue::Bool(true));
+    assert_eq!(MetaValue::from_word("-12"), MetaValue::Int(-12));
+    assert_eq!(MetaValue::from_word("1.5"), MetaValue::Float(1.5));
+    assert_eq!(MetaValue::from_word("inf"), MetaValue::String("inf".into()));
+    assert_eq!(MetaValue::from_word("2021-07-05"), MetaValue::String("2021-07-05".into()));
+}
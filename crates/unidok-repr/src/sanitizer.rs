@@ -0,0 +1,121 @@
+/// Allowlists for safe mode. HTML elements, attributes and URLs that aren't
+/// allowed are removed when the document is converted to the IR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sanitizer {
+    /// Allowed HTML elements. A trailing `*` matches any suffix, e.g. `x-*`
+    /// for custom elements
+    pub elements: Vec<String>,
+    /// Allowed HTML attributes. A trailing `*` matches any suffix, e.g.
+    /// `data-*`
+    pub attributes: Vec<String>,
+    /// Allowed URL schemes, e.g. `https`. Relative URLs are always allowed.
+    pub url_schemes: Vec<String>,
+}
+
+#[rustfmt::skip]
+const ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "blockquote", "br", "caption", "cite", "code", "col",
+    "colgroup", "dd", "del", "details", "dfn", "div", "dl", "dt", "em", "figcaption", "figure",
+    "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "ins", "kbd", "li", "mark", "ol", "p",
+    "picture", "pre", "q", "rp", "rt", "ruby", "s", "samp", "small", "source", "span", "strike",
+    "strong", "sub", "summary", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "time",
+    "tr", "tt", "u", "ul", "var", "wbr",
+];
+
+#[rustfmt::skip]
+const ATTRIBUTES: &[&str] = &[
+    "abbr", "align", "alt", "aria-*", "border", "cite", "class", "colspan", "data-*", "datetime",
+    "dir", "headers", "height", "href", "hreflang", "id", "lang", "open", "rel", "reversed",
+    "rowspan", "scope", "span", "src", "start", "summary", "title", "type", "valign", "value",
+    "width",
+];
+
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        let to_vec = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        Sanitizer {
+            elements: to_vec(ELEMENTS),
+            attributes: to_vec(ATTRIBUTES),
+            url_schemes: to_vec(URL_SCHEMES),
+        }
+    }
+}
+
+impl Sanitizer {
+    pub fn allows_element(&self, name: &str) -> bool {
+        self.elements.iter().any(|e| matches_pattern(e, name))
+    }
+
+    pub fn allows_attribute(&self, key: &str) -> bool {
+        self.attributes.iter().any(|a| matches_pattern(a, key))
+    }
+
+    /// Returns whether the attribute value is allowed. This checks the URLs
+    /// in attributes such as `href` and `src`.
+    pub fn allows_attribute_value(&self, key: &str, value: &str) -> bool {
+        if key.eq_ignore_ascii_case("srcset") {
+            value.split(',').all(|c| self.allows_url(c.split_whitespace().next().unwrap_or("")))
+        } else {
+            !is_url_attribute(key) || self.allows_url(value)
+        }
+    }
+
+    /// Returns whether the URL is relative or has an allowed scheme
+    pub fn allows_url(&self, url: &str) -> bool {
+        // browsers ignore leading control characters and spaces, and tabs and
+        // line breaks anywhere in the URL
+        let url: String = url
+            .trim_start_matches(|c: char| c <= ' ')
+            .chars()
+            .filter(|&c| !matches!(c, '\t' | '\n' | '\r'))
+            .collect();
+
+        match url.find([':', '/', '?', '#']) {
+            Some(i) if url[i..].starts_with(':') => {
+                self.url_schemes.iter().any(|s| s.eq_ignore_ascii_case(&url[..i]))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Matches a name case-insensitively against an allowlist entry, which may
+/// end with a `*` wildcard
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => {
+            matches!(name.get(..prefix.len()), Some(p) if p.eq_ignore_ascii_case(prefix))
+        }
+        None => pattern.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Returns whether the attribute's value is a URL
+pub fn is_url_attribute(key: &str) -> bool {
+    ["href", "src", "xlink:href", "action", "formaction", "cite", "poster", "background"]
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(key))
+}
+
+#[test]
+fn test_sanitizer() {
+    let s = Sanitizer::default();
+    assert!(s.allows_element("DIV"));
+    assert!(!s.allows_element("script"));
+    assert!(!s.allows_element("x-foo"));
+    assert!(s.allows_attribute("data-foo"));
+    assert!(!s.allows_attribute("onclick"));
+    assert!(s.allows_attribute_value("href", "/foo:bar"));
+    assert!(!s.allows_attribute_value("href", " java\tscript:alert(1)"));
+    assert!(!s.allows_attribute_value("srcset", "a.png 1x, javascript:b 2x"));
+    assert!(s.allows_url("HTTPS://example.org"));
+    assert!(s.allows_url("#foo"));
+    assert!(!s.allows_url("data:text/html,foo"));
+
+    let s = Sanitizer { elements: vec!["svg".into(), "x-*".into()], ..Sanitizer::default() };
+    assert!(s.allows_element("SVG"));
+    assert!(s.allows_element("x-foo"));
+    assert!(!s.allows_element("div"));
+}
@@ -112,6 +112,10 @@ pub enum SyntaxKind {
     CurlyBraces,
 
     Escaped,
+
+    FrontMatter,
+
+    TaskMarker,
 }
 
 #[cfg(feature = "serde-spans")]
@@ -15,6 +15,7 @@ impl ToPlaintext for Segment<'_> {
                 buf.push('&');
                 buf.push_str(e.0);
             }
+            &Segment::CharRef(c) => buf.push(c),
             Segment::Limiter => {}
             &Segment::Text(t) => buf.push_str(t),
             Segment::Text2(t) => buf.push_str(t),
@@ -125,7 +126,37 @@ impl ToPlaintext for HtmlElem<'_> {
 impl ToPlaintext for AnnBlock<'_> {
     fn to_plaintext(&self, buf: &mut String) {
         if self.macros.iter().all(|m| !matches!(m, Macro::NoText)) {
+            for r#macro in &self.macros {
+                match r#macro {
+                    Macro::Admonition(_, title) => {
+                        buf.push_str(title);
+                        buf.push_str(": ");
+                    }
+                    Macro::Details { summary, .. } if !summary.is_empty() => {
+                        for s in summary {
+                            s.to_plaintext(buf);
+                        }
+                        buf.push_str(": ");
+                    }
+                    _ => {}
+                }
+            }
             self.block.to_plaintext(buf);
+
+            for r#macro in &self.macros {
+                if let Macro::Caption(caption) = r#macro {
+                    buf.push_str(caption.label);
+                    buf.push(' ');
+                    buf.push_str(&caption.number.to_string());
+                    if !caption.text.is_empty() {
+                        buf.push_str(": ");
+                        for s in &caption.text {
+                            s.to_plaintext(buf);
+                        }
+                    }
+                    buf.push('\n');
+                }
+            }
         }
     }
 }
@@ -138,7 +169,7 @@ impl ToPlaintext for Block<'_> {
             Block::Heading(h) => h.to_plaintext(buf),
             Block::Table(_) => {} // TODO: Emit warning
             Block::ThematicBreak(_) => buf.push_str("---------\n\n"),
-            Block::List(_) => {} // TODO: Emit warning
+            Block::List(_) | Block::LabeledList(_) => {} // TODO: Emit warning
             Block::Quote(q) => q.to_plaintext(buf),
             Block::Braces(blocks) => {
                 for block in blocks {
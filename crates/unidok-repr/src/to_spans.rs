@@ -1,5 +1,6 @@
 use crate::ast::blocks::{
-    BlockAst, CodeBlockAst, Comment, HeadingAst, LinkRefDef, ListAst, QuoteAst, TableAst,
+    BlockAst, CellContentAst, CodeBlockAst, Comment, FootnoteDef, HeadingAst, HeadingKind,
+    LabeledListAst, LinkRefDef, ListAst, QuoteAst, TableAst,
 };
 use crate::ast::html::{ElemContentAst, HtmlNodeAst};
 use crate::ast::macros::{
@@ -23,11 +24,13 @@ impl ToSpans for BlockAst {
             BlockAst::Table(t) => t.to_spans(buf),
             BlockAst::ThematicBreak(_) => {}
             BlockAst::List(l) => l.to_spans(buf),
+            BlockAst::LabeledList(l) => l.to_spans(buf),
             BlockAst::Quote(q) => q.to_spans(buf),
             BlockAst::BlockMacro(b) => b.to_spans(buf),
             BlockAst::BlockHtml(h) => h.to_spans(buf),
             BlockAst::Comment(c) => c.to_spans(buf),
             BlockAst::LinkRefDef(l) => l.to_spans(buf),
+            BlockAst::FootnoteDef(f) => f.to_spans(buf),
         }
     }
 }
@@ -48,7 +51,7 @@ impl ToSpans for SegmentAst {
             SegmentAst::Image(i) => i.to_spans(buf),
             SegmentAst::InlineMacro(m) => m.to_spans(buf),
             SegmentAst::InlineHtml(i) => i.to_spans(buf),
-            SegmentAst::HtmlEntity(_) => {}
+            SegmentAst::HtmlEntity(_) | SegmentAst::CharRef(_) => {}
             SegmentAst::Format(f) => f.to_spans(buf),
             SegmentAst::Code(c) => c.to_spans(buf),
         }
@@ -73,6 +76,12 @@ impl ToSpans for [BlockAst] {
 
 impl ToSpans for HeadingAst {
     fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
+        let kind = match (self.kind, self.level) {
+            (HeadingKind::Atx, _) => SyntaxKind::AtxHeading,
+            (HeadingKind::Setext, 1) => SyntaxKind::SetextHeading1,
+            (HeadingKind::Setext, _) => SyntaxKind::SetextHeading2,
+        };
+        buf.push(self.span.with(kind));
         self.segments.to_spans(buf);
     }
 }
@@ -101,7 +110,14 @@ impl ToSpans for TableAst {
     fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
         for r in &self.rows {
             for c in &r.cells {
-                c.segments.to_spans(buf);
+                buf.push(c.span.with(SyntaxKind::TableCell));
+                if !c.meta_span.is_empty() {
+                    buf.push(c.meta_span.with(SyntaxKind::TableCellMeta));
+                }
+                match &c.content {
+                    CellContentAst::Inline(s) => s.to_spans(buf),
+                    CellContentAst::Blocks(b) => b.to_spans(buf),
+                }
             }
         }
     }
@@ -123,12 +139,26 @@ impl ToSpans for BlockMacro {
 
 impl ToSpans for ListAst {
     fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
-        for item in &self.items {
+        for ((item, &bullet), task) in self.items.iter().zip(&self.bullets).zip(&self.tasks) {
+            buf.push(bullet.with(SyntaxKind::ListMarker));
+            if let Some(task) = task {
+                buf.push(task.span.with(SyntaxKind::TaskMarker));
+            }
             item.to_spans(buf);
         }
     }
 }
 
+impl ToSpans for LabeledListAst {
+    fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
+        for item in &self.items {
+            item.label.to_spans(buf);
+            buf.push(item.marker.with(SyntaxKind::ListMarker));
+            item.content.to_spans(buf);
+        }
+    }
+}
+
 impl ToSpans for QuoteAst {
     fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
         self.content.to_spans(buf);
@@ -150,7 +180,7 @@ impl ToSpans for HtmlNodeAst {
             HtmlNodeAst::CData(c) => {
                 buf.push(Span::from(c.text).with(SyntaxKind::HtmlCdata));
             }
-            HtmlNodeAst::Comment(_) => {}
+            HtmlNodeAst::Comment(_) | HtmlNodeAst::Raw(_) => {}
             HtmlNodeAst::Doctype(d) => {
                 buf.push(Span::from(d.text).with(SyntaxKind::HtmlCdata));
             }
@@ -165,6 +195,13 @@ impl ToSpans for LinkRefDef {
     }
 }
 
+impl ToSpans for FootnoteDef {
+    fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
+        buf.push(Span::from(self.name).with(SyntaxKind::Footnote));
+        self.segments.to_spans(buf);
+    }
+}
+
 impl ToSpans for BracesAst {
     fn to_spans(&self, buf: &mut Vec<SyntaxSpan>) {
         self.segments.to_spans(buf);
@@ -178,11 +215,14 @@ impl ToSpans for LinkAst {
         }
 
         match self.target {
-            LinkTarget::Url { href: _, title: _ } => {}
+            LinkTarget::Url { .. } => {}
             LinkTarget::Reference(r) => {
                 buf.push(Span::from(r).with(SyntaxKind::LinkRef));
             }
             LinkTarget::Footnote => {}
+            LinkTarget::NamedFootnote(name) => {
+                buf.push(Span::from(name).with(SyntaxKind::Footnote));
+            }
         }
     }
 }
@@ -194,11 +234,11 @@ impl ToSpans for ImageAst {
         }
 
         match self.target {
-            LinkTarget::Url { href: _, title: _ } => {}
+            LinkTarget::Url { .. } => {}
             LinkTarget::Reference(r) => {
                 buf.push(Span::from(r).with(SyntaxKind::LinkRef));
             }
-            LinkTarget::Footnote => {}
+            LinkTarget::Footnote | LinkTarget::NamedFootnote(_) => {}
         }
     }
 }
@@ -258,13 +298,14 @@ impl ToSpans for TokenTreeAtom {
             &TokenTreeAtom::Word(w) => {
                 buf.push(Span::from(w).with(SyntaxKind::MacroArg));
             }
-            TokenTreeAtom::QuotedWord(_) => {}
+            TokenTreeAtom::QuotedWord(..) => {}
             TokenTreeAtom::Tuple(tts) => {
                 for tt in tts {
                     tt.to_spans(buf);
                 }
             }
-            TokenTreeAtom::Braces(b) => b.to_spans(buf),
+            TokenTreeAtom::Braces(b, _) => b.to_spans(buf),
+            TokenTreeAtom::QuotedSegments(s, _) => s.to_spans(buf),
         }
     }
 }
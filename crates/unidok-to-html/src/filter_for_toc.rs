@@ -1,4 +1,4 @@
-use unidok_repr::ir::html::HtmlNode;
+use unidok_repr::ir::html::{ElemContent, HtmlElem, HtmlNode};
 use unidok_repr::ir::macros::Macro;
 use unidok_repr::ir::segments::{Braces, Code, Image, InlineFormat, Link, Segment};
 
@@ -9,7 +9,7 @@ pub fn filter_for_toc<'a>(s: &[Segment<'a>]) -> Vec<Segment<'a>> {
                 if is_allowed_toc(macros) {
                     Some(Segment::Braces(Braces {
                         macros: macros.clone(),
-                        segments: filter_for_toc(&segments),
+                        segments: filter_for_toc(segments),
                     }))
                 } else {
                     None
@@ -48,11 +48,24 @@ pub fn filter_for_toc<'a>(s: &[Segment<'a>]) -> Vec<Segment<'a>> {
                 }
             }
             Segment::InlineHtml(HtmlNode::Element(e)) => {
-                if is_allowed_toc(&e.macros) {
-                    Some(Segment::InlineHtml(HtmlNode::Element(e.clone())))
-                } else {
-                    None
+                if !is_allowed_toc(&e.macros) {
+                    return None;
                 }
+                let content = match &e.content {
+                    Some(ElemContent::Inline(segments)) => {
+                        Some(ElemContent::Inline(filter_for_toc(segments)))
+                    }
+                    // blocks could contain a `@TOC`
+                    Some(ElemContent::Blocks(_)) => return None,
+                    content => content.clone(),
+                };
+                Some(Segment::InlineHtml(HtmlNode::Element(HtmlElem {
+                    macros: e.macros.clone(),
+                    name: e.name.clone(),
+                    attrs: e.attrs.clone(),
+                    content,
+                    close: e.close,
+                })))
             }
             Segment::Code(c) => {
                 if is_allowed_toc(&c.macros) {
@@ -75,6 +88,7 @@ pub fn filter_for_toc<'a>(s: &[Segment<'a>]) -> Vec<Segment<'a>> {
         .collect()
 }
 
+/// A `@TOC` within a heading would contain itself, so it is removed as well
 fn is_allowed_toc(m: &[Macro<'_>]) -> bool {
-    m.iter().all(|m| !matches!(m, Macro::NoToc))
+    m.iter().all(|m| !matches!(m, Macro::NoToc | Macro::Toc))
 }
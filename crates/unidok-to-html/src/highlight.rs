@@ -0,0 +1,69 @@
+//! Server-side syntax highlighting of code blocks. This requires the `syntect`
+//! feature; without it, code blocks are never highlighted.
+
+#[cfg(feature = "syntect")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "syntect")]
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+#[cfg(feature = "syntect")]
+use syntect::parsing::SyntaxSet;
+#[cfg(feature = "syntect")]
+use syntect::util::LinesWithEndings;
+
+/// All CSS classes emitted by the highlighter start with this prefix.
+#[cfg(feature = "syntect")]
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+#[cfg(feature = "syntect")]
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+#[cfg(feature = "syntect")]
+static THEME_SET: Lazy<syntect::highlighting::ThemeSet> =
+    Lazy::new(syntect::highlighting::ThemeSet::load_defaults);
+
+/// Highlights the code and returns HTML consisting of `<span>` elements with
+/// CSS classes. Returns `None` if the language isn't supported.
+#[cfg(feature = "syntect")]
+pub(crate) fn highlight(lang: &str, code: &str) -> Option<String> {
+    let syntax = SYNTAX_SET.find_syntax_by_token(lang)?;
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).ok()?;
+    }
+    Some(generator.finalize())
+}
+
+#[cfg(not(feature = "syntect"))]
+pub(crate) fn highlight(_: &str, _: &str) -> Option<String> {
+    None
+}
+
+/// Returns the names of the built-in syntax highlighting themes.
+#[cfg(feature = "syntect")]
+pub fn highlight_themes() -> Vec<&'static str> {
+    THEME_SET.themes.keys().map(String::as_str).collect()
+}
+
+/// Returns the CSS for a built-in syntax highlighting theme, or `None` if the
+/// theme doesn't exist.
+#[cfg(feature = "syntect")]
+pub fn highlight_css(theme: &str) -> Option<String> {
+    let theme = THEME_SET.themes.get(theme)?;
+    syntect::html::css_for_theme_with_class_style(theme, CLASS_STYLE).ok()
+}
+
+#[cfg(feature = "syntect")]
+#[test]
+fn test_highlight() {
+    let html = highlight("rust", "let x = 5;\n").unwrap();
+    assert!(html.starts_with(r#"<span class="hl-source hl-rust">"#));
+    assert!(html.contains(r#"<span class="hl-storage hl-type hl-rust">let</span>"#));
+
+    assert_eq!(highlight("no-such-language", "foo\n"), None);
+
+    let css = highlight_css("InspiredGitHub").unwrap();
+    assert!(css.contains(".hl-"));
+    assert_eq!(highlight_css("no-such-theme"), None);
+}
@@ -2,9 +2,10 @@ use std::mem::take;
 
 use unidok_repr::ast::blocks::{Bullet, CellAlignment};
 use unidok_repr::ast::html::ElemName;
-use unidok_repr::config::HeadingAnchor;
+use unidok_repr::config::{Dialect, HeadingAnchor};
 use unidok_repr::ir::blocks::*;
 use unidok_repr::ir::macros::Macro;
+use unidok_repr::ir::segments::Segment;
 use unidok_repr::ir::IrState;
 use unidok_repr::try_reduce::{Reduced1, TryReduce};
 
@@ -30,6 +31,7 @@ impl<'a> IntoNode<'a> for Block<'a> {
             Block::Table(t) => t.into_node(state),
             Block::BlockHtml(h) => h.into_node(state),
             Block::List(l) => l.into_node(state),
+            Block::LabeledList(l) => l.into_node(state),
             Block::Quote(q) => q.into_node(state),
             Block::Braces(m) => Node::Fragment(m.into_nodes(state)),
             Block::Empty => Node::Fragment(vec![]),
@@ -70,14 +72,28 @@ fn into_nodes_tight<'a>(blocks: Vec<AnnBlock<'a>>, state: &IrState<'a>) -> Vec<N
 impl<'a> IntoNode<'a> for CodeBlock<'a> {
     fn into_node(self, state: &IrState<'a>) -> Node<'a> {
         let info = self.info.trim_start();
-        let attrs = if !info.is_empty() {
-            let lang = info.split(|c| matches!(c, ' ' | '\t' | ',' | ';')).next().unwrap();
+        let lang = match state.config.dialect {
+            Dialect::Unidok => info.split([' ', '\t', ',', ';']).next().unwrap(),
+            Dialect::CommonMark => info.split([' ', '\t']).next().unwrap(),
+        };
+        let attrs = if lang.is_empty() {
+            vec![]
+        } else if state.config.dialect == Dialect::CommonMark {
+            // CommonMark puts the language in a class
+            vec![Attr { key: "class", value: Some(format!("language-{}", lang)) }]
+        } else {
             vec![Attr { key: "data-language", value: Some(lang.to_string()) }]
+        };
+
+        let highlighted = if state.config.highlight && !lang.is_empty() {
+            code_block_text(&self.lines).and_then(|code| crate::highlight::highlight(lang, &code))
         } else {
-            vec![]
+            None
         };
 
-        let content = if self.lines.is_empty() {
+        let content = if let Some(html) = highlighted {
+            vec![Node::Verbatim(html)]
+        } else if self.lines.is_empty() {
             vec![]
         } else {
             self.lines
@@ -101,6 +117,26 @@ impl<'a> IntoNode<'a> for CodeBlock<'a> {
     }
 }
 
+/// Returns the text of a code block, if it only contains plain text
+fn code_block_text(lines: &[Block<'_>]) -> Option<String> {
+    let mut text = String::new();
+    for line in lines {
+        if let Block::Paragraph(p) = line {
+            for segment in &p.segments {
+                match segment {
+                    Segment::Text(t) | Segment::EscapedText(t) => text.push_str(t),
+                    Segment::Text2(t) => text.push_str(t),
+                    _ => return None,
+                }
+            }
+            text.push('\n');
+        } else {
+            return None;
+        }
+    }
+    Some(text)
+}
+
 impl<'a> IntoNode<'a> for Paragraph<'a> {
     fn into_node(self, state: &IrState<'a>) -> Node<'a> {
         let segments = into_nodes_trimmed(self.segments, state);
@@ -184,8 +220,8 @@ impl<'a> IntoNode<'a> for Heading<'a> {
             3 => ElemName::H3,
             4 => ElemName::H4,
             5 => ElemName::H5,
-            6 => ElemName::H6,
-            l => panic!("Invalid heading level {}", l),
+            // the level is clamped to 1..=6 when the heading is converted to the IR
+            _ => ElemName::H6,
         };
 
         let slug = self.slug;
@@ -255,13 +291,21 @@ impl<'a> IntoNode<'a> for List<'a> {
             match r#macro {
                 Macro::Loose => loose = true,
                 Macro::ListStyle(s) => list_style = Some(s),
-                r#macro => {
-                    panic!("Unexpected macro {:?}", r#macro)
-                }
+                // other macros are applied to the list element, not the list
+                _ => {}
             }
         }
 
-        let mut attrs = if loose { vec![attr!(class = "loose")] } else { vec![] };
+        let is_task_list = self.checked.iter().any(Option::is_some);
+        // CommonMark doesn't mark loose lists with a class
+        let loose_class = loose && state.config.dialect == Dialect::Unidok;
+        let class = match (loose_class, is_task_list) {
+            (true, true) => Some("loose task-list"),
+            (true, false) => Some("loose"),
+            (false, true) => Some("task-list"),
+            (false, false) => None,
+        };
+        let mut attrs = class.map(|c| attr!(class = c)).into_iter().collect::<Vec<_>>();
 
         if let Some(list_style) = list_style {
             attrs.push(attr!(style = format!("list-style: {}", list_style)));
@@ -274,9 +318,13 @@ impl<'a> IntoNode<'a> for List<'a> {
         let items = self
             .items
             .into_iter()
-            .map(|it| {
-                let content =
+            .zip(self.checked)
+            .map(|(it, checked)| {
+                let mut content =
                     if loose { it.into_nodes(state) } else { into_nodes_tight(it, state) };
+                if let Some(checked) = checked {
+                    add_checkbox(&mut content, checked);
+                }
 
                 Node::Element(elem!(
                     <Li>{ content } is_block_level: true, contains_blocks: loose
@@ -290,6 +338,59 @@ impl<'a> IntoNode<'a> for List<'a> {
     }
 }
 
+impl<'a> IntoNode<'a> for LabeledList<'a> {
+    fn into_node(self, state: &IrState<'a>) -> Node<'a> {
+        // labeled lists have no bullets, so the list style is ignored
+        let loose = self.macros.iter().any(|m| matches!(m, Macro::Loose));
+
+        let attrs = if loose { vec![attr!(class = "loose")] } else { vec![] };
+
+        let mut items = Vec::with_capacity(self.items.len() * 2);
+        for item in self.items {
+            items.push(Node::Element(elem!(
+                <Dt>{ item.label.into_nodes(state) } is_block_level: true, contains_blocks: false
+            )));
+
+            // consecutive labels can share a definition
+            if !item.content.is_empty() {
+                let content = if loose {
+                    item.content.into_nodes(state)
+                } else {
+                    into_nodes_tight(item.content, state)
+                };
+                items.push(Node::Element(elem!(
+                    <Dd>{ content } is_block_level: true, contains_blocks: loose
+                )));
+            }
+        }
+
+        Node::Element(elem!(
+            <Dl {attrs}>{ items } is_block_level: true, contains_blocks: true
+        ))
+    }
+}
+
+/// Inserts the checkbox of a task list item before the item's content. In a
+/// loose list, it is inserted into the first paragraph.
+fn add_checkbox(content: &mut Vec<Node<'_>>, checked: bool) {
+    let mut attrs = vec![attr!(type = "checkbox"), attr!(disabled)];
+    if checked {
+        attrs.push(attr!(checked));
+    }
+    let checkbox = Node::Element(elem!(
+        <Input {attrs} /> is_block_level: false, contains_blocks: false
+    ));
+
+    let target = match content.first_mut() {
+        Some(Node::Element(Element { name: ElemName::P, content: Some(c), .. })) => c,
+        _ => content,
+    };
+    if !target.is_empty() {
+        target.insert(0, Node::Text(" "));
+    }
+    target.insert(0, checkbox);
+}
+
 impl<'a> IntoNode<'a> for Quote<'a> {
     fn into_node(self, state: &IrState<'a>) -> Node<'a> {
         Node::Element(elem!(
@@ -338,9 +439,14 @@ fn create_table_cell<'a>(
         }
     }
 
-    Node::Element(elem!(
-        <{name} {attrs}>{
-            into_nodes_trimmed(cell.segments, state)
-        } is_block_level: true, contains_blocks: false
-    ))
+    match cell.content {
+        CellContent::Inline(segments) => Node::Element(elem!(
+            <{name} {attrs}>{
+                into_nodes_trimmed(segments, state)
+            } is_block_level: true, contains_blocks: false
+        )),
+        CellContent::Blocks(blocks) => Node::Element(elem!(
+            <{name} {attrs}>{ blocks.into_nodes(state) } is_block_level: true, contains_blocks: true
+        )),
+    }
 }
@@ -12,21 +12,23 @@ impl<'a> IntoNode<'a> for HtmlNode<'a> {
             HtmlNode::CData(c) => Node::Cdata(c),
             HtmlNode::Comment(c) => Node::Comment(c),
             HtmlNode::Doctype(d) => Node::Doctype(d),
+            HtmlNode::Raw(r) => Node::Verbatim(r),
         }
     }
 }
 
 impl<'a> IntoNode<'a> for HtmlElem<'a> {
     fn into_node(self, state: &IrState<'a>) -> Node<'a> {
+        let is_block_level = self.is_block_level();
         let content = self.content.map(|c| elem_content_ir_into_nodes(c, state));
         let contains_blocks =
             content.as_ref().map(|c| c.iter().any(Node::is_block_level)).unwrap_or(false);
 
         let mut node = Node::Element(Element {
+            is_block_level,
             name: self.name,
             attrs: self.attrs,
             content,
-            is_block_level: self.name.is_block_level(),
             contains_blocks,
         });
         apply_post_annotations(self.macros, &mut node, state);
@@ -3,14 +3,14 @@ use std::mem::replace;
 
 use unidok_repr::ast::html::ElemName;
 use unidok_repr::ir::blocks::Heading;
-use unidok_repr::ir::macros::{Footnote, Macro};
+use unidok_repr::ir::macros::{Caption, Footnote, Macro};
 use unidok_repr::ir::{macros, IrState};
 use unidok_repr::try_reduce::{Reduced1, TryReduce};
 
 use crate::filter_for_toc::filter_for_toc;
 use crate::{Attr, Element, IntoNodes, Node};
 
-use super::segment::add_attributes;
+use super::segment::{add_attributes, footnote_ref_anchor};
 
 pub(crate) fn apply_post_annotations<'a>(
     macros: Vec<Macro<'a>>,
@@ -23,34 +23,86 @@ pub(crate) fn apply_post_annotations<'a>(
                 let taken = replace(node, Node::Text(""));
                 *node = add_attributes_to_node(taken, attrs);
             }
-            Macro::Toc => {
-                let first_is_level_1 = state.headings.first().into_iter().any(|h| h.level == 1);
-                let rem_has_level_1 = state.headings.iter().skip(1).any(|h| h.level == 1);
-
-                let level = if rem_has_level_1 { 1 } else { 2 };
-                let headings = if first_is_level_1 && !rem_has_level_1 {
-                    &state.headings[1..]
-                } else {
-                    &state.headings
-                };
-
-                let (content, _) = toc_list(level, headings, state);
+            Macro::Toc => *node = Node::Element(table_of_contents(state)),
+            Macro::MathScript if state.contains_math => *node = Node::Fragment(math_script()),
+            Macro::MathScriptInHead if state.contains_math && state.math_script_in_head => {
+                *node = Node::Fragment(math_script())
+            }
+            Macro::MathScript | Macro::MathScriptInHead => *node = Node::Fragment(vec![]),
+            Macro::Admonition(kind, title) => {
+                let content = replace(node, Node::Text(""));
+                let title = Node::Element(elem!(
+                    <P class="admonition-title">[Node::Text2(title)] contains_blocks: false, is_block_level: true
+                ));
+                *node = Node::Element(elem!(
+                    <Div class={format!("admonition {}", kind.class())} role="note">[title, content]
+                        contains_blocks: true, is_block_level: true
+                ));
+            }
+            Macro::Details { summary, open } => {
+                let content = replace(node, Node::Text(""));
+                let mut children = Vec::with_capacity(2);
+                if !summary.is_empty() {
+                    children.push(Node::Element(elem!(
+                        <Summary>{ summary.into_nodes(state) } contains_blocks: false, is_block_level: true
+                    )));
+                }
+                children.push(content);
 
-                let toc = elem!(<Ul class="table-of-contents"> { content }
-                    is_block_level: true, contains_blocks: true);
-                *node = Node::Element(toc);
+                let mut details = elem!(
+                    <Details>{ children } contains_blocks: true, is_block_level: true
+                );
+                if open {
+                    details.attrs.push(attr!(open));
+                }
+                *node = Node::Element(details);
             }
-            Macro::MathScript if state.contains_math => {
-                let s1 = elem!(<Script src="https://polyfill.io/v3/polyfill.min.js?features=es6">[]
-                    is_block_level: true, contains_blocks: false);
+            Macro::Caption(caption) => {
+                let id = caption.id();
+                let caption = caption_content(caption, state);
 
-                let s2 = elem!(<Script id="MathJax-script" async
-                    src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/mml-chtml.js">[]
-                    is_block_level: true, contains_blocks: false);
+                if let Node::Element(table @ Element { name: ElemName::Table, .. }) = node {
+                    table.attrs.push(attr!(id = id));
+                    let caption = Node::Element(elem!(
+                        <Caption>{ caption } contains_blocks: false, is_block_level: true
+                    ));
+                    table.content.get_or_insert_with(Vec::new).insert(0, caption);
+                } else {
+                    let content = unwrap_paragraph(replace(node, Node::Text("")));
+                    let caption = Node::Element(elem!(
+                        <Figcaption>{ caption } contains_blocks: false, is_block_level: true
+                    ));
+                    *node = Node::Element(elem!(
+                        <Figure id={id}>[content, caption] contains_blocks: true, is_block_level: true
+                    ));
+                }
+            }
+            Macro::Cols(columns) => {
+                if let Node::Element(table @ Element { name: ElemName::Table, .. }) = node {
+                    let cols = columns
+                        .into_iter()
+                        .map(|column| {
+                            let mut col =
+                                elem!(<Col /> contains_blocks: false, is_block_level: true);
+                            add_attributes(column.attrs, &mut col);
+                            Node::Element(col)
+                        })
+                        .collect();
+                    let colgroup = Node::Element(elem!(
+                        <Colgroup>{ cols } contains_blocks: true, is_block_level: true
+                    ));
 
-                *node = Node::Fragment(vec![Node::Element(s1), Node::Element(s2)]);
+                    // the `<colgroup>` must come after the `<caption>`
+                    let content = table.content.get_or_insert_with(Vec::new);
+                    let index = content
+                        .iter()
+                        .take_while(
+                            |n| matches!(n, Node::Element(e) if e.name == ElemName::Caption),
+                        )
+                        .count();
+                    content.insert(index, colgroup);
+                }
             }
-            Macro::MathScript => *node = Node::Fragment(vec![]),
             Macro::Blank => {
                 if let Node::Element(e @ Element { name: ElemName::A, .. }) = node {
                     e.attrs.push(attr!(target = "_blank"));
@@ -67,17 +119,31 @@ pub(crate) fn apply_post_annotations<'a>(
                         <Hr class="footnotes-line" /> contains_blocks: false, is_block_level: true
                     )));
                     for Footnote { num, text } in footnotes {
-                        children.push(Node::Element(elem!(
-                        <Div class="footnote-def">[
+                        let mut content = vec![
                             Node::Element(elem!(
-                                <A href={format!("#footnote-ref-{}", num)} id={num.to_string()}>[
+                                <A href={format!("#{}", footnote_ref_anchor(num, 1))} id={num.to_string()}>[
                                     Node::Text2(num.to_string())
                                 ] contains_blocks: false, is_block_level: false
                             )),
                             Node::Text(". "),
-                            Node::Fragment(text.into_nodes(state))
-                        ] contains_blocks: false, is_block_level: true
-                    )));
+                            Node::Fragment(text.into_nodes(state)),
+                        ];
+                        // back-links to every reference of a footnote referenced more than once
+                        let refs = state.footnote_refs.get(&num).copied().unwrap_or(1);
+                        if refs > 1 {
+                            for index in 1..=refs {
+                                content.push(Node::Text(" "));
+                                content.push(Node::Element(elem!(
+                                    <A href={format!("#{}", footnote_ref_anchor(num, index))} class="footnote-backref">[
+                                        Node::Text2(format!("↩{}", index))
+                                    ] contains_blocks: false, is_block_level: false
+                                )));
+                            }
+                        }
+
+                        children.push(Node::Element(elem!(
+                            <Div class="footnote-def">{ content } contains_blocks: false, is_block_level: true
+                        )));
                     }
 
                     *node = Node::Element(elem!(
@@ -90,6 +156,30 @@ pub(crate) fn apply_post_annotations<'a>(
     }
 }
 
+pub(crate) fn table_of_contents<'a>(state: &IrState<'a>) -> Element<'a> {
+    let first_is_level_1 = state.headings.first().into_iter().any(|h| h.level == 1);
+    let rem_has_level_1 = state.headings.iter().skip(1).any(|h| h.level == 1);
+
+    let level = if rem_has_level_1 { 1 } else { 2 };
+    let headings =
+        if first_is_level_1 && !rem_has_level_1 { &state.headings[1..] } else { &state.headings };
+
+    let (content, _) = toc_list(level, headings, state);
+
+    elem!(<Ul class="table-of-contents"> { content } is_block_level: true, contains_blocks: true)
+}
+
+pub(crate) fn math_script<'a>() -> Vec<Node<'a>> {
+    let s1 = elem!(<Script src="https://polyfill.io/v3/polyfill.min.js?features=es6">[]
+        is_block_level: true, contains_blocks: false);
+
+    let s2 = elem!(<Script id="MathJax-script" async
+        src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/mml-chtml.js">[]
+        is_block_level: true, contains_blocks: false);
+
+    vec![Node::Element(s1), Node::Element(s2)]
+}
+
 fn toc_list<'a>(
     level: u8,
     headings: &'_ [Heading<'a>],
@@ -126,20 +216,19 @@ fn toc_list<'a>(
                 let (n, new_i) = toc_list(level + 1, &headings[i..], state);
                 i += new_i;
 
-                if result.is_empty() {
-                    let li = Element {
-                        name: ElemName::Li,
-                        attrs: vec![],
-                        content: Some(vec![]),
-                        is_block_level: true,
-                        contains_blocks: true,
-                    };
-                    result.push(Node::Element(li));
-                }
-
-                let last = result.last_mut().unwrap();
+                let sub_list = Node::Element(Element {
+                    name: ElemName::Ul,
+                    attrs: vec![],
+                    content: Some(n),
+                    is_block_level: true,
+                    contains_blocks: true,
+                });
 
-                if let Node::Element(Element { content: Some(content), contains_blocks, .. }) = last
+                if let Some(Node::Element(Element {
+                    content: Some(content),
+                    contains_blocks,
+                    ..
+                })) = result.last_mut()
                 {
                     *contains_blocks = true;
                     for n in &mut *content {
@@ -147,15 +236,16 @@ fn toc_list<'a>(
                             e.is_block_level = true;
                         }
                     }
-                    content.push(Node::Element(Element {
-                        name: ElemName::Ul,
+                    content.push(sub_list);
+                } else {
+                    let li = Element {
+                        name: ElemName::Li,
                         attrs: vec![],
-                        content: Some(n),
+                        content: Some(vec![sub_list]),
                         is_block_level: true,
                         contains_blocks: true,
-                    }));
-                } else {
-                    unreachable!("Last node in the TOC list is not a proper element");
+                    };
+                    result.push(Node::Element(li));
                 }
             }
             Ordering::Less => {
@@ -167,6 +257,40 @@ fn toc_list<'a>(
     (result, i)
 }
 
+/// Returns the number and the text of a figure caption, e.g. `Figure 3: Text`
+fn caption_content<'a>(caption: Caption<'a>, state: &IrState<'a>) -> Vec<Node<'a>> {
+    let number = format!("{} {}", caption.label, caption.number);
+    if caption.text.is_empty() {
+        vec![Node::Element(elem!(
+            <Span class="caption-number">[Node::Text2(number)] contains_blocks: false, is_block_level: false
+        ))]
+    } else {
+        vec![
+            Node::Element(elem!(
+                <Span class="caption-number">[Node::Text2(format!("{}:", number))]
+                    contains_blocks: false, is_block_level: false
+            )),
+            Node::Text(" "),
+            Node::Fragment(caption.text.into_nodes(state)),
+        ]
+    }
+}
+
+/// Removes the paragraph around a single inline element, e.g. an image, so it
+/// can be placed in a `<figure>` directly
+fn unwrap_paragraph(node: Node<'_>) -> Node<'_> {
+    match node {
+        Node::Element(Element { name: ElemName::P, attrs, content: Some(mut content), .. })
+            if attrs.is_empty()
+                && content.len() == 1
+                && matches!(content[0], Node::Element(Element { is_block_level: false, .. })) =>
+        {
+            content.pop().unwrap()
+        }
+        node => node,
+    }
+}
+
 fn add_attributes_to_node<'a>(node: Node<'a>, args: Vec<macros::Attr<'a>>) -> Node<'a> {
     if let Node::Element(mut elem) = node {
         add_attributes(args, &mut elem);
@@ -186,7 +310,19 @@ fn add_attributes_to_node<'a>(node: Node<'a>, args: Vec<macros::Attr<'a>>) -> No
         };
         add_attributes(args, &mut elem);
         Node::Element(elem)
+    } else if let Node::Text(_) | Node::Text2(_) | Node::Entity(_) | Node::Verbatim(_) = node {
+        let mut elem = Element {
+            name: ElemName::Span,
+            attrs: vec![],
+            content: Some(vec![node]),
+            is_block_level: false,
+            contains_blocks: false,
+        };
+        add_attributes(args, &mut elem);
+        Node::Element(elem)
     } else {
-        panic!("Empty macro can't be applied to this kind of node");
+        // comments, CDATA sections and doctypes can't have attributes; this is
+        // reported when the document is converted to the IR
+        node
     }
 }
@@ -6,6 +6,8 @@ mod html;
 mod macros;
 mod segment;
 
+pub(crate) use self::macros::{math_script, table_of_contents};
+
 pub trait IntoNode<'a> {
     fn into_node(self, state: &IrState<'a>) -> crate::Node<'a>;
 }
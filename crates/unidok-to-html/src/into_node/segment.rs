@@ -18,6 +18,7 @@ impl<'a> IntoNode<'a> for Segment<'a> {
             Segment::LineBreak => Node::Text("\n"),
             Segment::Limiter => Node::Fragment(vec![]),
             Segment::HtmlEntity(e) => Node::Entity(e.0),
+            Segment::CharRef(c) => Node::Text2(c.to_string()),
             Segment::Braces(b) => b.into_node(state),
             Segment::Link(l) => l.into_node(state),
             Segment::Image(i) => i.into_node(state),
@@ -80,8 +81,8 @@ impl<'a> IntoNode<'a> for Link<'a> {
                 if let Some(title) = self.title {
                     attrs.push(attr!(title = title));
                 }
-                if let Some(n) = self.footnote {
-                    attrs.push(attr!(name = format!("footnote-ref-{}", n)));
+                if let Some(f) = self.footnote {
+                    attrs.push(attr!(name = footnote_ref_anchor(f.num, f.index)));
                     attrs.push(attr!(class = "footnote"));
                 }
 
@@ -197,10 +198,27 @@ fn add_attribute_kv<'a>(
 
 impl<'a> IntoNode<'a> for Math<'a> {
     fn into_node(self, _: &IrState) -> Node<'a> {
-        let formatted = asciimath_rs::parse(self.text).to_mathml();
+        // asciimath-rs recurses infinitely when the formula starts with a
+        // backslash; leading whitespace is ignored, so this avoids it
+        let formatted = if self.text.starts_with('\\') {
+            asciimath_rs::parse(format!(" {}", self.text)).to_mathml()
+        } else {
+            asciimath_rs::parse(self.text).to_mathml()
+        };
 
         Node::Element(elem!(
             <Math>[Node::Verbatim(formatted)] is_block_level: false, contains_blocks: true
         ))
     }
 }
+
+/// The anchor of a footnote reference. The first reference of a footnote has
+/// the anchor `footnote-ref-{num}`, further references have the anchor
+/// `footnote-ref-{num}-{index}`.
+pub(crate) fn footnote_ref_anchor(num: u32, index: u32) -> String {
+    if index == 1 {
+        format!("footnote-ref-{}", num)
+    } else {
+        format!("footnote-ref-{}-{}", num, index)
+    }
+}
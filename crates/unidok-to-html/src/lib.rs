@@ -2,15 +2,20 @@
 mod util;
 
 mod filter_for_toc;
+mod highlight;
 mod into_node;
 mod nice_debug;
+mod template;
 mod to_html;
 
 use unidok_parser::Doc;
 use unidok_repr::ast::html::ElemName;
 use unidok_repr::ir::html::Attr;
 
+#[cfg(feature = "syntect")]
+pub use crate::highlight::{highlight_css, highlight_themes};
 pub use crate::into_node::{IntoNode, IntoNodes};
+pub use crate::template::{PageData, Template, TemplateError};
 pub use crate::to_html::ToHtml;
 
 pub fn convert(ir: Doc<'_>) -> Vec<Node<'_>> {
@@ -46,8 +51,8 @@ impl Node<'_> {
 
     pub fn is_whitespace(&self) -> bool {
         match self {
-            &Node::Text(t) => t.trim_start_matches(|c| matches!(c, ' ' | '\t' | '\n')).is_empty(),
-            Node::Text2(t) => t.trim_start_matches(|c| matches!(c, ' ' | '\t' | '\n')).is_empty(),
+            &Node::Text(t) => t.trim_start_matches([' ', '\t', '\n']).is_empty(),
+            Node::Text2(t) => t.trim_start_matches([' ', '\t', '\n']).is_empty(),
             _ => false,
         }
     }
@@ -65,3 +70,60 @@ pub struct Element<'a> {
     pub is_block_level: bool,
     pub contains_blocks: bool,
 }
+
+#[test]
+fn test_safe_mode() {
+    use unidok_parser::Input;
+    use unidok_repr::config::Config;
+    use unidok_repr::diagnostics::DiagnosticCode;
+    use unidok_repr::sanitizer::Sanitizer;
+
+    let config = Config { sanitizer: Some(Sanitizer::default()), ..Config::default() };
+    let mut input = Input::new(
+        "<script>alert(1)</script>\n\n<b onclick=\"x\" class=\"y\">a</b> [b](javascript:x)\n\n\
+         @(.z onmouseover=\"x\")\n<a href=\" JavaScript:x\" title=\"t\">c</a>",
+    );
+    let doc = unidok_parser::parse(&mut input, config);
+    let codes: Vec<_> = doc.diagnostics.iter().map(|d| d.code).collect();
+    let html = to_string(&convert(doc));
+
+    assert_eq!(html, "<p><b class=\"y\">a</b> b</p>\n<p class=\"z\"><a title=\"t\">c</a></p>\n");
+    assert_eq!(
+        codes,
+        [
+            DiagnosticCode::DisallowedHtml,
+            DiagnosticCode::DisallowedHtml,
+            DiagnosticCode::DisallowedUrl,
+            DiagnosticCode::DisallowedHtml,
+            DiagnosticCode::DisallowedUrl,
+        ]
+    );
+}
+
+#[test]
+fn test_safe_mode_allowlist() {
+    use unidok_parser::Input;
+    use unidok_repr::config::Config;
+    use unidok_repr::sanitizer::Sanitizer;
+
+    let text = "@COLS(width=20%)\n|| a\n\n<svg><circle/></svg>\n\n<x-y>b</x-y>";
+    let convert_with = |sanitizer| {
+        let config = Config { sanitizer: Some(sanitizer), ..Config::default() };
+        let mut input = Input::new(text);
+        let doc = unidok_parser::parse(&mut input, config);
+        let warnings = doc.diagnostics.len();
+        (to_string(&convert(doc)), warnings)
+    };
+    let table = "<table>\n<colgroup>\n<col style=\"width: 20%\"/>\n</colgroup>\n\
+                 <tr>\n<td>a</td>\n</tr>\n</table>\n";
+
+    let (html, warnings) = convert_with(Sanitizer::default());
+    assert_eq!(html, table);
+    assert_eq!(warnings, 2);
+
+    let mut sanitizer = Sanitizer::default();
+    sanitizer.elements.extend(["svg", "circle", "x-*"].iter().map(|s| s.to_string()));
+    let (html, warnings) = convert_with(sanitizer);
+    assert_eq!(html, format!("{}<svg><circle/></svg>\n<p><x-y>b</x-y></p>\n", table));
+    assert_eq!(warnings, 0);
+}
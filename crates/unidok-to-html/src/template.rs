@@ -0,0 +1,214 @@
+use std::fmt;
+
+use unidok_parser::Doc;
+use unidok_repr::metadata::{MetaValue, Metadata};
+
+use crate::into_node::{math_script, table_of_contents};
+use crate::{Node, ToHtml};
+
+const DEFAULT_TEMPLATE: &str = include_str!("default_template.html");
+
+/// A template for a complete HTML page. It contains placeholders such as
+/// `{{ content }}`, which are replaced when the template is rendered:
+///
+/// - `content`: The converted document
+/// - `title`: The `title` metadata
+/// - `lang`: The `lang` metadata, or `en`
+/// - `stylesheet`: The `stylesheet` metadata, or `style.css`
+/// - `toc`: The table of contents
+/// - `head`: Elements that belong in the `<head>`, e.g. the math script if
+///   `@MATH_SCRIPT(head)` is used
+/// - `meta.<key>`: Any metadata value. Lists are separated by commas
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Content,
+    Title,
+    Lang,
+    Stylesheet,
+    Toc,
+    Head,
+    Meta(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+    pub message: String,
+    /// The byte offset of the error in the template
+    pub offset: usize,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+        let mut offset = 0;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let error = |message: String| TemplateError { message, offset: offset + start };
+
+            let placeholder = &rest[start + 2..];
+            let end = placeholder.find("}}").ok_or_else(|| error("unclosed placeholder".into()))?;
+
+            let part = match placeholder[..end].trim() {
+                "content" => Part::Content,
+                "title" => Part::Title,
+                "lang" => Part::Lang,
+                "stylesheet" => Part::Stylesheet,
+                "toc" => Part::Toc,
+                "head" => Part::Head,
+                name => match name.strip_prefix("meta.") {
+                    Some(key) if !key.is_empty() => Part::Meta(key.to_string()),
+                    _ => return Err(error(format!("unknown placeholder `{}`", name))),
+                },
+            };
+            parts.push(part);
+
+            let len = start + 2 + end + 2;
+            rest = &rest[len..];
+            offset += len;
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Template { parts })
+    }
+
+    pub fn render(&self, content: &[Node<'_>], page: &PageData<'_>) -> String {
+        let mut buf = String::new();
+        let metadata = &page.metadata;
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => buf.push_str(text),
+                Part::Content => content.to_html(&mut buf, false),
+                Part::Title => push_text(metadata.title().unwrap_or_default(), &mut buf),
+                Part::Lang => push_text(metadata.get_str("lang").unwrap_or("en"), &mut buf),
+                Part::Stylesheet => {
+                    push_text(metadata.get_str("stylesheet").unwrap_or("style.css"), &mut buf)
+                }
+                Part::Toc => page.toc.to_html(&mut buf, false),
+                Part::Head => page.head.to_html(&mut buf, false),
+                Part::Meta(key) => {
+                    if let Some(value) = metadata.get(key) {
+                        push_text(&meta_to_string(value), &mut buf);
+                    }
+                }
+            }
+        }
+        buf
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template::parse(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+/// The information about a document that is needed to render a template,
+/// besides its content. It must be created before the document is converted.
+///
+/// The math script requested with `@MATH_SCRIPT(head)` is moved to the page
+/// data, so it isn't rendered in the content. If no page data is created,
+/// the script is rendered where the macro appears.
+pub struct PageData<'a> {
+    metadata: Metadata,
+    toc: Vec<Node<'a>>,
+    head: Vec<Node<'a>>,
+}
+
+impl<'a> PageData<'a> {
+    pub fn new(doc: &mut Doc<'a>) -> Self {
+        let state = &mut doc.state;
+
+        let toc = if state.headings.is_empty() {
+            vec![]
+        } else {
+            vec![Node::Element(table_of_contents(state))]
+        };
+        let head =
+            if state.math_script_in_head && state.contains_math { math_script() } else { vec![] };
+        state.math_script_in_head = false;
+
+        PageData { metadata: state.metadata.clone(), toc, head }
+    }
+}
+
+fn push_text(text: &str, buf: &mut String) {
+    Node::Text(text).to_html(buf, true);
+}
+
+fn meta_to_string(value: &MetaValue) -> String {
+    match value {
+        MetaValue::String(s) => s.clone(),
+        MetaValue::Bool(b) => b.to_string(),
+        MetaValue::Int(n) => n.to_string(),
+        MetaValue::Float(n) => n.to_string(),
+        MetaValue::List(l) => l.iter().map(meta_to_string).collect::<Vec<_>>().join(", "),
+    }
+}
+
+#[test]
+fn test_template() {
+    use unidok_parser::Input;
+    use unidok_repr::config::Config;
+
+    let template = Template::parse(
+        "<title>{{title}}</title>{{ head }}\n<nav>{{ toc }}</nav>\n\
+         <main>{{ content }}</main><p>{{ meta.tags }}</p>",
+    )
+    .unwrap();
+
+    let mut input =
+        Input::new("---\ntitle: A & B\ntags: [x, y]\n---\n@MATH_SCRIPT(head)\n\n## Foo");
+    let mut doc = unidok_parser::parse(&mut input, Config::default());
+    let page = PageData::new(&mut doc);
+    let nodes = crate::convert(doc);
+
+    assert_eq!(
+        template.render(&nodes, &page),
+        "<title>A &amp; B</title>\n\
+         <nav><ul class=\"table-of-contents\">\n<li><a href=\"#foo\">Foo</a></li>\n</ul>\n</nav>\n\
+         <main><h2 id=\"foo\">Foo</h2>\n</main><p>x, y</p>"
+    );
+
+    let template = Template::parse("<head>{{ head }}</head><body>{{ content }}</body>").unwrap();
+    let mut input = Input::new("@MATH_SCRIPT(head)\n\n%{x^2}");
+    let mut doc = unidok_parser::parse(&mut input, Config::default());
+    let page = PageData::new(&mut doc);
+    let nodes = crate::convert(doc);
+
+    let html = template.render(&nodes, &page);
+    let body_start = html.find("<body>").unwrap();
+    assert!(html.find("MathJax-script").unwrap() < body_start);
+    assert_eq!(html.matches("MathJax-script").count(), 1);
+
+    // without a page, the script is rendered where the macro appears
+    let mut input = Input::new("@MATH_SCRIPT(head)\n\n%{x^2}");
+    let doc = unidok_parser::parse(&mut input, Config::default());
+    let html = crate::to_string(&crate::convert(doc));
+    assert_eq!(html.matches("MathJax-script").count(), 1);
+
+    let error = Template::parse("{{ content }} {{ foo }}").unwrap_err();
+    assert_eq!(error.to_string(), "unknown placeholder `foo` at byte 14");
+    assert!(Template::parse("{{ content").is_err());
+    assert!(Template::parse(DEFAULT_TEMPLATE).is_ok());
+}
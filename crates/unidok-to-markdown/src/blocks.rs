@@ -0,0 +1,425 @@
+use unidok_repr::ast::blocks::{Bullet, CellAlignment, ThematicBreakKind};
+use unidok_repr::ir::blocks::*;
+use unidok_repr::ir::html::HtmlNode;
+use unidok_repr::ir::macros::Macro;
+use unidok_repr::ir::segments::Segment;
+use unidok_repr::ir::IrState;
+
+use crate::segments::{segments_to_markdown, Context};
+use crate::to_html;
+
+/// A rendered block. The kind is needed to decide how blocks are separated.
+struct Rendered {
+    text: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Paragraph,
+    Heading,
+    CodeBlock,
+    ThematicBreak,
+    /// Contains the bullet char (`-`, `+`, `*`), or `.`/`)` for ordered lists,
+    /// and whether it can interrupt a paragraph
+    List(char, bool),
+    Quote,
+    Html,
+}
+
+impl Kind {
+    fn can_interrupt_paragraph(self) -> bool {
+        match self {
+            Kind::Heading | Kind::CodeBlock | Kind::Quote => true,
+            Kind::List(_, interrupts) => interrupts,
+            Kind::Paragraph | Kind::ThematicBreak | Kind::Html => false,
+        }
+    }
+
+    /// Whether a block can directly follow this block in the next line
+    /// without becoming part of it
+    fn is_closed(self) -> bool {
+        matches!(self, Kind::Heading | Kind::CodeBlock | Kind::ThematicBreak)
+    }
+}
+
+/// Converts blocks to Markdown. In a tight list item, consecutive paragraphs
+/// are joined with hard line breaks, like in the HTML output, and blocks
+/// aren't separated by blank lines where possible.
+pub(crate) fn blocks_to_markdown<'a>(
+    blocks: Vec<AnnBlock<'a>>,
+    state: &IrState<'a>,
+    tight: bool,
+) -> String {
+    let mut rendered = Vec::new();
+    for block in blocks {
+        render_ann_block(block, state, &mut rendered);
+    }
+
+    let mut buf = String::new();
+    let mut prev: Option<Kind> = None;
+
+    for Rendered { mut text, kind } in rendered {
+        if let Some(prev_kind) = prev {
+            match (prev_kind, kind) {
+                (Kind::Paragraph, Kind::Paragraph) if tight => buf.push_str("\\\n"),
+                (Kind::List(prev_c, _), Kind::List(c, _)) if prev_c == c => {
+                    // adjacent lists of the same kind must use different bullets
+                    let new_c = alternate_bullet(c);
+                    text = replace_bullets(&text, c, new_c);
+                    buf.push_str("\n\n");
+                    prev = Some(Kind::List(new_c, false));
+                    buf.push_str(&text);
+                    continue;
+                }
+                (Kind::Quote, Kind::Quote) => buf.push_str("\n\n"),
+                (Kind::Paragraph | Kind::Quote | Kind::List(..), kind)
+                    if tight && kind.can_interrupt_paragraph() =>
+                {
+                    buf.push('\n')
+                }
+                (prev_kind, _) if tight && prev_kind.is_closed() => buf.push('\n'),
+                _ => buf.push_str("\n\n"),
+            }
+        }
+        buf.push_str(&text);
+        prev = Some(kind);
+    }
+    buf
+}
+
+fn alternate_bullet(c: char) -> char {
+    match c {
+        '-' => '*',
+        '.' => ')',
+        ')' => '.',
+        _ => '-',
+    }
+}
+
+/// Replaces the bullets of the top-level list items. Only the first line and
+/// lines that aren't indented can start a list item.
+fn replace_bullets(text: &str, old: char, new: char) -> String {
+    let mut buf = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+        if matches!(old, '.' | ')') && digits > 0 && line[digits..].starts_with(old) {
+            buf.push_str(&line[..digits]);
+            buf.push(new);
+            buf.push_str(&line[digits + 1..]);
+        } else if !matches!(old, '.' | ')') && line.starts_with(old) {
+            buf.push(new);
+            buf.push_str(&line[1..]);
+        } else {
+            buf.push_str(line);
+        }
+    }
+    buf
+}
+
+fn render_ann_block<'a>(block: AnnBlock<'a>, state: &IrState<'a>, out: &mut Vec<Rendered>) {
+    if block.macros.iter().any(needs_html) {
+        push_html(to_html(block, state), out);
+    } else {
+        render_block(block.block, state, out);
+    }
+}
+
+/// Returns whether a macro changes the HTML output, so the annotated element
+/// must be rendered as HTML
+pub(crate) fn needs_html(m: &Macro<'_>) -> bool {
+    match m {
+        Macro::HtmlAttrs(_)
+        | Macro::ListStyle(_)
+        | Macro::Toc
+        | Macro::Footnotes(_)
+        | Macro::Blank
+        | Macro::Admonition(..)
+        | Macro::Details { .. }
+        | Macro::Caption(_)
+        | Macro::Cols(_) => true,
+        Macro::Loose
+        | Macro::NoToc
+        | Macro::NoText
+        | Macro::MathScript
+        | Macro::MathScriptInHead
+        | Macro::Config
+        | Macro::Meta
+        | Macro::Include(_)
+        | Macro::Invalid => false,
+    }
+}
+
+fn push_html(text: String, out: &mut Vec<Rendered>) {
+    if !text.is_empty() {
+        out.push(Rendered { text, kind: Kind::Html });
+    }
+}
+
+fn render_block<'a>(block: Block<'a>, state: &IrState<'a>, out: &mut Vec<Rendered>) {
+    match block {
+        Block::CodeBlock(c) => match code_block(&c) {
+            Some(text) => out.push(Rendered { text, kind: Kind::CodeBlock }),
+            None => push_html(to_html(Block::CodeBlock(c), state), out),
+        },
+        Block::Paragraph(p) => {
+            if p.segments.iter().any(is_block_html) {
+                push_html(to_html(p, state), out);
+            } else {
+                let text = segments_to_markdown(p.segments, state, Context::Paragraph);
+                let text = text.trim_matches(|c| matches!(c, ' ' | '\t' | '\n'));
+                if !text.is_empty() {
+                    out.push(Rendered { text: text.to_string(), kind: Kind::Paragraph });
+                }
+            }
+        }
+        Block::Heading(h) => {
+            let text = segments_to_markdown(h.segments, state, Context::Heading);
+            let text = text.trim();
+            let mut buf = "#".repeat(h.level as usize);
+            if !text.is_empty() {
+                buf.push(' ');
+                buf.push_str(text);
+            }
+            out.push(Rendered { text: buf, kind: Kind::Heading });
+        }
+        Block::Table(t) => {
+            if is_gfm_table(&t) {
+                out.push(Rendered { text: table(t, state), kind: Kind::Html });
+            } else {
+                push_html(to_html(Block::Table(t), state), out);
+            }
+        }
+        Block::ThematicBreak(t) => {
+            let text = match t.kind {
+                ThematicBreakKind::Dashes => "---",
+                ThematicBreakKind::Stars => "***",
+                ThematicBreakKind::Underscores => "___",
+            };
+            out.push(Rendered { text: text.to_string(), kind: Kind::ThematicBreak });
+        }
+        Block::List(l) => {
+            if l.macros.iter().any(needs_html) {
+                push_html(to_html(Block::List(l), state), out);
+            } else {
+                out.push(list(l, state));
+            }
+        }
+        // Markdown has no description lists
+        Block::LabeledList(l) => push_html(to_html(Block::LabeledList(l), state), out),
+        Block::Quote(q) => {
+            let content = blocks_to_markdown(q.content, state, false);
+            out.push(Rendered { text: prefix_lines(&content, ">"), kind: Kind::Quote });
+        }
+        Block::BlockHtml(h) => push_html(to_html(h, state), out),
+        Block::Braces(blocks) => {
+            for block in blocks {
+                render_ann_block(block, state, out);
+            }
+        }
+        Block::Empty => {}
+    }
+}
+
+/// Returns whether a segment is rendered as a separate block in HTML, so the
+/// paragraph can't be expressed in Markdown
+fn is_block_html(segment: &Segment<'_>) -> bool {
+    match segment {
+        Segment::InlineHtml(HtmlNode::Element(e)) => e.is_block_level(),
+        Segment::InlineHtml(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns the Markdown for a code block, if it only contains plain text
+fn code_block(c: &CodeBlock<'_>) -> Option<String> {
+    let mut lines = Vec::with_capacity(c.lines.len());
+    for line in &c.lines {
+        let mut text = String::new();
+        if let Block::Paragraph(p) = line {
+            for segment in &p.segments {
+                match segment {
+                    Segment::Text(t) | Segment::EscapedText(t) => text.push_str(t),
+                    Segment::Text2(t) => text.push_str(t),
+                    _ => return None,
+                }
+            }
+        } else {
+            return None;
+        }
+        lines.push(text);
+    }
+
+    let mut info = String::new();
+    for c in c.info.trim().chars() {
+        if matches!(c, '\\' | '&') {
+            info.push('\\');
+        }
+        info.push(c);
+    }
+    let fence_char = if info.contains('`') { '~' } else { '`' };
+    let longest_run = lines.iter().map(|l| longest_run(l, fence_char)).max().unwrap_or(0);
+    let fence = fence_char.to_string().repeat(longest_run.max(2) + 1);
+
+    let mut buf = format!("{}{}\n", fence, info);
+    for line in lines {
+        buf.push_str(&line);
+        buf.push('\n');
+    }
+    buf.push_str(&fence);
+    Some(buf)
+}
+
+pub(crate) fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn list<'a>(l: List<'a>, state: &IrState<'a>) -> Rendered {
+    let loose = l.macros.contains(&Macro::Loose);
+    let (bullet_char, interrupts) = match l.bullet {
+        Bullet::Dash => ('-', true),
+        Bullet::Plus => ('+', true),
+        Bullet::Star => ('*', true),
+        // only ordered lists starting with 1 can interrupt a paragraph
+        Bullet::Dot { start } => ('.', start == 1),
+        Bullet::Paren { start } => (')', start == 1),
+    };
+
+    let mut buf = String::new();
+    for (i, (item, checked)) in l.items.into_iter().zip(l.checked).enumerate() {
+        if i > 0 {
+            buf.push_str(if loose { "\n\n" } else { "\n" });
+        }
+
+        let marker = match l.bullet {
+            Bullet::Dot { start } | Bullet::Paren { start } => {
+                format!("{}{}", start as usize + i, bullet_char)
+            }
+            _ => bullet_char.to_string(),
+        };
+        let mut content = blocks_to_markdown(item, state, !loose);
+        if let Some(checked) = checked {
+            let checkbox = if checked { "[x]" } else { "[ ]" };
+            content = format!("{} {}", checkbox, content).trim_end_matches(' ').to_string();
+        }
+        let indent = " ".repeat(marker.len() + 1);
+
+        buf.push_str(&marker);
+        let mut lines = content.split('\n');
+        if let Some(first) = lines.next().filter(|line| !line.is_empty()) {
+            // e.g. `- - -` is a thematic break, not a nested list
+            if is_thematic_break(&format!("{} {}", marker, first)) {
+                buf.push('\n');
+                buf.push_str(&indent);
+            } else {
+                buf.push(' ');
+            }
+            buf.push_str(first);
+        }
+        for line in lines {
+            buf.push('\n');
+            if !line.is_empty() {
+                buf.push_str(&indent);
+                buf.push_str(line);
+            }
+        }
+    }
+
+    Rendered { text: buf, kind: Kind::List(bullet_char, interrupts) }
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    ['-', '*', '_']
+        .iter()
+        .any(|&c| line.chars().all(|ch| ch == c || ch == ' ') && line.matches(c).count() >= 3)
+}
+
+fn prefix_lines(text: &str, prefix: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            buf.push('\n');
+        }
+        buf.push_str(prefix);
+        if !line.is_empty() {
+            buf.push(' ');
+            buf.push_str(line);
+        }
+    }
+    buf
+}
+
+/// Returns whether a table can be expressed as a GFM table: The first row must
+/// be the only header row, all rows must have the same number of cells, no
+/// cell may span multiple rows or columns or contain blocks, and all cells in a
+/// column must have the same alignment.
+fn is_gfm_table(t: &Table<'_>) -> bool {
+    let first = match t.rows.first() {
+        Some(first) => first,
+        None => return false,
+    };
+    let columns = first.cells.len();
+    if columns == 0 {
+        return false;
+    }
+
+    t.rows.iter().enumerate().all(|(i, row)| {
+        row.cells.len() == columns && row.cells.iter().enumerate().all(|(j, cell)| {
+            let is_header = row.is_header_row || cell.meta.is_header_cell;
+            is_header == (i == 0)
+                && cell.meta.rowspan == 1
+                && cell.meta.colspan == 1
+                && cell.meta.vertical_alignment == CellAlignment::Unset
+                && cell.meta.alignment == first.cells[j].meta.alignment
+                && matches!(&cell.content, CellContent::Inline(s) if !s.iter().any(is_block_html))
+        })
+    })
+}
+
+fn table<'a>(t: Table<'a>, state: &IrState<'a>) -> String {
+    let mut buf = String::new();
+    let alignments: Vec<_> = t.rows[0].cells.iter().map(|c| c.meta.alignment).collect();
+
+    for (i, row) in t.rows.into_iter().enumerate() {
+        buf.push('|');
+        for cell in row.cells {
+            let segments = match cell.content {
+                CellContent::Inline(segments) => segments,
+                CellContent::Blocks(_) => unreachable!("Block content in GFM table"),
+            };
+            let text = segments_to_markdown(segments, state, Context::Table);
+            buf.push(' ');
+            buf.push_str(text.trim());
+            buf.push_str(" |");
+        }
+        buf.push('\n');
+        if i == 0 {
+            push_delimiter_row(&alignments, &mut buf);
+        }
+    }
+    buf.pop();
+    buf
+}
+
+fn push_delimiter_row(alignments: &[CellAlignment], buf: &mut String) {
+    buf.push('|');
+    for alignment in alignments {
+        buf.push_str(match alignment {
+            CellAlignment::Unset => " --- |",
+            CellAlignment::LeftTop => " :-- |",
+            CellAlignment::RightBottom => " --: |",
+            CellAlignment::Center => " :-: |",
+        });
+    }
+    buf.push('\n');
+}
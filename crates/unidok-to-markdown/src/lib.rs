@@ -0,0 +1,51 @@
+//! Converts a parsed Unidok document to CommonMark, with the GFM extensions
+//! for tables and strikethrough.
+//!
+//! Constructs that can't be expressed in Markdown (e.g. table cells spanning
+//! multiple rows or columns, `@()` attributes, superscript and subscript) are
+//! emitted as inline HTML, using the same output as `unidok-to-html`.
+
+mod blocks;
+mod segments;
+
+use unidok_parser::Doc;
+use unidok_repr::ir::IrState;
+use unidok_to_html::{IntoNode, ToHtml};
+
+pub fn convert(ir: Doc<'_>) -> String {
+    let mut buf = blocks::blocks_to_markdown(ir.blocks, &ir.state, false);
+    if !buf.is_empty() {
+        buf.push('\n');
+    }
+    buf
+}
+
+/// Renders something that has no Markdown equivalent as HTML
+fn to_html<'a>(value: impl IntoNode<'a>, state: &IrState<'a>) -> String {
+    let mut buf = String::new();
+    value.into_node(state).to_html(&mut buf, false);
+    let len = buf.trim_end_matches('\n').len();
+    buf.truncate(len);
+    buf
+}
+
+#[test]
+fn test_convert() {
+    use unidok_parser::Input;
+    use unidok_repr::config::Config;
+
+    fn md(text: &str) -> String {
+        let mut input = Input::new(text);
+        convert(unidok_parser::parse(&mut input, Config::default()))
+    }
+
+    assert_eq!(md("# Hello *world*"), "# Hello *world*\n");
+    assert_eq!(md("- a\n- b\n\n1. c\n2. d"), "- a\n- b\n\n1. c\n2. d\n");
+    assert_eq!(md("> quote\n>\n> more"), "> quote\n>\n> more\n");
+    assert_eq!(md("```rust\nfn x() {}\n```"), "```rust\nfn x() {}\n```\n");
+    assert_eq!(md("1 * 2 = [3]"), "1 \\* 2 = \\[3\\]\n");
+    assert_eq!(md("x^2^ and #y#"), "x<sup>2</sup> and <sub>y</sub>\n");
+    assert_eq!(md("@(class=\"x\")\nText"), "<p class=\"x\">Text</p>\n");
+    assert_eq!(md("#|| a | b\n || c | d"), "| a | b |\n| --- | --- |\n| c | d |\n");
+    assert_eq!(md("- [x] a\n- [ ] b\n- c"), "- [x] a\n- [ ] b\n- c\n");
+}
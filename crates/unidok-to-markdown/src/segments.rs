@@ -0,0 +1,293 @@
+use unidok_repr::ast::segments::Formatting;
+use unidok_repr::ir::segments::*;
+use unidok_repr::ir::IrState;
+
+use crate::blocks::{longest_run, needs_html};
+use crate::to_html;
+
+/// Where inline content appears. Headings and table cells can't contain line
+/// breaks, and table cells must escape `|`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Context {
+    Paragraph,
+    Heading,
+    Table,
+}
+
+pub(crate) fn segments_to_markdown<'a>(
+    segments: Vec<Segment<'a>>,
+    state: &IrState<'a>,
+    context: Context,
+) -> String {
+    let mut writer = Writer { buf: String::new(), state, context };
+    writer.segments(segments);
+    writer.buf
+}
+
+struct Writer<'s, 'a> {
+    buf: String,
+    state: &'s IrState<'a>,
+    context: Context,
+}
+
+impl<'a> Writer<'_, 'a> {
+    fn segments(&mut self, segments: Vec<Segment<'a>>) {
+        for segment in segments {
+            self.segment(segment);
+        }
+    }
+
+    fn segment(&mut self, segment: Segment<'a>) {
+        match segment {
+            Segment::LineBreak => self.line_break(),
+            Segment::Text(t) | Segment::EscapedText(t) => self.text(t),
+            Segment::Text2(t) => self.text(&t),
+            Segment::Limiter => {}
+            Segment::HtmlEntity(e) => {
+                self.buf.push('&');
+                self.buf.push_str(e.0);
+                // Markdown doesn't support legacy entities without semicolon
+                if !e.0.ends_with(';') {
+                    self.buf.push(';');
+                }
+            }
+            Segment::CharRef(c) => {
+                self.buf.push_str(&format!("&#{};", c as u32));
+            }
+            Segment::Braces(b) => {
+                if b.macros.iter().any(needs_html) {
+                    self.html(Segment::Braces(b));
+                } else {
+                    self.segments(b.segments);
+                }
+            }
+            Segment::Format(f) => self.format(f),
+            Segment::Code(c) => self.code(c),
+            Segment::Link(l) => self.link(l),
+            Segment::Image(i) => self.image(i),
+            Segment::Math(_) | Segment::InlineHtml(_) => self.html(segment),
+        }
+    }
+
+    fn html(&mut self, segment: Segment<'a>) {
+        let html = to_html(segment, self.state);
+        if self.context == Context::Paragraph {
+            self.buf.push_str(&html);
+        } else {
+            self.buf.push_str(&html.replace('\n', " "));
+        }
+    }
+
+    fn line_break(&mut self) {
+        // trailing spaces would be a hard line break
+        let len = self.buf.trim_end_matches(' ').len();
+        self.buf.truncate(len);
+
+        if self.context == Context::Paragraph {
+            self.buf.push('\n');
+        } else {
+            self.buf.push(' ');
+        }
+    }
+
+    fn is_at_line_start(&self) -> bool {
+        self.buf.trim_end_matches([' ', '\t']).ends_with('\n') || self.buf.trim().is_empty()
+    }
+
+    fn text(&mut self, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.line_break();
+            }
+            let mut line = line;
+            if self.is_at_line_start() {
+                line = line.trim_start_matches([' ', '\t']);
+                line = self.escape_line_start(line);
+            }
+            self.escape(line);
+        }
+    }
+
+    /// Returns whether the current line only contains digits, so a `.` or `)`
+    /// would start an ordered list
+    fn is_after_line_number(&self) -> bool {
+        let line = self.buf.rsplit('\n').next().unwrap_or_default().trim_start();
+        !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    /// Escapes characters that would start a block at the start of a line
+    fn escape_line_start<'t>(&mut self, line: &'t str) -> &'t str {
+        if let Some(c) = line.chars().next().filter(|c| matches!(c, '#' | '>' | '-' | '+' | '=')) {
+            self.buf.push('\\');
+            self.buf.push(c);
+            return &line[1..];
+        }
+
+        let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+        if digits > 0 && line[digits..].starts_with(['.', ')']) {
+            self.buf.push_str(&line[..digits]);
+            self.buf.push('\\');
+            return &line[digits..];
+        }
+        line
+    }
+
+    fn escape(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '&' | '~' => self.buf.push('\\'),
+                '|' if self.context == Context::Table => self.buf.push('\\'),
+                '#' if self.context == Context::Heading => self.buf.push('\\'),
+                '.' | ')' if self.is_after_line_number() => self.buf.push('\\'),
+                _ => {}
+            }
+            self.buf.push(c);
+        }
+    }
+
+    fn format(&mut self, f: InlineFormat<'a>) {
+        let (delim, tag) = match f.formatting {
+            Formatting::Bold => ("**", "strong"),
+            Formatting::Italic => ("*", "em"),
+            Formatting::StrikeThrough => ("~~", "s"),
+            Formatting::Superscript => ("", "sup"),
+            Formatting::Subscript => ("", "sub"),
+        };
+
+        let start = self.buf.len();
+        self.segments(f.segments);
+        let content = &self.buf[start..];
+
+        // delimiters only work reliably if they are adjacent to the content,
+        // and the content doesn't start or end with punctuation
+        let is_flanking = |c: Option<char>| matches!(c, Some(c) if !c.is_whitespace() && !c.is_ascii_punctuation());
+        if !delim.is_empty()
+            && is_flanking(content.chars().next())
+            && is_flanking(content.chars().last())
+        {
+            self.buf.insert_str(start, delim);
+            self.buf.push_str(delim);
+        } else {
+            self.buf.insert_str(start, &format!("<{}>", tag));
+            self.buf.push_str(&format!("</{}>", tag));
+        }
+    }
+
+    fn code(&mut self, c: Code<'a>) {
+        let mut text = String::new();
+        for segment in &c.segments {
+            match segment {
+                Segment::Text(t) | Segment::EscapedText(t) => text.push_str(t),
+                Segment::Text2(t) => text.push_str(t),
+                Segment::LineBreak => text.push(' '),
+                _ => return self.html(Segment::Code(c)),
+            }
+        }
+
+        let needs_html = c.macros.iter().any(needs_html)
+            || text.is_empty()
+            || text.trim().is_empty()
+            || (self.context == Context::Table && text.contains('|'));
+        if needs_html {
+            return self.html(Segment::Code(c));
+        }
+
+        let fence = "`".repeat(longest_run(&text, '`') + 1);
+        let padding = if text.starts_with('`') || text.ends_with('`') {
+            " "
+        } else if text.starts_with(' ') && text.ends_with(' ') {
+            // a single space at both ends would be stripped
+            " "
+        } else {
+            ""
+        };
+        self.buf.push_str(&fence);
+        self.buf.push_str(padding);
+        self.buf.push_str(&text);
+        self.buf.push_str(padding);
+        self.buf.push_str(&fence);
+    }
+
+    fn link(&mut self, l: Link<'a>) {
+        // Markdown doesn't allow links within links
+        if l.footnote.is_some() || l.macros.iter().any(needs_html) || contains_link(&l.text) {
+            return self.html(Segment::Link(l));
+        }
+        // an unresolved reference is rendered as text
+        let href = match l.href {
+            Some(href) => href,
+            None => return self.segments(l.text),
+        };
+
+        // `![` would start an image
+        if self.buf.ends_with('!') && !self.buf.ends_with("\\!") {
+            self.buf.insert(self.buf.len() - 1, '\\');
+        }
+        self.buf.push('[');
+        self.segments(l.text);
+        self.buf.push(']');
+        self.destination(&href, l.title.as_deref());
+    }
+
+    fn image(&mut self, i: Image<'a>) {
+        if i.macros.iter().any(needs_html) {
+            return self.html(Segment::Image(i));
+        }
+        let href = match i.href {
+            Some(href) => href,
+            None => return self.segments(i.alt),
+        };
+
+        self.buf.push_str("![");
+        self.segments(i.alt);
+        self.buf.push(']');
+        self.destination(&href, i.title.as_deref());
+    }
+
+    fn destination(&mut self, href: &str, title: Option<&str>) {
+        self.buf.push('(');
+        let needs_brackets =
+            href.is_empty() || href.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')'));
+        if needs_brackets {
+            self.buf.push('<');
+        }
+        for c in href.chars() {
+            match c {
+                '\\' | '<' | '>' | '&' => self.buf.push('\\'),
+                '|' if self.context == Context::Table => self.buf.push('\\'),
+                _ => {}
+            }
+            if c == '\n' {
+                self.buf.push_str("%0A");
+            } else {
+                self.buf.push(c);
+            }
+        }
+        if needs_brackets {
+            self.buf.push('>');
+        }
+
+        if let Some(title) = title {
+            self.buf.push_str(" \"");
+            for c in title.chars() {
+                match c {
+                    '\\' | '"' | '&' => self.buf.push('\\'),
+                    '|' if self.context == Context::Table => self.buf.push('\\'),
+                    _ => {}
+                }
+                self.buf.push(c);
+            }
+            self.buf.push('"');
+        }
+        self.buf.push(')');
+    }
+}
+
+fn contains_link(segments: &[Segment<'_>]) -> bool {
+    segments.iter().any(|s| match s {
+        Segment::Link(_) => true,
+        Segment::Braces(b) => contains_link(&b.segments),
+        Segment::Format(f) => contains_link(&f.segments),
+        _ => false,
+    })
+}
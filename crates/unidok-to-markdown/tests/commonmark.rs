@@ -0,0 +1,201 @@
+//! Round-trip tests: Each example of the CommonMark spec is parsed as Unidok
+//! and converted to HTML and to Markdown. The Markdown is then rendered with
+//! pulldown-cmark, which should produce the same HTML.
+
+use std::panic;
+
+use pulldown_cmark::{html, Options, Parser};
+use unidok_parser::Input;
+use unidok_repr::config::Config;
+
+/// The minimum percentage of examples that must survive the round trip
+const MIN_PASS_RATE: f64 = 95.0;
+
+#[derive(Debug)]
+struct Example {
+    markdown: String,
+    example: u64,
+}
+
+fn load_examples() -> Vec<Example> {
+    let json = include_str!("../../../tests/commonmark.json");
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| Example {
+            markdown: e["markdown"].as_str().unwrap().to_string(),
+            example: e["example"].as_u64().unwrap(),
+        })
+        .collect()
+}
+
+/// Returns the HTML and the Markdown output for a Unidok document
+fn convert(text: &str) -> (String, String) {
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, Config::default());
+    let html = unidok_to_html::to_string(&unidok_to_html::convert(doc));
+
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, Config::default());
+    let markdown = unidok_to_markdown::convert(doc);
+    (html, markdown)
+}
+
+fn render_markdown(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut buf = String::new();
+    html::push_html(&mut buf, Parser::new_ext(markdown, options));
+    buf
+}
+
+/// Removes differences between the HTML output of Unidok and pulldown-cmark
+/// that don't affect how the HTML is displayed
+fn normalize(html: &str) -> String {
+    let mut html = html
+        .replace(" class=\"loose\"", "")
+        .replace("<thead>", "")
+        .replace("</thead>", "")
+        .replace("<tbody>", "")
+        .replace("</tbody>", "")
+        .replace("<del>", "<s>")
+        .replace("</del>", "</s>")
+        .replace("style=\"text-align: ", "align=\"")
+        .replace("class=\"language-", "data-language=\"")
+        .replace(" />", ">")
+        .replace("/>", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", "\u{a0}");
+    html = decode_urls(&html);
+
+    // heading ids
+    while let Some(start) = html.find(" id=\"") {
+        let end = match html[start + 5..].find('"') {
+            Some(i) => start + 5 + i + 1,
+            None => html.len(),
+        };
+        html.replace_range(start..end, "");
+    }
+
+    // whitespace around block-level tags and line breaks
+    let mut result = String::with_capacity(html.len());
+    for part in html.split_whitespace() {
+        let is_block_tag = BLOCK_TAGS.iter().any(|tag| part.starts_with(tag));
+        if !result.is_empty() && !result.ends_with("<br>") && !is_block_tag {
+            result.push(' ');
+        }
+        result.push_str(part);
+    }
+
+    // a tight list item containing a paragraph and a block
+    for tag in BLOCK_TAGS {
+        result = result.replace(&format!("<br>{}", tag), tag);
+    }
+    result
+}
+
+const BLOCK_TAGS: &[&str] = &[
+    "<ul",
+    "</ul",
+    "<ol",
+    "</ol",
+    "<li",
+    "</li",
+    "<p>",
+    "</p>",
+    "<pre",
+    "<blockquote",
+    "</blockquote",
+    "<h",
+    "</h",
+    "<table",
+    "<tr",
+    "</tr",
+    "<hr",
+];
+
+/// pulldown-cmark percent-encodes some characters in URLs
+fn decode_urls(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("href=\"").or_else(|| rest.find("src=\"")) {
+        let start = start + rest[start..].find('"').unwrap() + 1;
+        let len = rest[start..].find('"').unwrap_or(rest.len() - start);
+        result.push_str(&rest[..start]);
+
+        let url = rest[start..start + len]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+        let mut bytes = Vec::with_capacity(url.len());
+        let mut i = 0;
+        while i < url.len() {
+            let hex = url.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+            match hex {
+                Some(b) if url.as_bytes()[i] == b'%' => {
+                    bytes.push(b);
+                    i += 3;
+                }
+                _ => {
+                    bytes.push(url.as_bytes()[i]);
+                    i += 1;
+                }
+            }
+        }
+        result.push_str(&String::from_utf8_lossy(&bytes));
+        rest = &rest[start + len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[test]
+fn commonmark_round_trip() {
+    let examples = load_examples();
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut passed = 0;
+    let mut failed = Vec::new();
+    let mut panicked = Vec::new();
+
+    for example in &examples {
+        let (html, markdown) = match panic::catch_unwind(|| convert(&example.markdown)) {
+            Ok(result) => result,
+            Err(_) => {
+                panicked.push(example.example);
+                continue;
+            }
+        };
+
+        let expected = normalize(&html);
+        let actual = normalize(&render_markdown(&markdown));
+        if expected == actual {
+            passed += 1;
+        } else {
+            failed.push((example, markdown, expected, actual));
+        }
+    }
+    panic::set_hook(default_hook);
+
+    assert!(panicked.is_empty(), "the conversion panicked in examples {:?}", panicked);
+
+    let rate = passed as f64 * 100.0 / examples.len() as f64;
+    eprintln!("{} of {} examples passed ({:.1}%)", passed, examples.len(), rate);
+
+    if rate < MIN_PASS_RATE {
+        for (example, markdown, expected, actual) in &failed {
+            eprintln!("Example {}: {:?}", example.example, example.markdown);
+            eprintln!("   markdown: {:?}", markdown);
+            eprintln!("   expected: {}", expected);
+            eprintln!("     actual: {}\n", actual);
+        }
+        panic!("pass rate {:.1}% is below {}%", rate, MIN_PASS_RATE);
+    }
+}
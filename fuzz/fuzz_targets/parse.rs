@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use unidok_parser::{parse, Input};
+use unidok_repr::config::Config;
+
+// Parsing and converting any input must not panic or overflow the stack
+fuzz_target!(|text: &str| {
+    let mut input = Input::new(text);
+    let doc = parse(&mut input, Config::default());
+    let nodes = unidok_to_html::convert(doc);
+    unidok_to_html::to_string(&nodes);
+});
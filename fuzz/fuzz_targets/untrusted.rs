@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use unidok_parser::{parse, Input};
+use unidok_repr::config::{Config, Limits};
+use unidok_repr::sanitizer::Sanitizer;
+
+// With the limits for untrusted input, parsing must finish quickly even for
+// pathological input. Run it with `-timeout=5`, so slow inputs are reported.
+fuzz_target!(|text: &str| {
+    let config = Config {
+        limits: Limits::untrusted(),
+        sanitizer: Some(Sanitizer::default()),
+        ..Config::default()
+    };
+    let mut input = Input::new(text);
+    let doc = parse(&mut input, config);
+    let nodes = unidok_to_html::convert(doc);
+    unidok_to_html::to_string(&nodes);
+});
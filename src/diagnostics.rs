@@ -0,0 +1,96 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use unidok_parser::Input;
+use unidok_repr::diagnostics::Diagnostic;
+
+/// Formats a diagnostic like rustc does, with the offending line of the
+/// source text:
+///
+/// ```text
+/// error[unknown-macro]: unknown macro `@FOO`
+///  --> doc.ud:3:2
+///   |
+/// 3 | @FOO
+///   |  ^^^
+/// ```
+///
+/// Diagnostics in a file included with `@INCLUDE` are reported against that
+/// file.
+pub fn format_diagnostic(diagnostic: &Diagnostic, input: &Input, file_name: &str) -> String {
+    let (mut start, mut end) = (diagnostic.span.start as usize, diagnostic.span.end as usize);
+    let mut text = &input.text[..];
+    let mut file_name = file_name.to_string();
+
+    let span = diagnostic.span;
+    if let Some(file) =
+        input.includes.iter().find(|f| f.span.start <= span.start && span.start <= f.span.end)
+    {
+        text = &text[file.span.start as usize..file.span.end as usize];
+        start -= file.span.start as usize;
+        end = end.saturating_sub(file.span.start as usize);
+        file_name = display_path(&file.path);
+    }
+
+    let start = start.min(text.len());
+    let end = end.clamp(start, text.len());
+
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+    let line = text[line_start..line_end].trim_end_matches('\r');
+
+    let line_number = text[..start].matches('\n').count() + 1;
+    let column = text[line_start..start].chars().count() + 1;
+    let carets = text[start..end.min(line_end)].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+
+    let mut s = String::new();
+    let _ = writeln!(
+        s,
+        "{}[{}]: {}",
+        diagnostic.severity.as_str(),
+        diagnostic.code.as_str(),
+        diagnostic.message
+    );
+    let _ = writeln!(s, "{}--> {}:{}:{}", gutter, file_name, line_number, column);
+    let _ = writeln!(s, "{} |", gutter);
+    let _ = writeln!(s, "{} | {}", line_number, line);
+    let _ = writeln!(s, "{} | {}{}", gutter, " ".repeat(column - 1), "^".repeat(carets));
+    s
+}
+
+pub fn print_diagnostics(diagnostics: &[Diagnostic], input: &Input, file_name: &str) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", format_diagnostic(diagnostic, input, file_name));
+    }
+}
+
+/// Displays the path relative to the current directory, if possible
+fn display_path(path: &Path) -> String {
+    let cwd = std::env::current_dir().and_then(|d| d.canonicalize()).ok();
+    match cwd.as_deref().and_then(|cwd| path.strip_prefix(cwd).ok()) {
+        Some(relative) => relative.display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+#[test]
+fn test_included_file() {
+    use unidok_repr::diagnostics::{DiagnosticCode, IncludedFile};
+    use unidok_repr::Span;
+
+    let mut input = Input::new("a\nb\n@INCLUDE(b.ud)\n# B\n\n@FOO");
+    input.includes.push(IncludedFile { path: "/b.ud".into(), span: Span { start: 19, end: 28 } });
+
+    let in_host =
+        Diagnostic::error(DiagnosticCode::IncludeFailed, "x", Span { start: 13, end: 17 });
+    let in_include =
+        Diagnostic::error(DiagnosticCode::UnknownMacro, "x", Span { start: 25, end: 28 });
+
+    let host = format_diagnostic(&in_host, &input, "a.ud");
+    assert!(host.contains("--> a.ud:3:10\n"), "{}", host);
+    let include = format_diagnostic(&in_include, &input, "a.ud");
+    assert!(include.contains("--> /b.ud:3:2\n"), "{}", include);
+    assert!(include.contains("3 | @FOO\n"), "{}", include);
+}
@@ -1,35 +1,47 @@
-use std::fs;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
+use std::{fs, mem};
 
 use anyhow::Context;
 use ignore::Walk;
-use rayon::iter::{ParallelBridge, ParallelIterator};
-use unidok_repr::config::{Config, UnsafeConfig};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use unidok_repr::config::Config;
+use unidok_to_html::{PageData, Template};
+
+pub struct Options {
+    pub config: Config,
+    /// If this is set, a complete HTML page is generated with this template
+    pub template: Option<Template>,
+}
+
+/// The files included with `@INCLUDE` by each converted file
+pub type Includes = BTreeMap<PathBuf, Vec<PathBuf>>;
 
+/// Converts a file and returns the files it includes with `@INCLUDE`
 pub fn convert_file(
     input_path: &Path,
     output_path: &Path,
     verbosity: u8,
-    is_unsafe: bool,
-) -> anyhow::Result<()> {
+    options: &Options,
+) -> anyhow::Result<Vec<PathBuf>> {
     let content = fs::read_to_string(input_path)
         .with_context(|| format!("File `{}` couldn't be read", input_path.display()))?;
 
     let start = Instant::now();
 
     let mut input = unidok_parser::Input::new(&content);
-    let mut config = Config::default();
-    if is_unsafe {
-        let cwd = std::env::current_dir().context("Could not get current directory path")?;
-        config.unsafe_config = Some(UnsafeConfig { root: Some(cwd) });
-    }
-    let res = unidok_parser::parse(&mut input, config);
+    let mut res = unidok_parser::parse(&mut input, options.config.clone());
+    let diagnostics = mem::take(&mut res.diagnostics);
 
     let time1 = start.elapsed();
 
+    let page = options.template.as_ref().map(|template| (template, PageData::new(&mut res)));
     let nodes = unidok_to_html::convert(res);
-    let html = unidok_to_html::to_string(&nodes);
+    let html = match &page {
+        Some((template, page)) => template.render(&nodes, page),
+        None => unidok_to_html::to_string(&nodes),
+    };
 
     let time2 = start.elapsed();
 
@@ -58,51 +70,73 @@ pub fn convert_file(
         }
     }
 
-    Ok(())
+    let file_name = input_path.display().to_string();
+    crate::diagnostics::print_diagnostics(&diagnostics, &input, &file_name);
+
+    Ok(input.includes.into_iter().map(|f| f.path).collect())
 }
 
+/// Converts all Unidok files in a directory and returns the files they include
 pub fn convert_dir(
     input: &Path,
     output: &Path,
     verbosity: u8,
-    is_unsafe: bool,
-) -> anyhow::Result<()> {
-    Walk::new(input).par_bridge().try_for_each(|entry| {
+    options: &Options,
+) -> anyhow::Result<Includes> {
+    unidok_files(input)?
+        .into_par_iter()
+        .filter_map(|path| {
+            let output = output_path(input, output, &path)?;
+            Some(convert_file(&path, &output, verbosity, options).map(|includes| (path, includes)))
+        })
+        .collect()
+}
+
+/// Returns the Unidok files in a directory. Files excluded by `.gitignore` and
+/// similar files are skipped.
+pub fn unidok_files(input: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in Walk::new(input) {
         let entry =
             entry.with_context(|| format!("An entry in `{}` couldn't be read", input.display()))?;
         let path = entry.into_path();
 
         if is_unidok_file(&path)? {
-            if let Ok(rel_path) = path.strip_prefix(input) {
-                let output = output.join(rel_path).with_extension("html");
-                convert_file(&path, &output, verbosity, is_unsafe)?;
-            }
+            files.push(path);
         }
+    }
+    Ok(files)
+}
 
-        Ok(())
-    })
+/// Returns the path of the HTML file that is generated for the given file.
+/// `input` and `output` are the paths passed to the CLI, which can be files or
+/// directories.
+pub fn output_path(input: &Path, output: &Path, path: &Path) -> Option<PathBuf> {
+    if path == input {
+        Some(output.to_path_buf())
+    } else {
+        let rel_path = path.strip_prefix(input).ok()?;
+        Some(output.join(rel_path).with_extension("html"))
+    }
 }
 
-fn is_unidok_file(path: &Path) -> anyhow::Result<bool> {
-    let path2;
+pub fn has_unidok_extension(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some(e) if e.eq_ignore_ascii_case("ud"))
+}
 
+pub fn is_unidok_file(path: &Path) -> anyhow::Result<bool> {
     let meta = path.metadata().with_context(|| {
         format!("Metadata for input file `{}` couldn't be retrieved", path.display())
     })?;
 
-    let ext = if meta.is_file() {
-        path.extension()
+    if meta.is_file() {
+        Ok(has_unidok_extension(path))
     } else if path.is_dir() {
-        return Ok(false);
+        Ok(false)
     } else {
-        path2 = path
+        let path2 = path
             .canonicalize()
             .with_context(|| format!("Path `{}` couldn't be canonicalized", path.display()))?;
-        if path2.is_file() {
-            path.extension()
-        } else {
-            return Ok(false);
-        }
-    };
-    Ok(matches!(ext.and_then(|e| e.to_str()), Some(e) if e.eq_ignore_ascii_case("ud")))
+        Ok(path2.is_file() && has_unidok_extension(path))
+    }
 }
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use ignore::Walk;
+
+use crate::file_conversions::is_unidok_file;
+
+/// Formats the given files and the Unidok files in the given directories. In
+/// check mode, the files aren't modified; instead, an error is returned if any
+/// of them isn't formatted.
+pub fn format_paths(paths: &[&Path], check: bool, verbosity: u8) -> anyhow::Result<()> {
+    let mut unformatted = Vec::new();
+
+    for &input in paths {
+        for entry in Walk::new(input) {
+            let entry = entry
+                .with_context(|| format!("An entry in `{}` couldn't be read", input.display()))?;
+
+            // files that are specified explicitly don't need the `.ud` extension
+            let is_input_file = entry.depth() == 0 && input.is_file();
+            let path = entry.into_path();
+            if (is_input_file || is_unidok_file(&path)?) && !format_file(&path, check, verbosity)? {
+                unformatted.push(path);
+            }
+        }
+    }
+
+    if check && !unformatted.is_empty() {
+        for path in &unformatted {
+            eprintln!("Not formatted: {}", path.display());
+        }
+        bail!("{} file(s) aren't formatted", unformatted.len());
+    }
+    Ok(())
+}
+
+/// Formats a file. Returns whether the file was already formatted.
+fn format_file(path: &Path, check: bool, verbosity: u8) -> anyhow::Result<bool> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("File `{}` couldn't be read", path.display()))?;
+
+    let formatted = unidok_fmt::format(&content);
+    if formatted == content {
+        return Ok(true);
+    }
+
+    if !check {
+        fs::write(path, formatted)
+            .with_context(|| format!("File `{}` couldn't be written", path.display()))?;
+        if verbosity > 0 {
+            eprintln!("Formatted {}", path.display());
+        }
+    }
+    Ok(false)
+}
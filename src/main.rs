@@ -1,14 +1,19 @@
-use std::fs;
 use std::path::Path;
 use std::time::Instant;
+use std::{fs, mem};
 
 use anyhow::{bail, Context};
-use clap::{App, AppSettings, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use unidok_repr::config::{Config, UnsafeConfig};
+use unidok_repr::sanitizer::Sanitizer;
+use unidok_to_html::{PageData, Template};
 
-use crate::file_conversions::{convert_dir, convert_file};
+use crate::file_conversions::{convert_dir, convert_file, Options};
 
+mod diagnostics;
 mod file_conversions;
+mod format;
+mod watch;
 
 fn app() -> clap::App<'static, 'static> {
     App::new("unidok")
@@ -42,9 +47,49 @@ fn app() -> clap::App<'static, 'static> {
                         .value_name("PATH")
                         .help("The file or directory where the HTML output should be saved")
                         .required(true),
+                    Arg::with_name("watch")
+                        .short("w")
+                        .long("watch")
+                        .takes_value(false)
+                        .help("Watch the input for changes and convert changed files"),
                     Arg::with_name("unsafe")
+                        .long("unsafe")
                         .takes_value(false)
                         .help("Enable unsafe mode, which allows things like file system access"),
+                    Arg::with_name("no-sanitize")
+                        .long("no-sanitize")
+                        .takes_value(false)
+                        .help("Don't remove HTML elements, attributes and URLs that aren't allowed in safe mode"),
+                    Arg::with_name("allow-elements")
+                        .long("allow-elements")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML elements to allow in safe mode, e.g. `svg,x-*`"),
+                    Arg::with_name("allow-attributes")
+                        .long("allow-attributes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML attributes to allow in safe mode, e.g. `style,aria-*`"),
+                    Arg::with_name("allow-url-schemes")
+                        .long("allow-url-schemes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated URL schemes to allow in safe mode, e.g. `ftp,tel`"),
+                    Arg::with_name("highlight")
+                        .long("highlight")
+                        .takes_value(false)
+                        .help("Highlight code blocks. Requires the `syntect` feature"),
+                    Arg::with_name("full-page")
+                        .long("full-page")
+                        .takes_value(false)
+                        .help("Output a complete HTML page using the default template"),
+                    Arg::with_name("template")
+                        .long("template")
+                        .value_name("PATH")
+                        .help("Output a complete HTML page using the specified template"),
                 ]),
         )
         .subcommand(
@@ -58,13 +103,182 @@ fn app() -> clap::App<'static, 'static> {
                         .help("The Unidok text to convert")
                         .required(true),
                     Arg::with_name("unsafe")
+                        .long("unsafe")
                         .takes_value(false)
                         .help("Enable unsafe mode, which allows things like file system access"),
+                    Arg::with_name("no-sanitize")
+                        .long("no-sanitize")
+                        .takes_value(false)
+                        .help("Don't remove HTML elements, attributes and URLs that aren't allowed in safe mode"),
+                    Arg::with_name("allow-elements")
+                        .long("allow-elements")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML elements to allow in safe mode, e.g. `svg,x-*`"),
+                    Arg::with_name("allow-attributes")
+                        .long("allow-attributes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML attributes to allow in safe mode, e.g. `style,aria-*`"),
+                    Arg::with_name("allow-url-schemes")
+                        .long("allow-url-schemes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated URL schemes to allow in safe mode, e.g. `ftp,tel`"),
+                    Arg::with_name("highlight")
+                        .long("highlight")
+                        .takes_value(false)
+                        .help("Highlight code blocks. Requires the `syntect` feature"),
+                    Arg::with_name("full-page")
+                        .long("full-page")
+                        .takes_value(false)
+                        .help("Output a complete HTML page using the default template"),
+                    Arg::with_name("template")
+                        .long("template")
+                        .value_name("PATH")
+                        .help("Output a complete HTML page using the specified template"),
+                ]),
+        )
+        .subcommand(
+            SubCommand::with_name("to-markdown")
+                .visible_alias("m")
+                .aliases(&["to_markdown", "tomarkdown", "to-md"])
+                .about("Convert a file to Markdown")
+                .args(&[
+                    Arg::with_name("in")
+                        .short("i")
+                        .long("in")
+                        .value_name("PATH")
+                        .help("The file to convert")
+                        .required(true),
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("PATH")
+                        .help("The file where the Markdown output should be saved. If omitted, it is printed"),
+                    Arg::with_name("unsafe")
+                        .long("unsafe")
+                        .takes_value(false)
+                        .help("Enable unsafe mode, which allows things like file system access"),
+                    Arg::with_name("no-sanitize")
+                        .long("no-sanitize")
+                        .takes_value(false)
+                        .help("Don't remove HTML elements, attributes and URLs that aren't allowed in safe mode"),
+                    Arg::with_name("allow-elements")
+                        .long("allow-elements")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML elements to allow in safe mode, e.g. `svg,x-*`"),
+                    Arg::with_name("allow-attributes")
+                        .long("allow-attributes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated HTML attributes to allow in safe mode, e.g. `style,aria-*`"),
+                    Arg::with_name("allow-url-schemes")
+                        .long("allow-url-schemes")
+                        .value_name("LIST")
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .help("Comma-separated URL schemes to allow in safe mode, e.g. `ftp,tel`"),
+                ]),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .visible_alias("f")
+                .aliases(&["format", "fomrat", "ftm"])
+                .about("Format Unidok files")
+                .args(&[
+                    Arg::with_name("paths")
+                        .value_name("PATH")
+                        .help("The files or directories to format")
+                        .multiple(true)
+                        .required(true),
+                    Arg::with_name("check")
+                        .long("check")
+                        .takes_value(false)
+                        .help("Don't modify the files; fail if any of them isn't formatted"),
                 ]),
         )
+        .subcommand(
+            SubCommand::with_name("meta").about("Print the metadata of a file as JSON").args(&[
+                Arg::with_name("in")
+                    .value_name("PATH")
+                    .help("The file whose metadata should be printed")
+                    .required(true),
+            ]),
+        )
+        .subcommand(
+            SubCommand::with_name("highlight-css")
+                .about("Print the CSS for a syntax highlighting theme")
+                .args(&[Arg::with_name("theme")
+                    .value_name("THEME")
+                    .help("The name of the theme")
+                    .default_value("InspiredGitHub")]),
+        )
         .setting(AppSettings::SubcommandRequiredElseHelp)
 }
 
+fn options_from_args(args: &ArgMatches) -> anyhow::Result<Options> {
+    let config = config_from_args(args)?;
+
+    let template = if let Some(path) = args.value_of_os("template") {
+        let path = Path::new(path);
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Template `{}` couldn't be read", path.display()))?;
+        let template = Template::parse(&text)
+            .with_context(|| format!("Template `{}` is invalid", path.display()))?;
+        Some(template)
+    } else if args.is_present("full-page") {
+        Some(Template::default())
+    } else {
+        None
+    };
+
+    Ok(Options { config, template })
+}
+
+fn config_from_args(args: &ArgMatches) -> anyhow::Result<Config> {
+    let mut config = Config::default();
+    if args.is_present("unsafe") {
+        let cwd = std::env::current_dir().context("Could not get current directory path")?;
+        config.unsafe_config = Some(UnsafeConfig { root: Some(cwd) });
+    }
+    if !args.is_present("no-sanitize") {
+        let mut sanitizer = Sanitizer::default();
+        let values = |name| args.values_of(name).into_iter().flatten().map(str::to_string);
+        sanitizer.elements.extend(values("allow-elements"));
+        sanitizer.attributes.extend(values("allow-attributes"));
+        sanitizer.url_schemes.extend(values("allow-url-schemes"));
+        config.sanitizer = Some(sanitizer);
+    }
+    config.highlight = args.is_present("highlight");
+    Ok(config)
+}
+
+#[cfg(feature = "syntect")]
+fn print_highlight_css(theme: &str) -> anyhow::Result<()> {
+    match unidok_to_html::highlight_css(theme) {
+        Some(css) => {
+            println!("{}", css);
+            Ok(())
+        }
+        None => {
+            let themes = unidok_to_html::highlight_themes().join(", ");
+            bail!("Unknown theme `{}`. Available themes: {}", theme, themes)
+        }
+    }
+}
+
+#[cfg(not(feature = "syntect"))]
+fn print_highlight_css(_: &str) -> anyhow::Result<()> {
+    bail!("Syntax highlighting requires the `syntect` feature")
+}
+
 fn main() -> anyhow::Result<()> {
     let start = Instant::now();
 
@@ -79,7 +293,7 @@ fn main() -> anyhow::Result<()> {
     if let Some(args) = args.subcommand_matches("to-html") {
         let input = args.value_of_os("in").context("missing --in")?;
         let output = args.value_of_os("out").context("missing --out")?;
-        let is_unsafe = args.is_present("unsafe");
+        let options = options_from_args(args)?;
 
         let input = Path::new(input);
         let output = Path::new(output);
@@ -94,27 +308,75 @@ fn main() -> anyhow::Result<()> {
 
         let file_type = meta.file_type();
 
-        if file_type.is_file() {
-            convert_file(&input, &output, verbosity, is_unsafe)?;
+        let includes = if file_type.is_file() {
+            let includes = convert_file(&input, output, verbosity, &options)?;
+            std::iter::once((input.clone(), includes)).collect()
         } else if file_type.is_dir() {
-            convert_dir(&input, &output, verbosity, is_unsafe)?;
+            convert_dir(&input, output, verbosity, &options)?
         } else {
             bail!("The specified path `{}` is not a file or directory", input.display());
+        };
+
+        if args.is_present("watch") {
+            watch::watch(&input, output, verbosity, &options, includes)?;
         }
     } else if let Some(args) = args.subcommand_matches("stdio") {
         let input_str = args.value_of("input").context("missing input")?;
-        let is_unsafe = args.is_present("unsafe");
+        let options = options_from_args(args)?;
 
-        let mut input = unidok_parser::Input::new(&input_str);
-        let mut config = Config::default();
-        if is_unsafe {
-            config.unsafe_config = Some(UnsafeConfig { root: None });
-        }
-
-        let res = unidok_parser::parse(&mut input, config);
+        let mut input = unidok_parser::Input::new(input_str);
+        let mut res = unidok_parser::parse(&mut input, options.config);
+        let diagnostics = mem::take(&mut res.diagnostics);
+        let page = options.template.as_ref().map(|template| (template, PageData::new(&mut res)));
         let nodes = unidok_to_html::convert(res);
-        let html = unidok_to_html::to_string(&nodes);
+        let html = match &page {
+            Some((template, page)) => template.render(&nodes, page),
+            None => unidok_to_html::to_string(&nodes),
+        };
         println!("{}", html);
+
+        diagnostics::print_diagnostics(&diagnostics, &input, "<input>");
+    } else if let Some(args) = args.subcommand_matches("to-markdown") {
+        let path = args.value_of_os("in").context("missing --in")?;
+        let path = Path::new(path);
+        let config = config_from_args(args)?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("File `{}` couldn't be read", path.display()))?;
+
+        let mut input = unidok_parser::Input::new(&content);
+        let mut res = unidok_parser::parse(&mut input, config);
+        let diagnostics = mem::take(&mut res.diagnostics);
+        let markdown = unidok_to_markdown::convert(res);
+
+        match args.value_of_os("out") {
+            Some(output) => {
+                let output = Path::new(output);
+                fs::write(output, markdown)
+                    .with_context(|| format!("File `{}` couldn't be written", output.display()))?;
+            }
+            None => print!("{}", markdown),
+        }
+
+        diagnostics::print_diagnostics(&diagnostics, &input, &path.display().to_string());
+    } else if let Some(args) = args.subcommand_matches("fmt") {
+        let paths: Vec<_> =
+            args.values_of_os("paths").context("missing paths")?.map(Path::new).collect();
+        format::format_paths(&paths, args.is_present("check"), verbosity)?;
+    } else if let Some(args) = args.subcommand_matches("meta") {
+        let path = args.value_of_os("in").context("missing input path")?;
+        let path = Path::new(path);
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("File `{}` couldn't be read", path.display()))?;
+
+        let mut input = unidok_parser::Input::new(&content);
+        let res = unidok_parser::parse(&mut input, Config::default());
+        let json = serde_json::to_string_pretty(res.metadata())?;
+        println!("{}", json);
+
+        diagnostics::print_diagnostics(&res.diagnostics, &input, &path.display().to_string());
+    } else if let Some(args) = args.subcommand_matches("highlight-css") {
+        let theme = args.value_of("theme").context("missing theme")?;
+        print_highlight_css(theme)?;
     }
 
     let time = start.elapsed();
@@ -125,3 +387,32 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_sanitizer_args() {
+    let args = app().get_matches_from([
+        "unidok",
+        "stdio",
+        "--allow-elements",
+        "svg,x-*",
+        "--allow-attributes=style",
+        "--allow-elements",
+        "circle",
+        "<svg><circle/></svg>",
+    ]);
+    let config = config_from_args(args.subcommand_matches("stdio").unwrap()).unwrap();
+    let sanitizer = config.sanitizer.unwrap();
+    assert!(sanitizer.allows_element("svg"));
+    assert!(sanitizer.allows_element("circle"));
+    assert!(sanitizer.allows_element("x-foo"));
+    assert!(sanitizer.allows_element("div"));
+    assert!(sanitizer.allows_attribute("style"));
+    assert!(!sanitizer.allows_url("ftp://example.org"));
+
+    let args =
+        app().get_matches_from(["unidok", "stdio", "--no-sanitize", "--allow-elements=svg", "x"]);
+    assert_eq!(
+        config_from_args(args.subcommand_matches("stdio").unwrap()).unwrap().sanitizer,
+        None
+    );
+}
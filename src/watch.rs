@@ -0,0 +1,269 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use ignore::{Walk, WalkBuilder};
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+
+use crate::file_conversions::{
+    convert_file, has_unidok_extension, is_unidok_file, output_path, Includes, Options,
+};
+
+/// Events that occur within this duration are processed together
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Watches the input file or directory and converts files when they change.
+/// Files that include a changed file with `@INCLUDE` are converted as well.
+/// When a file is removed, its HTML output is removed as well. This runs until
+/// the process is killed.
+pub fn watch(
+    input: &Path,
+    output: &Path,
+    verbosity: u8,
+    options: &Options,
+    mut includes: Includes,
+) -> anyhow::Result<()> {
+    // Editors often replace a file when saving it, so the parent directory of
+    // a single file is watched instead of the file itself
+    let (watched, mode) = match input.parent() {
+        Some(parent) if input.is_file() => (parent, RecursiveMode::NonRecursive),
+        _ => (input, RecursiveMode::Recursive),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, tx)?;
+    debouncer
+        .watcher()
+        .watch(watched, mode)
+        .with_context(|| format!("`{}` couldn't be watched", watched.display()))?;
+
+    // Included files can be outside of the watched directory, so their parent
+    // directories are watched as well
+    let mut watched_dirs = BTreeSet::new();
+    let mut watch_includes = |includes: &Includes| {
+        for dir in includes.values().flatten().filter_map(|path| path.parent()) {
+            if !dir.starts_with(watched) && watched_dirs.insert(dir.to_path_buf()) {
+                if let Err(e) = debouncer.watcher().watch(dir, RecursiveMode::NonRecursive) {
+                    eprintln!("Error: `{}` couldn't be watched: {}", dir.display(), e);
+                }
+            }
+        }
+    };
+    watch_includes(&includes);
+
+    let mut sources = Sources::new(input)?;
+
+    eprintln!("Watching `{}` for changes...", input.display());
+
+    for result in rx {
+        match result {
+            Ok(events) => {
+                let paths: BTreeSet<_> = events.into_iter().map(|e| e.path).collect();
+                if let Err(e) = sources.update(input, &paths) {
+                    eprintln!("Error: {:#}", e);
+                }
+                rebuild(&paths, &sources, input, output, verbosity, options, &mut includes);
+                watch_includes(&includes);
+            }
+            Err(e) => eprintln!("Error: {:#}", anyhow::Error::from(e)),
+        }
+    }
+    Ok(())
+}
+
+/// The Unidok files that are converted without `--watch`, and the directories
+/// containing them that aren't excluded by `.gitignore` and similar files. It
+/// is built once and updated from the paths of file system events.
+struct Sources {
+    files: BTreeSet<PathBuf>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl Sources {
+    fn new(input: &Path) -> anyhow::Result<Self> {
+        let mut sources = Sources { files: BTreeSet::new(), dirs: BTreeSet::new() };
+        if input.is_dir() {
+            sources.add_dir(input)?;
+        } else {
+            sources.files.insert(input.to_path_buf());
+        }
+        Ok(sources)
+    }
+
+    fn add_dir(&mut self, dir: &Path) -> anyhow::Result<()> {
+        for entry in Walk::new(dir) {
+            let entry = entry
+                .with_context(|| format!("An entry in `{}` couldn't be read", dir.display()))?;
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
+                self.dirs.insert(entry.into_path());
+            } else if is_unidok_file(entry.path())? {
+                self.files.insert(entry.into_path());
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the sources after the paths were created, modified or removed
+    fn update(&mut self, input: &Path, paths: &BTreeSet<PathBuf>) -> anyhow::Result<()> {
+        if !input.is_dir() {
+            return Ok(());
+        }
+        // changed ignore rules can include or exclude any file
+        let is_ignore_file = |p: &&PathBuf| {
+            matches!(p.file_name().and_then(|n| n.to_str()), Some(".gitignore" | ".ignore"))
+        };
+        if paths.iter().any(|p| is_ignore_file(&p)) {
+            *self = Sources::new(input)?;
+            return Ok(());
+        }
+
+        for path in paths {
+            if self.files.contains(path) && path.is_file() {
+                continue;
+            }
+            self.files.retain(|p| !p.starts_with(path));
+            self.dirs.retain(|p| !p.starts_with(path));
+            if !path.exists() || !self.is_included(path) {
+                continue;
+            }
+            if path.is_dir() {
+                self.add_dir(path)?;
+            } else if is_unidok_file(path)? {
+                self.files.insert(path.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the path is in a known directory, and isn't excluded by
+    /// the ignore rules that apply to that directory
+    fn is_included(&self, path: &Path) -> bool {
+        let parent = match path.parent() {
+            Some(parent) if self.dirs.contains(parent) => parent,
+            _ => return false,
+        };
+        WalkBuilder::new(parent)
+            .max_depth(Some(1))
+            .build()
+            .filter_map(Result::ok)
+            .any(|entry| entry.path() == path)
+    }
+}
+
+/// Converts the changed files and the files that include them, and removes
+/// the output of deleted files. Only the files that would be converted
+/// without `--watch` are considered, so files excluded by `.gitignore` are
+/// skipped. Errors are printed, so a single invalid file doesn't stop the
+/// watcher.
+fn rebuild(
+    paths: &BTreeSet<PathBuf>,
+    sources: &Sources,
+    input: &Path,
+    output: &Path,
+    verbosity: u8,
+    options: &Options,
+    includes: &mut Includes,
+) -> usize {
+    let start = Instant::now();
+    let mut count = 0;
+
+    let is_affected = |source: &&PathBuf| {
+        paths.contains(*source)
+            || includes.get(*source).is_some_and(|inc| inc.iter().any(|p| paths.contains(p)))
+    };
+    let affected: Vec<_> = sources.files.iter().filter(is_affected).cloned().collect();
+
+    for path in affected {
+        let output = match output_path(input, output, &path) {
+            Some(output) => output,
+            None => continue,
+        };
+        match convert_file(&path, &output, verbosity, options) {
+            Ok(included) => {
+                includes.insert(path, included);
+                count += 1;
+            }
+            Err(e) => eprintln!("Error: {:#}", e),
+        }
+    }
+
+    for path in paths.iter().filter(|p| has_unidok_extension(p) && !p.exists()) {
+        includes.remove(path);
+        let output = match output_path(input, output, path) {
+            Some(output) if output.is_file() => output,
+            _ => continue,
+        };
+        if verbosity > 0 {
+            eprintln!();
+            eprintln!("Removed: {}", output.display());
+        }
+        match fs::remove_file(&output) {
+            Ok(()) => count += 1,
+            Err(e) => eprintln!("Error: File `{}` couldn't be removed: {}", output.display(), e),
+        }
+    }
+
+    if verbosity > 0 && count > 0 {
+        eprintln!();
+        eprintln!("Rebuilt {} file(s) in {:.1?}", count, start.elapsed());
+    }
+    count
+}
+
+#[test]
+fn test_rebuild() {
+    use unidok_repr::config::{Config, UnsafeConfig};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    let (input, output) = (root.join("in"), root.join("out"));
+    fs::create_dir_all(input.join("dir")).unwrap();
+    fs::create_dir_all(input.join(".git")).unwrap();
+    fs::create_dir_all(output.join("dir")).unwrap();
+
+    fs::write(input.join("dir/a.ud"), "# A").unwrap();
+    fs::write(input.join("b.txt"), "B").unwrap();
+    fs::write(input.join(".gitignore"), "ignored.ud").unwrap();
+    fs::write(input.join("ignored.ud"), "# Ignored").unwrap();
+    fs::write(input.join("main.ud"), "@INCLUDE(in/dir/a.ud)").unwrap();
+    fs::write(output.join("c.html"), "<h1>C</h1>").unwrap();
+
+    let mut sources = Sources::new(&input).unwrap();
+    fs::write(input.join("dir/new.ud"), "New").unwrap();
+
+    let paths = [
+        input.join("dir/a.ud"),
+        input.join("dir/new.ud"),
+        input.join("b.txt"),
+        input.join("c.ud"),
+        input.join("ignored.ud"),
+    ];
+    let paths = paths.iter().cloned().collect();
+    sources.update(&input, &paths).unwrap();
+    assert!(sources.files.contains(&input.join("dir/new.ud")));
+    assert!(!sources.files.contains(&input.join("ignored.ud")));
+
+    let options = Options {
+        config: Config {
+            unsafe_config: Some(UnsafeConfig { root: Some(root.clone()) }),
+            ..Config::default()
+        },
+        template: None,
+    };
+    let mut includes =
+        std::iter::once((input.join("main.ud"), vec![input.join("dir/a.ud")])).collect();
+    let count = rebuild(&paths, &sources, &input, &output, 0, &options, &mut includes);
+
+    assert_eq!(count, 4);
+    assert_eq!(fs::read_to_string(output.join("dir/a.html")).unwrap(), "<h1 id=\"a\">A</h1>\n");
+    assert_eq!(fs::read_to_string(output.join("main.html")).unwrap(), "<h1 id=\"a\">A</h1>\n");
+    assert_eq!(fs::read_to_string(output.join("dir/new.html")).unwrap(), "<p>New</p>\n");
+    assert!(!output.join("b.html").exists());
+    assert!(!output.join("c.html").exists());
+    assert!(!output.join("ignored.html").exists());
+    assert_eq!(includes[&input.join("main.ud")], [input.join("dir/a.ud")]);
+}
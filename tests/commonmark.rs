@@ -0,0 +1,207 @@
+//! Conformance tests: The examples of the CommonMark spec are parsed with the
+//! CommonMark dialect and converted to HTML, which is compared with the HTML
+//! from the spec. The pass rate is reported for each section of the spec.
+//!
+//! Set `VERBOSE=1` to print the examples that fail.
+
+use std::collections::BTreeMap;
+use std::process;
+
+use unidok_parser::Input;
+use unidok_repr::config::{Config, Dialect};
+
+const RED: &str = "\u{001b}[0;31m";
+const YELLOW: &str = "\u{001b}[0;33m";
+const GREEN: &str = "\u{001b}[0;32m";
+const BOLD: &str = "\u{001b}[1m";
+const RESET: &str = "\u{001b}[0m";
+
+/// The number of examples that currently pass. The test fails if fewer pass,
+/// so any regression is caught; raise it when conformance improves.
+const MIN_PASSED: usize = 553;
+
+struct Example {
+    markdown: String,
+    html: String,
+    example: u64,
+    section: String,
+}
+
+fn main() {
+    let verbose = matches!(std::env::var("VERBOSE").as_deref(), Ok("1"));
+
+    let mut sections = BTreeMap::<&str, (usize, usize)>::new();
+    let mut passed = 0;
+    let examples = load_examples();
+
+    for example in &examples {
+        let expected = normalize(&example.html);
+        let actual = normalize(&convert(&example.markdown));
+
+        let (section_passed, section_total) = sections.entry(&example.section).or_default();
+        *section_total += 1;
+        if expected == actual {
+            *section_passed += 1;
+            passed += 1;
+        } else if verbose {
+            eprintln!("{}Example {}{} ({})", BOLD, example.example, RESET, example.section);
+            eprintln!("    markdown: {:?}", example.markdown);
+            eprintln!("    expected: {:?}", expected);
+            eprintln!("      actual: {:?}\n", actual);
+        }
+    }
+
+    for (section, (section_passed, section_total)) in &sections {
+        let rate = *section_passed as f64 * 100.0 / *section_total as f64;
+        let color = match rate {
+            r if r >= 100.0 => GREEN,
+            r if r > 0.0 => YELLOW,
+            _ => RED,
+        };
+        eprintln!(
+            "{}{:>6.1}%{} {:>3}/{:<3} {}",
+            color, rate, RESET, section_passed, section_total, section
+        );
+    }
+
+    let rate = passed as f64 * 100.0 / examples.len() as f64;
+    eprintln!("\n{}{} of {} examples passed ({:.1}%){}", BOLD, passed, examples.len(), rate, RESET);
+
+    if passed < MIN_PASSED {
+        eprintln!("{}Fewer than {} examples passed{}", RED, MIN_PASSED, RESET);
+        process::exit(1);
+    }
+}
+
+fn load_examples() -> Vec<Example> {
+    let json = include_str!("commonmark.json");
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| Example {
+            markdown: e["markdown"].as_str().unwrap().to_string(),
+            html: e["html"].as_str().unwrap().to_string(),
+            example: e["example"].as_u64().unwrap(),
+            section: e["section"].as_str().unwrap().to_string(),
+        })
+        .collect()
+}
+
+fn convert(text: &str) -> String {
+    let config = Config { dialect: Dialect::CommonMark, ..Config::default() };
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, config);
+    unidok_to_html::to_string(&unidok_to_html::convert(doc))
+}
+
+/// Removes differences in the HTML that don't affect how it is displayed,
+/// similar to the normalization of the CommonMark spec tests
+fn normalize(html: &str) -> String {
+    let mut html = html.replace(" />", ">").replace("/>", ">").replace("&quot;", "\"");
+
+    // URLs are percent-encoded in the spec, but browsers do this anyway
+    for attr in ["href=\"", "src=\""] {
+        let mut start = 0;
+        while let Some(i) = html[start..].find(attr) {
+            let value_start = start + i + attr.len();
+            let value_end = html[value_start..].find('"').map_or(html.len(), |i| value_start + i);
+            let decoded = decode_url(&html[value_start..value_end]);
+            html.replace_range(value_start..value_end, &decoded);
+            start = value_start + decoded.len();
+        }
+    }
+
+    // heading ids
+    while let Some(start) = html.find(" id=\"") {
+        let end = match html[start + 5..].find('"') {
+            Some(i) => start + 5 + i + 1,
+            None => html.len(),
+        };
+        html.replace_range(start..end, "");
+    }
+
+    // whitespace around block-level tags, except in `<pre>` elements
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html.as_str();
+    while !rest.is_empty() {
+        let (outside, pre) = match rest.find("<pre") {
+            Some(start) => {
+                let end = rest[start..].find("</pre>").map_or(rest.len(), |i| start + i + 6);
+                (&rest[..start], &rest[start..end])
+            }
+            None => (rest, ""),
+        };
+        rest = &rest[outside.len() + pre.len()..];
+
+        for (i, line) in outside.split('\n').enumerate() {
+            let line = line.trim_matches(|c| c == ' ' || c == '\t');
+            let is_block_tag = |s: &str| BLOCK_TAGS.iter().any(|tag| s.starts_with(tag));
+            if i > 0 && !line.is_empty() && !is_block_tag(line) {
+                let prev_ends_block = result.ends_with('>')
+                    && result.rfind('<').is_some_and(|i| is_block_tag(&result[i..]));
+                if !prev_ends_block {
+                    result.push('\n');
+                }
+            }
+            result.push_str(line);
+        }
+        result.push_str(pre);
+    }
+    result
+}
+
+fn decode_url(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(b) if bytes[i] == b'%' => {
+                decoded.push(b);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+const BLOCK_TAGS: &[&str] = &[
+    "<ul",
+    "</ul",
+    "<ol",
+    "</ol",
+    "<li",
+    "</li",
+    "<p>",
+    "</p>",
+    "<pre",
+    "</pre",
+    "<blockquote",
+    "</blockquote",
+    "<h1",
+    "</h1",
+    "<h2",
+    "</h2",
+    "<h3",
+    "</h3",
+    "<h4",
+    "</h4",
+    "<h5",
+    "</h5",
+    "<h6",
+    "</h6",
+    "<hr",
+    "<table",
+    "</table",
+    "<tr",
+    "</tr",
+    "<div",
+    "</div",
+];
@@ -0,0 +1,107 @@
+//! Property tests: Random documents, assembled from snippets of Unidok syntax,
+//! are parsed and converted to HTML, Markdown and formatted Unidok, which must
+//! never panic.
+
+use std::panic;
+
+use unidok_parser::Input;
+use unidok_repr::config::{Config, Limits};
+use unidok_repr::sanitizer::Sanitizer;
+
+/// The number of random documents that are tested per configuration
+const ITERATIONS: usize = 10_000;
+
+const MAX_SNIPPETS: usize = 40;
+
+#[rustfmt::skip]
+const SNIPPETS: &[&str] = &[
+    "a", "b c", " ", "  ", "\t", "\n", "\n\n", "é", "😀", "[", "]", "(", ")", "{", "}", "<", ">",
+    "#", "# ", "## ", "####### ", "- ", "* ", "+ ", "1. ", "2) ", "> ", "|", "||", "|-", "|:-:|",
+    "`", "```", "~~~", "$", "%", "%{", "$[", "]$", "${", "\\", "!", ":", "::", "'", "\"", "...",
+    "->", "(C)", "-- ", "*", "**", "_", "~", "^", "=", "---", "===", "//", "/*", "*/",
+    "[^", "[^a]:", "[a]: b", "[ ] ", "[x] ", "](c)", "![", "https://a.b", "<https://a>",
+    "www.a.b", "mailto:a@b", "&amp;", "&#", "&#x41;", "&#0;",
+    "<div>", "</div>", "<span>", "</span>", "<svg>", "</svg>", "<math>", "<br/>", "<x-a>",
+    "</x-a>", "<!--", "-->", "<![CDATA[", "]]>", "<!DOCTYPE html>", "<script>", "<a href=\"",
+    "@", "@()", "@(.a)", "@(#b)", "@(", "@TOC", "@NOTOC", "@FOOTNOTES", "@NOTE", "@DETAILS",
+    "@DETAILS(\"s\")", "@CAPTION(\"x\")", "@FIGURE", "@COLS(l r)", "@COLS", "@LOOSE", "@TIGHT",
+    "@BULLET(-)", "@BULLET(a=b)", "@BLANK", "@PASS", "@NOPASS", "@NOTXT", "@MATH_SCRIPT",
+    "@MATH_SCRIPT(head)", "@INCLUDE(x)", "@CONFIG(autolink=bare)", "@CONFIG(heading_anchor=start)",
+    "@CONFIG(block_elements=x-a)", "@META(title=a)", "---\n",
+];
+
+/// A xorshift random number generator, so the tests are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn document(&mut self) -> String {
+        let len = 1 + self.below(MAX_SNIPPETS);
+        (0..len).map(|_| SNIPPETS[self.below(SNIPPETS.len())]).collect()
+    }
+}
+
+fn convert(text: &str, config: &Config) {
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, config.clone());
+    unidok_to_html::to_string(&unidok_to_html::convert(doc));
+
+    let mut input = Input::new(text);
+    let doc = unidok_parser::parse(&mut input, config.clone());
+    unidok_to_markdown::convert(doc);
+
+    unidok_fmt::format(text);
+}
+
+fn assert_no_panic(text: &str, config: &Config) {
+    let result = panic::catch_unwind(|| convert(text, config));
+    if result.is_err() {
+        panic!("converting {:?} panicked", text);
+    }
+}
+
+fn run(seed: u64, config: Config) {
+    let mut rng = Rng(seed);
+    for _ in 0..ITERATIONS {
+        assert_no_panic(&rng.document(), &config);
+    }
+}
+
+#[test]
+fn random_documents() {
+    run(0x2545_F491_4F6C_DD1D, Config::default());
+}
+
+#[test]
+fn random_documents_untrusted() {
+    let config = Config {
+        limits: Limits::untrusted(),
+        sanitizer: Some(Sanitizer::default()),
+        ..Config::default()
+    };
+    run(0x9E37_79B9_7F4A_7C15, config);
+}
+
+#[test]
+fn regressions() {
+    for text in [
+        "## @TOC<v>",
+        "## <b>@TOC{x}</b>",
+        "## *@TOC{x}*",
+        "%{\\&}",
+        "@(.a)\n<!-- x -->",
+        "@(.a){\n<!DOCTYPE html>\n}",
+    ] {
+        assert_no_panic(text, &Config::default());
+    }
+}
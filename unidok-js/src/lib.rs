@@ -2,6 +2,9 @@ mod utils;
 
 use serde::Serialize;
 use unidok_repr::config::Config;
+use unidok_repr::diagnostics::Diagnostic;
+use unidok_repr::metadata::Metadata;
+use unidok_repr::sanitizer::Sanitizer;
 use unidok_repr::SyntaxSpan;
 use wasm_bindgen::prelude::*;
 
@@ -11,25 +14,38 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-/// Returns a `CompileResult`.
+/// Returns a `CompileResult`. HTML elements, attributes and URLs that aren't
+/// allowed in safe mode are removed, unless `sanitize` is `false`.
 #[wasm_bindgen]
-pub fn compile(input_str: &str, retrieve_spans: Option<bool>) -> JsValue {
+pub fn compile(input_str: &str, retrieve_spans: Option<bool>, sanitize: Option<bool>) -> JsValue {
     utils::set_panic_hook();
 
     let retrieve_spans = retrieve_spans.unwrap_or(false);
     let mut config = Config::default();
     config.retrieve_spans = retrieve_spans;
+    if sanitize.unwrap_or(true) {
+        config.sanitizer = Some(Sanitizer::default());
+    }
 
     let mut input = unidok_parser::Input::new(input_str);
     let res = unidok_parser::parse(&mut input, config);
     let contains_math = res.state.contains_math;
+    let diagnostics = res.diagnostics.clone();
+    let metadata = res.metadata().clone();
 
     let spans = if retrieve_spans { Some(res.spans.clone()) } else { None };
 
     let nodes = unidok_to_html::convert(res);
     let text = unidok_to_html::to_string(&nodes);
 
-    serde_wasm_bindgen::to_value(&CompileResult { text, contains_math, spans }).unwrap()
+    serde_wasm_bindgen::to_value(&CompileResult {
+        text,
+        contains_math,
+        spans,
+        diagnostics,
+        metadata,
+    })
+    .unwrap()
 }
 
 #[derive(Serialize)]
@@ -37,6 +53,8 @@ pub struct CompileResult {
     pub text: String,
     pub contains_math: bool,
     pub spans: Option<Vec<SyntaxSpan>>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub metadata: Metadata,
 }
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -45,6 +63,17 @@ declare interface CompileResult {
     text: string;
     contains_math: boolean;
     spans?: SyntaxSpan[];
+    diagnostics: Diagnostic[];
+    metadata: Map<string, MetaValue>;
+}
+
+declare type MetaValue = string | boolean | number | MetaValue[];
+
+declare interface Diagnostic {
+    severity: 'error' | 'warning';
+    code: string;
+    message: string;
+    span: [number, number];
 }
 
 declare type SyntaxSpan = [SyntaxKind, number, number]
@@ -123,5 +152,9 @@ declare enum SyntaxKind {
     CurlyBraces,
 
     Escaped,
+
+    FrontMatter,
+
+    TaskMarker,
 }
 "#;
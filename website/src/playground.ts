@@ -114,7 +114,7 @@ export class Playground {
         let spans
         try {
             if (this.isHtml) {
-                let result = unidok.compile(value)
+                let result = unidok.compile(value, false, false)
                 this.preview.innerText = result.text
                 spans = result.spans
             } else {
@@ -218,7 +218,7 @@ export function convertToHtml(
     dont_wait?: boolean,
     retrieve_spans?: boolean
 ): unidok.SyntaxSpan[] | void {
-    const result = unidok.compile(text, retrieve_spans)
+    const result = unidok.compile(text, retrieve_spans, false)
 
     if (result.contains_math) {
         if (dont_wait) {
@@ -287,5 +287,7 @@ const _formattings = [
     'HtmlComment',
     'HtmlEntity',
     'Macro', 'MacroName', 'MacroArg', 'MacroKey', 'MacroArgString', 'MacroArgList', 'CurlyBraces',
-    'Escaped'
+    'Escaped',
+    'FrontMatter',
+    'TaskMarker',
 ]